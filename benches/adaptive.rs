@@ -0,0 +1,60 @@
+//! Compares `recv_adaptive` against blocking straight away with `recv_streaming`, for a fast and a slow sending side.
+//!
+//! Run with `cargo bench --bench adaptive --features pool`. Set `THREADBEAM_BENCH_ITERATIONS` to change the number of values streamed
+//! in each run. Spinning only pays off with a core to spare for the sending side, so expect `recv_adaptive` to gain nothing on one core.
+
+use std::{
+	hint::black_box,
+	time::{Duration, Instant},
+};
+
+type Recv = fn(&threadbeam::ThreadBeamRx<u64>) -> Option<u64>;
+
+// Streams `values` values through one pooled thread beam, with the sending side busy for `delay` before each
+fn bench(name: &str, values: u32, delay: Duration, recv: Recv) {
+	let pool = threadbeam::ThreadBeamPool::new();
+	let (tx, rx) = pool.channel();
+	let start = Instant::now();
+	let thread = std::thread::spawn(move || {
+		for value in 0..values {
+			let busy = Instant::now();
+			while busy.elapsed() < delay {
+				std::hint::spin_loop();
+			}
+			tx.send_streaming(black_box(value as u64)).unwrap();
+		}
+	});
+	while let Some(value) = recv(&rx) {
+		black_box(value);
+	}
+	thread.join().unwrap();
+	let elapsed = start.elapsed();
+	println!("{name:<32} {:>10.1} ns/value", elapsed.as_nanos() as f64 / values as f64);
+}
+
+fn main() {
+	let values = std::env::var("THREADBEAM_BENCH_ITERATIONS")
+		.ok()
+		.and_then(|iterations| iterations.parse().ok())
+		.unwrap_or(100_000);
+
+	let cores = std::thread::available_parallelism().map_or(1, usize::from);
+	println!("{cores} cores");
+
+	for (regime, delay) in [("fast sender", Duration::from_micros(1)), ("slow sender", Duration::from_micros(200))] {
+		// The slow sender takes a while per value, so it streams fewer of them
+		let values = if delay > Duration::from_micros(10) { values / 100 } else { values };
+		bench(
+			&format!("{regime}, recv_streaming"),
+			values,
+			delay,
+			threadbeam::ThreadBeamRx::recv_streaming,
+		);
+		bench(
+			&format!("{regime}, recv_adaptive"),
+			values,
+			delay,
+			threadbeam::ThreadBeamRx::recv_adaptive,
+		);
+	}
+}
@@ -0,0 +1,59 @@
+//! Measures the operations that can answer from the atomic snapshot of a thread beam's flags without taking its lock.
+//!
+//! Run with `cargo bench --bench fast_path`. Set `THREADBEAM_BENCH_ITERATIONS` to change the number of iterations.
+//!
+//! The fast path only exists in the std backend. Each operation is measured both uncontended and while another thread hammers the same thread beam's lock with `peek`, which always locks.
+
+use std::{
+	hint::black_box,
+	sync::atomic::{AtomicBool, Ordering},
+	time::Instant,
+};
+
+fn bench(name: &str, iterations: u32, rx: &threadbeam::ThreadBeamRx<u64>, op: impl Fn(&threadbeam::ThreadBeamRx<u64>)) {
+	let start = Instant::now();
+	for _ in 0..iterations {
+		op(rx);
+	}
+	let elapsed = start.elapsed();
+	println!("{name:<32} {:>8.1} ns/iter", elapsed.as_nanos() as f64 / iterations as f64);
+}
+
+fn main() {
+	let iterations = std::env::var("THREADBEAM_BENCH_ITERATIONS")
+		.ok()
+		.and_then(|iterations| iterations.parse().ok())
+		.unwrap_or(10_000_000);
+
+	let (tx, rx) = threadbeam::channel::<u64>();
+
+	bench("try_recv (empty)", iterations, &rx, |rx| {
+		let _ = black_box(rx.try_recv());
+	});
+	bench("is_disconnected", iterations, &rx, |rx| {
+		black_box(rx.is_disconnected());
+	});
+	bench("peek (always locks)", iterations, &rx, |rx| {
+		black_box(rx.peek());
+	});
+
+	let stop = AtomicBool::new(false);
+	std::thread::scope(|s| {
+		s.spawn(|| {
+			while !stop.load(Ordering::Relaxed) {
+				black_box(rx.peek());
+			}
+		});
+
+		bench("try_recv (empty, contended)", iterations, &rx, |rx| {
+			let _ = black_box(rx.try_recv());
+		});
+		bench("is_disconnected (contended)", iterations, &rx, |rx| {
+			black_box(rx.is_disconnected());
+		});
+
+		stop.store(true, Ordering::Relaxed);
+	});
+
+	drop(tx);
+}
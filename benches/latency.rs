@@ -0,0 +1,113 @@
+//! Measures end-to-end latency and throughput of beaming a value out of a newly spawned thread, for comparing the backends.
+//!
+//! Run once per backend and compare the output:
+//!
+//! ```text
+//! cargo bench --bench latency
+//! cargo bench --bench latency --features parking_lot
+//! cargo bench --bench latency --features spin
+//! cargo bench --bench latency --features no_std
+//! ```
+//!
+//! Set `THREADBEAM_BENCH_ITERATIONS` to change the number of iterations.
+//!
+//! The `no_std` backend spins while waiting, so it yields to the OS scheduler through [`set_yield_hook`](threadbeam::set_yield_hook) here, as a
+//! hosted user would. Without that, spinning receivers starve the sender on machines with few cores.
+
+use std::{hint::black_box, time::Instant};
+
+const BACKEND: &str = if cfg!(feature = "no_std") {
+	"no_std (spin)"
+} else if cfg!(feature = "spin") {
+	"std (spin)"
+} else if cfg!(feature = "parking_lot") {
+	"parking_lot"
+} else {
+	"std"
+};
+
+fn bench(name: &str, iterations: u32, mut f: impl FnMut()) {
+	let start = Instant::now();
+	for _ in 0..iterations {
+		f();
+	}
+	let elapsed = start.elapsed();
+	println!("{name:<28} {:>10.1} ns/iter", elapsed.as_nanos() as f64 / iterations as f64);
+}
+
+fn main() {
+	let iterations = std::env::var("THREADBEAM_BENCH_ITERATIONS")
+		.ok()
+		.and_then(|iterations| iterations.parse().ok())
+		.unwrap_or(10_000);
+
+	#[cfg(feature = "no_std")]
+	threadbeam::set_yield_hook(std::thread::yield_now);
+
+	let cores = std::thread::available_parallelism().map_or(1, usize::from);
+	println!("backend: {BACKEND}, {cores} cores");
+
+	// The value is already waiting by the time the receiver looks
+	bench("sender runs first", iterations, || {
+		let (tx, rx) = threadbeam::channel();
+		let thread = std::thread::spawn(move || tx.send(black_box(0u64)).unwrap());
+		thread.join().unwrap();
+		black_box(rx.recv());
+	});
+
+	// The receiver is already blocked by the time the value is sent
+	bench("receiver blocks first", iterations, || {
+		let (tx, rx) = threadbeam::channel();
+		let thread = std::thread::spawn(move || {
+			while !tx.has_waiter() {
+				std::thread::yield_now();
+			}
+			tx.send(black_box(0u64)).unwrap();
+		});
+		black_box(rx.recv());
+		thread.join().unwrap();
+	});
+
+	// Whichever gets there first
+	bench("racing", iterations, || {
+		let (tx, rx) = threadbeam::channel();
+		let thread = std::thread::spawn(move || tx.send(black_box(0u64)).unwrap());
+		black_box(rx.recv());
+		thread.join().unwrap();
+	});
+
+	// Polling with a short timeout in a tight loop, as an event loop would, both with the value already there and with nothing to
+	// receive, to keep the clock reads and timed waits on this path in check
+	#[cfg(not(feature = "no_std"))]
+	{
+		let (tx, rx) = threadbeam::channel();
+		bench("recv_timeout, ready", iterations, || {
+			tx.send_streaming(black_box(0u64)).unwrap();
+			black_box(rx.recv_timeout(std::time::Duration::from_millis(1)).unwrap());
+		});
+		bench("recv_timeout, timing out", iterations, || {
+			black_box(rx.recv_timeout(std::time::Duration::ZERO)).unwrap_err();
+		});
+	}
+
+	// One thread streaming many values through the same beam
+	let values = iterations * 10;
+	let (tx, rx) = threadbeam::channel();
+	let start = Instant::now();
+	let thread = std::thread::spawn(move || {
+		for value in 0..values {
+			tx.send_streaming(black_box(value)).unwrap();
+		}
+	});
+	while let Some(value) = rx.recv_streaming() {
+		black_box(value);
+	}
+	thread.join().unwrap();
+	let elapsed = start.elapsed();
+	println!(
+		"{:<28} {:>10.1} ns/value, {:.0} values/s",
+		"streaming throughput",
+		elapsed.as_nanos() as f64 / values as f64,
+		values as f64 / elapsed.as_secs_f64()
+	);
+}
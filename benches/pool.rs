@@ -0,0 +1,61 @@
+//! Compares creating thread beams with `channel` against reusing allocations from a `ThreadBeamPool`.
+//!
+//! Run with `cargo bench --bench pool --features pool`. Set `THREADBEAM_BENCH_ITERATIONS` to change the number of iterations.
+
+use std::{
+	alloc::{GlobalAlloc, Layout, System},
+	hint::black_box,
+	sync::atomic::{AtomicUsize, Ordering},
+	time::Instant,
+};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		System.alloc(layout)
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		System.dealloc(ptr, layout)
+	}
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn bench(name: &str, iterations: u32, channel: impl Fn() -> (threadbeam::ThreadBeamTx<u64>, threadbeam::ThreadBeamRx<u64>)) {
+	let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+	let start = Instant::now();
+	for i in 0..iterations {
+		let (tx, rx) = channel();
+		tx.send(i as u64).unwrap();
+		black_box(rx.recv());
+	}
+	let elapsed = start.elapsed();
+	let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+	println!(
+		"{name:<16} {:>8.1} ns/iter {:>10} allocations",
+		elapsed.as_nanos() as f64 / iterations as f64,
+		allocations
+	);
+}
+
+fn main() {
+	let iterations = std::env::var("THREADBEAM_BENCH_ITERATIONS")
+		.ok()
+		.and_then(|iterations| iterations.parse().ok())
+		.unwrap_or(1_000_000);
+
+	bench("channel", iterations, threadbeam::channel);
+
+	// Pools are only available with the std backend
+	#[cfg(not(feature = "no_std"))]
+	{
+		let pool = threadbeam::ThreadBeamPool::new();
+		bench("pool.channel", iterations, || pool.channel());
+	}
+}
@@ -0,0 +1,42 @@
+//! Compares the cost of a thread beam carrying a zero-sized readiness signal against one carrying a larger value, and a large value by value against one behind a `Box`.
+//!
+//! Run with `cargo bench --bench signal`. Set `THREADBEAM_BENCH_ITERATIONS` to change the number of iterations.
+
+use std::{hint::black_box, time::Instant};
+
+fn bench<T: Send>(name: &str, iterations: u32, value: impl Fn() -> T) {
+	let start = Instant::now();
+	for _ in 0..iterations {
+		let (tx, rx) = threadbeam::channel::<T>();
+		tx.send(value()).unwrap();
+		black_box(rx.recv());
+	}
+	let elapsed = start.elapsed();
+	println!("{name:<28} {:>8.1} ns/iter", elapsed.as_nanos() as f64 / iterations as f64);
+
+	let start = Instant::now();
+	for _ in 0..iterations {
+		let (tx, rx) = threadbeam::channel::<T>();
+		drop(tx);
+		black_box(rx.recv());
+	}
+	let elapsed = start.elapsed();
+	println!(
+		"{:<28} {:>8.1} ns/iter",
+		format!("{name} (hung up)"),
+		elapsed.as_nanos() as f64 / iterations as f64
+	);
+}
+
+fn main() {
+	let iterations = std::env::var("THREADBEAM_BENCH_ITERATIONS")
+		.ok()
+		.and_then(|iterations| iterations.parse().ok())
+		.unwrap_or(1_000_000);
+
+	bench("()", iterations, || ());
+	bench("u64", iterations, || black_box(0u64));
+	bench("[u64; 32]", iterations, || black_box([0u64; 32]));
+	bench("[u64; 1024]", iterations, || black_box([0u64; 1024]));
+	bench("Box<[u64; 1024]>", iterations, || black_box(Box::new([0u64; 1024])));
+}
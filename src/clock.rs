@@ -0,0 +1,76 @@
+use super::{r#impl::timeout_result, RecvTimeoutError, ThreadBeamRx};
+use std::time::{Duration, Instant};
+
+/// Where the timed receives of a thread beam get the time from, for [`ThreadBeamRx::recv_timeout_with_clock`].
+///
+/// [`SystemClock`], the real clock, is what [`recv_timeout`](ThreadBeamRx::recv_timeout) and [`recv_deadline`](ThreadBeamRx::recv_deadline)
+/// use. Swap in a fake one to test code with timeouts deterministically, without waiting for them to elapse for real.
+///
+/// The receiving thread still blocks on the real clock in between reading this one, so a clock that doesn't keep pace with real time
+/// should return a [`poll_interval`](Clock::poll_interval), or it will only be read again once a whole timeout has passed for real.
+///
+/// # Example
+///
+/// A clock that the test moves forward by hand:
+///
+/// ```rust
+/// use std::{sync::Mutex, time::{Duration, Instant}};
+///
+/// struct FakeClock(Mutex<Instant>);
+/// impl threadbeam::Clock for FakeClock {
+///     fn now(&self) -> Instant {
+///         *self.0.lock().unwrap()
+///     }
+///
+///     fn poll_interval(&self) -> Option<Duration> {
+///         Some(Duration::from_millis(1))
+///     }
+/// }
+///
+/// let clock = FakeClock(Mutex::new(Instant::now()));
+/// let (tx, rx) = threadbeam::channel::<u32>();
+///
+/// // However long the receiving side really waits, a timeout only elapses once the clock says so
+/// std::thread::scope(|s| {
+///     s.spawn(|| {
+///         std::thread::sleep(Duration::from_millis(10));
+///         *clock.0.lock().unwrap() += Duration::from_secs(60);
+///     });
+///     assert_eq!(rx.recv_timeout_with_clock(Duration::from_secs(30), &clock), Err(threadbeam::RecvTimeoutError::Timeout));
+/// });
+/// # drop(tx);
+/// ```
+pub trait Clock {
+	/// The current time, as far as this clock is concerned.
+	fn now(&self) -> Instant;
+
+	/// How long to block for at most before reading the clock again, whatever the timeout.
+	///
+	/// Defaults to `None`, blocking for the rest of the timeout at once, which is right for any clock that keeps pace with real time.
+	#[inline]
+	fn poll_interval(&self) -> Option<Duration> {
+		None
+	}
+}
+
+/// The real clock, [`Instant::now`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+impl Clock for SystemClock {
+	#[inline]
+	fn now(&self) -> Instant {
+		Instant::now()
+	}
+}
+
+impl<T: Send, Tag> ThreadBeamRx<T, Tag> {
+	/// Like [`recv_timeout`](ThreadBeamRx::recv_timeout), but with the time read from `clock`.
+	pub fn recv_timeout_with_clock(&self, timeout: Duration, clock: &impl Clock) -> Result<T, RecvTimeoutError> {
+		timeout_result(self.recv_status_until(clock, |now| now.checked_add(timeout)))
+	}
+
+	/// Like [`recv_deadline`](ThreadBeamRx::recv_deadline), but with the time read from `clock`.
+	pub fn recv_deadline_with_clock(&self, deadline: Instant, clock: &impl Clock) -> Result<T, RecvTimeoutError> {
+		timeout_result(self.recv_status_until(clock, |_| Some(deadline)))
+	}
+}
@@ -0,0 +1,58 @@
+#[cfg(not(feature = "safe"))]
+use core::sync::atomic::AtomicPtr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+type Hook = fn(id: usize, type_name: &'static str);
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+#[cfg(not(feature = "safe"))]
+static HOOK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+// Function pointers can't be stored atomically without casting them to and from data pointers
+#[cfg(all(feature = "safe", feature = "no_std"))]
+static HOOK: spin::Mutex<Option<Hook>> = spin::Mutex::new(None);
+#[cfg(all(feature = "safe", not(feature = "no_std")))]
+static HOOK: std::sync::Mutex<Option<Hook>> = std::sync::Mutex::new(None);
+
+/// Sets a hook that is called whenever a value is sent down a thread beam but dropped without ever being received.
+///
+/// The hook is given the thread beam's id (see `ThreadBeamTx::id` and `ThreadBeamRx::id`) and the type name of the value.
+///
+/// It runs while the thread beam is being deallocated, on whichever thread dropped the last side of it.
+pub fn set_unconsumed_hook(hook: Hook) {
+	#[cfg(not(feature = "safe"))]
+	HOOK.store(hook as *mut (), Ordering::Release);
+	#[cfg(feature = "safe")]
+	{
+		*lock_hook() = Some(hook);
+	}
+}
+
+#[cfg(all(feature = "safe", feature = "no_std"))]
+#[inline]
+fn lock_hook() -> spin::MutexGuard<'static, Option<Hook>> {
+	HOOK.lock()
+}
+#[cfg(all(feature = "safe", not(feature = "no_std")))]
+#[inline]
+fn lock_hook() -> std::sync::MutexGuard<'static, Option<Hook>> {
+	HOOK.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+#[inline]
+pub(super) fn next_id() -> usize {
+	NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+#[cold]
+pub(super) fn unconsumed(id: usize, type_name: &'static str) {
+	#[cfg(not(feature = "safe"))]
+	let hook = {
+		let hook = HOOK.load(Ordering::Acquire);
+		(!hook.is_null()).then(|| unsafe { core::mem::transmute::<*mut (), Hook>(hook) })
+	};
+	#[cfg(feature = "safe")]
+	let hook = *lock_hook();
+	if let Some(hook) = hook {
+		hook(id, type_name);
+	}
+}
@@ -0,0 +1,65 @@
+use super::ThreadBeamRx;
+use core::{
+	future::Future,
+	pin::Pin,
+	task::{Context, Poll},
+};
+
+/// A future that resolves to the value sent by the sending side of a thread beam.
+///
+/// See [`ThreadBeamRx::recv_async`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvFuture<T: Send, Tag = ()>(ThreadBeamRx<T, Tag>);
+
+impl<T: Send, Tag> ThreadBeamRx<T, Tag> {
+	/// Receive the value sent by the sending side of the thread beam without blocking the current thread.
+	///
+	/// The returned future resolves to `None` if the sending side of the thread beam has been dropped.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// # async fn example() {
+	/// let (tx, rx) = threadbeam::channel();
+	///
+	/// std::thread::spawn(move || {
+	///     tx.send(String::from("Hello, world!")).unwrap();
+	/// });
+	///
+	/// let hello = rx.recv_async().await;
+	/// assert_eq!(hello.as_deref(), Some("Hello, world!"));
+	/// # }
+	/// ```
+	#[inline]
+	pub fn recv_async(self) -> RecvFuture<T, Tag> {
+		RecvFuture(self)
+	}
+}
+
+impl<T: Send, Tag> RecvFuture<T, Tag> {
+	/// Checks for the value sent by the sending side of the thread beam, registering `cx`'s waker to be woken once it arrives.
+	///
+	/// This is what the [`Future`] impl calls, for plugging a thread beam into an executor by hand.
+	///
+	/// The thread beam holds a single waker. Each poll that returns `Poll::Pending` registers `cx`'s waker, replacing the one registered by the previous poll unless [`Waker::will_wake`](core::task::Waker::will_wake) says it would wake the same task. Only the most recently registered waker is woken, once, when a value is sent or the sending side is dropped, so poll again with the current task's context after every wake-up.
+	///
+	/// Returns `Poll::Ready(None)` if the sending side of the thread beam has been dropped, including when polled again after the value has already been returned.
+	#[inline]
+	pub fn poll_recv(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+		self.0.poll_recv(cx.waker())
+	}
+}
+impl<T: Send, Tag> From<ThreadBeamRx<T, Tag>> for RecvFuture<T, Tag> {
+	#[inline]
+	fn from(rx: ThreadBeamRx<T, Tag>) -> Self {
+		RecvFuture(rx)
+	}
+}
+impl<T: Send, Tag> Future for RecvFuture<T, Tag> {
+	type Output = Option<T>;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		self.poll_recv(cx)
+	}
+}
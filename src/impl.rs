@@ -1,29 +1,182 @@
-use super::{ThreadBeamFlags, ThreadBeamRx, ThreadBeamState, ThreadBeamTx};
-use core::{mem::MaybeUninit, ptr::NonNull};
+use super::{
+	share, split, Allocation, Clock, RecvCancelError, RecvStatus, RecvTimeoutError, Seen, SendError, SendTimeoutError, SpawnError, SystemClock,
+	ThreadBeamFlags, ThreadBeamRx, ThreadBeamState, ThreadBeamTx, TryRecvError, WeakThreadBeamTx,
+};
+use core::marker::PhantomData;
+#[cfg(all(not(feature = "safe"), feature = "pool"))]
+use core::ptr::NonNull;
+use core::task::Poll;
+#[cfg(feature = "pool")]
+use std::sync::atomic::AtomicU32;
+use std::{
+	sync::atomic::{AtomicBool, AtomicU8, Ordering},
+	time::{Duration, Instant},
+};
+#[cfg(not(feature = "safe"))]
+use {super::AllocError, core::mem::MaybeUninit};
 
+#[cfg(all(feature = "parking_lot", not(feature = "spin")))]
+use parking_lot::Condvar;
 #[cfg(feature = "parking_lot")]
-use parking_lot::{Condvar, Mutex};
+use parking_lot::{Mutex, MutexGuard};
 
+#[cfg(all(not(feature = "parking_lot"), not(feature = "spin")))]
+use std::sync::Condvar;
 #[cfg(not(feature = "parking_lot"))]
-use std::sync::{Condvar, Mutex};
+use std::sync::{Mutex, MutexGuard, PoisonError, TryLockError};
 
-#[cfg(not(feature = "parking_lot"))]
+// How long a blocked `recv` waits with the `paranoid` feature before checking that it hasn't missed a notification
+#[cfg(feature = "paranoid")]
+const PARANOID_WAKE_INTERVAL: Duration = Duration::from_millis(50);
+
+// The longest `recv_adaptive` spins before parking, about what it costs to park a thread and wake it up again
+#[cfg(feature = "pool")]
+const ADAPTIVE_SPIN_LIMIT: Duration = Duration::from_micros(20);
+
+// The state is never left half-updated, so a lock poisoned by a panic elsewhere (e.g. while a `BeamGuard` is held) is recovered
+// rather than propagated, which would stop either side from seeing the other hang up or from freeing the allocation.
+
+// The waiting macros take whatever holds the `lock` being waited on and the `cvar` to wait on it with, so that they can wait without
+// the condvar with the `spin` feature
+
+#[cfg(all(not(feature = "parking_lot"), not(feature = "spin")))]
 macro_rules! cvar_wait {
-	($lock:ident = $cvar:expr) => {
-		$lock = $cvar.wait($lock).unwrap();
+	($lock:ident = $owner:expr) => {
+		$lock = $owner.cvar.wait($lock).unwrap_or_else(PoisonError::into_inner);
 	};
 }
-#[cfg(feature = "parking_lot")]
+#[cfg(all(feature = "parking_lot", not(feature = "spin")))]
+macro_rules! cvar_wait {
+	($lock:ident = $owner:expr) => {
+		$owner.cvar.wait(&mut $lock);
+	};
+}
+#[cfg(feature = "spin")]
 macro_rules! cvar_wait {
-	($lock:ident = $cvar:expr) => {
-		$cvar.wait(&mut $lock);
+	($lock:ident = $owner:expr) => {
+		drop($lock);
+		spin_wait();
+		$lock = lock_mutex!($owner.lock);
+	};
+}
+
+#[cfg(all(not(feature = "parking_lot"), not(feature = "spin")))]
+macro_rules! cvar_wait_timeout {
+	($lock:ident = $owner:expr, $timeout:expr) => {
+		$lock = $owner.cvar.wait_timeout($lock, $timeout).unwrap_or_else(PoisonError::into_inner).0;
+	};
+}
+#[cfg(all(feature = "parking_lot", not(feature = "spin")))]
+macro_rules! cvar_wait_timeout {
+	($lock:ident = $owner:expr, $timeout:expr) => {
+		$owner.cvar.wait_for(&mut $lock, $timeout);
+	};
+}
+// Spinning waits are short enough that the caller's loop can check its own timeout
+#[cfg(feature = "spin")]
+macro_rules! cvar_wait_timeout {
+	($lock:ident = $owner:expr, $timeout:expr) => {
+		let _: Duration = $timeout;
+		cvar_wait!($lock = $owner);
+	};
+}
+
+// Waits while `$condition` holds, for at most `$timeout` in total, and evaluates to whether it timed out
+#[cfg(all(not(feature = "parking_lot"), not(feature = "spin")))]
+macro_rules! cvar_wait_while_timeout {
+	($lock:ident = $owner:expr, $timeout:expr, $condition:expr) => {{
+		let (lock, result) = $owner
+			.cvar
+			.wait_timeout_while($lock, $timeout, $condition)
+			.unwrap_or_else(PoisonError::into_inner);
+		$lock = lock;
+		result.timed_out()
+	}};
+}
+#[cfg(all(feature = "parking_lot", not(feature = "spin")))]
+macro_rules! cvar_wait_while_timeout {
+	($lock:ident = $owner:expr, $timeout:expr, $condition:expr) => {
+		$owner.cvar.wait_while_for(&mut $lock, $condition, $timeout).timed_out()
+	};
+}
+#[cfg(feature = "spin")]
+macro_rules! cvar_wait_while_timeout {
+	($lock:ident = $owner:expr, $timeout:expr, $condition:expr) => {{
+		let (lock, timed_out) = spin_wait_while_timeout(&$owner.lock, $lock, $timeout, $condition);
+		$lock = lock;
+		timed_out
+	}};
+}
+
+// Frees the shared state once the side in `$shared` is the last one to drop; with the `safe` feature, dropping the side does that
+#[cfg(all(not(feature = "safe"), not(feature = "pool")))]
+macro_rules! deallocate {
+	($shared:expr) => {
+		drop(unsafe { Box::from_raw($shared.as_ptr()) })
+	};
+}
+#[cfg(all(feature = "safe", not(feature = "pool")))]
+macro_rules! deallocate {
+	($shared:expr) => {{}};
+}
+
+// With the `pool` feature, returns the shared state to its pool instead, if it came from one
+#[cfg(all(not(feature = "safe"), feature = "pool"))]
+macro_rules! deallocate {
+	($shared:expr) => {
+		unsafe { ThreadBeamInner::deallocate($shared) }
+	};
+}
+#[cfg(all(feature = "safe", feature = "pool"))]
+macro_rules! deallocate {
+	($shared:expr) => {
+		ThreadBeamInner::deallocate(&$shared)
+	};
+}
+
+// Runs the condvar wait in `$wait`, counted in `cvar_waiters` so that `changed` knows how many threads to wake
+macro_rules! counted_wait {
+	($lock:ident, $wait:block) => {{
+		$lock.cvar_waiters += 1;
+		let result = $wait;
+		$lock.cvar_waiters -= 1;
+		result
+	}};
+}
+
+// Runs the condvar wait in `$wait` as a receiving side waiting for the sending side, advertising as much with the WAITING flag
+macro_rules! rx_wait {
+	($lock:ident = $inner:expr, $wait:block) => {{
+		$lock.set_waiting(true);
+		$inner.publish(&$lock);
+		let result = counted_wait!($lock, $wait);
+		$lock.set_waiting(false);
+		$inner.publish(&$lock);
+		result
+	}};
+}
+
+#[cfg(not(feature = "parking_lot"))]
+macro_rules! try_lock_mutex {
+	($mutex:expr) => {
+		match $mutex.try_lock() {
+			Ok(lock) => Some(lock),
+			Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+			Err(TryLockError::WouldBlock) => None,
+		}
+	};
+}
+#[cfg(feature = "parking_lot")]
+macro_rules! try_lock_mutex {
+	($mutex:expr) => {
+		$mutex.try_lock()
 	};
 }
 
 #[cfg(not(feature = "parking_lot"))]
 macro_rules! lock_mutex {
 	($mutex:expr) => {
-		$mutex.lock().unwrap()
+		$mutex.lock().unwrap_or_else(PoisonError::into_inner)
 	};
 }
 #[cfg(feature = "parking_lot")]
@@ -33,132 +186,2191 @@ macro_rules! lock_mutex {
 	};
 }
 
+// Stands in for parking on the condvar with the `spin` feature: spins for a moment, then lets the thread being waited on run
+#[cfg(feature = "spin")]
+fn spin_wait() {
+	for _ in 0..64 {
+		core::hint::spin_loop();
+	}
+	std::thread::yield_now();
+}
+
+#[cfg(feature = "spin")]
+fn spin_wait_while_timeout<'a, S>(
+	mutex: &'a Mutex<S>,
+	mut lock: MutexGuard<'a, S>,
+	timeout: Duration,
+	mut condition: impl FnMut(&mut S) -> bool,
+) -> (MutexGuard<'a, S>, bool) {
+	let start = Instant::now();
+	loop {
+		if !condition(&mut lock) {
+			return (lock, false);
+		} else if start.elapsed() >= timeout {
+			return (lock, true);
+		}
+		drop(lock);
+		spin_wait();
+		lock = lock_mutex!(mutex);
+	}
+}
+
+// What a timed receive returns for each outcome of `recv_status`
+#[inline]
+pub(super) fn timeout_result<T>(status: RecvStatus<T>) -> Result<T, RecvTimeoutError> {
+	match status {
+		RecvStatus::Received(value) => Ok(value),
+		RecvStatus::TimedOut => Err(RecvTimeoutError::Timeout),
+		RecvStatus::SenderDropped => Err(RecvTimeoutError::Disconnected),
+	}
+}
+
 pub(super) struct ThreadBeamInner<T> {
 	lock: Mutex<ThreadBeamState<T>>,
+	#[cfg(not(feature = "spin"))]
 	cvar: Condvar,
+
+	// A snapshot of the state's flags, for answering questions that don't need the lock
+	flags: AtomicU8,
+
+	// Where to return this allocation once both sides are gone, if it came from a `ThreadBeamPool`
+	#[cfg(feature = "pool")]
+	pool: Option<std::sync::Weak<FreeList<T>>>,
+
+	// How long `recv_adaptive` has recently waited for a value, in nanoseconds, as an exponentially weighted moving average. It outlives
+	// each use of a pooled allocation, so that the next thread beam made from it starts from what the last one learnt.
+	#[cfg(feature = "pool")]
+	wait_average: AtomicU32,
+}
+#[cfg(feature = "pool")]
+type FreeList<T> = Mutex<Vec<Allocation<T>>>;
+
+// Without the `pool` feature, the allocation is nothing but the lock, the condvar and the flags snapshot, so plain thread beams don't pay for pooling
+#[cfg(all(not(feature = "pool"), not(feature = "spin")))]
+const _: () = {
+	use core::mem::size_of;
+	assert!(size_of::<ThreadBeamInner<()>>() == size_of::<(Mutex<ThreadBeamState<()>>, Condvar, AtomicU8)>());
+	assert!(size_of::<ThreadBeamInner<u64>>() == size_of::<(Mutex<ThreadBeamState<u64>>, Condvar, AtomicU8)>());
+};
+#[cfg(all(not(feature = "pool"), feature = "spin"))]
+const _: () = {
+	use core::mem::size_of;
+	assert!(size_of::<ThreadBeamInner<()>>() == size_of::<(Mutex<ThreadBeamState<()>>, AtomicU8)>());
+	assert!(size_of::<ThreadBeamInner<u64>>() == size_of::<(Mutex<ThreadBeamState<u64>>, AtomicU8)>());
+};
+
+impl<T> ThreadBeamInner<T> {
+	#[inline]
+	pub(crate) fn new() -> Allocation<T> {
+		Allocation::new(Self::unboxed())
+	}
+
+	fn unboxed() -> Self {
+		let state = ThreadBeamState::new();
+		Self {
+			flags: AtomicU8::new(state.flags.bits()),
+			lock: Mutex::new(state),
+			#[cfg(not(feature = "spin"))]
+			cvar: Condvar::new(),
+			#[cfg(feature = "pool")]
+			pool: None,
+			#[cfg(feature = "pool")]
+			wait_average: AtomicU32::new(0),
+		}
+	}
+
+	#[cfg(feature = "pool")]
+	fn pooled(pool: std::sync::Weak<FreeList<T>>) -> Allocation<T> {
+		Allocation::new(Self {
+			pool: Some(pool),
+			..Self::unboxed()
+		})
+	}
+
+	// Safety: must only be called once, by whichever side of the thread beam drops last
+	#[cfg(all(not(feature = "safe"), feature = "pool"))]
+	unsafe fn deallocate(inner: NonNull<Self>) {
+		Self::recycle(Box::from_raw(inner.as_ptr()));
+	}
+
+	// The allocation itself is freed along with the last reference to it, so only returning it to its pool is left to do
+	#[cfg(all(feature = "safe", feature = "pool"))]
+	fn deallocate(inner: &alloc::sync::Arc<Self>) {
+		Self::recycle(inner.clone());
+	}
+
+	#[cfg(feature = "pool")]
+	fn recycle(inner: Allocation<T>) {
+		let Some(free_list) = inner.pool.as_ref().and_then(std::sync::Weak::upgrade) else {
+			return;
+		};
+
+		// Anything left over from this use is only dropped once the allocation is safely back in the pool
+		let leftover = core::mem::replace(&mut *lock_mutex!(inner.lock), ThreadBeamState::unsplit());
+		lock_mutex!(free_list).push(inner);
+		drop(leftover);
+	}
+
+	#[cfg(feature = "pool")]
+	fn reset(&self) {
+		let mut lock = lock_mutex!(self.lock);
+		*lock = ThreadBeamState::new();
+		self.publish(&lock);
+	}
+
+	// Must be called while the lock is still held after changing the state, if nobody needs waking up
+	#[inline]
+	fn publish(&self, state: &ThreadBeamState<T>) {
+		self.flags.store(state.flags.bits(), Ordering::Release);
+	}
+
+	// Must be called while the lock is still held after changing the state
+	//
+	// Every thread blocked on the condvar is counted in `cvar_waiters` while holding the lock, so the count is exact here: with a single
+	// receiving side blocked in `recv`, that one thread is woken, once, and nobody else is disturbed.
+	#[inline]
+	fn changed(&self, state: &ThreadBeamState<T>) {
+		self.publish(state);
+		#[cfg(feature = "paranoid")]
+		state.notified();
+		#[cfg(not(feature = "spin"))]
+		match state.cvar_waiters {
+			0 => {}
+			1 => {
+				self.cvar.notify_one();
+			}
+			_ => {
+				self.cvar.notify_all();
+			}
+		}
+	}
+
+	// Receives through `poll_data`, letting a streaming sender that may be waiting for the slot know that it has been freed
+	#[inline]
+	fn poll(&self, state: &mut ThreadBeamState<T>, seen: &Seen) -> Poll<Option<T>> {
+		let poll = state.poll_data(seen);
+		if let Poll::Ready(Some(_)) = poll {
+			self.changed(state);
+		}
+		poll
+	}
+
+	// Sends another value down a thread beam that has room for it, for the streaming senders
+	fn stream(&self, mut lock: MutexGuard<'_, ThreadBeamState<T>>, value: T) {
+		lock.set_data(value);
+
+		self.changed(&lock);
+
+		let waker = lock.take_waker();
+		drop(lock);
+		if let Some(waker) = waker {
+			waker.wake();
+		}
+	}
+
+	#[inline]
+	fn flags(&self) -> ThreadBeamFlags {
+		ThreadBeamFlags::from_bits_truncate(self.flags.load(Ordering::Acquire))
+	}
+
+	// Claims the slot for a sender with a compare-exchange on the flags snapshot, before it takes the lock to fill it, so that of several
+	// senders racing, only the first takes the lock at all. This is the first of two phases: the snapshot shows RESERVED from the claim
+	// until the value is in and HAS_DATA is published, and the value itself is only ever touched under the lock, so nothing can read it
+	// half-written. A claim that is overwritten by another side publishing its own change is harmless, as the lock has the final say.
+	fn claim(&self) -> bool {
+		let mut flags = self.flags.load(Ordering::Acquire);
+		loop {
+			let current = ThreadBeamFlags::from_bits_truncate(flags);
+			if current.intersects(ThreadBeamFlags::HAS_DATA | ThreadBeamFlags::RESERVED)
+				|| !current.contains(ThreadBeamFlags::TX | ThreadBeamFlags::RX)
+			{
+				return false;
+			}
+			match self
+				.flags
+				.compare_exchange_weak(flags, (current | ThreadBeamFlags::RESERVED).bits(), Ordering::AcqRel, Ordering::Acquire)
+			{
+				Ok(_) => return true,
+				Err(actual) => flags = actual,
+			}
+		}
+	}
 }
 
-impl<T: Send> ThreadBeamTx<T> {
+impl<T: Send, Tag> ThreadBeamTx<T, Tag> {
 	/// Send a value to the receiving side of the thread beam.
-	pub fn send(self, value: T) {
-		let inner = unsafe { self.0.as_ref() };
+	///
+	/// Returns the value back in a [`SendError`] if the receiving side of the thread beam has been dropped, or if another sending side (a clone, or one upgraded from a [`WeakThreadBeamTx`]) has already sent a value that is still waiting to be received.
+	pub fn send(self, value: T) -> Result<(), SendError<T>> {
+		let inner = self.inner();
+
+		// A clone that has lost the race to send finds out from claiming the slot on the flags, rather than queueing up on the lock behind
+		// the winner. The flags may be a moment out of date, which is no different from this send having come a moment earlier, while the
+		// value was still there.
+		if !inner.claim() {
+			return Err(SendError(value));
+		}
 
 		let mut lock = lock_mutex!(inner.lock);
+		if !lock.can_send() {
+			// Drops the claim along with anything else out of date in the snapshot
+			inner.publish(&lock);
+			return Err(SendError(value));
+		}
 		lock.set_data(value);
 
-		inner.cvar.notify_all();
+		inner.changed(&lock);
+
+		let waker = lock.take_waker();
+		drop(lock);
+		if let Some(waker) = waker {
+			waker.wake();
+		}
+
+		Ok(())
+	}
+
+	/// Send a value to the receiving side of the thread beam, returning a [`SendReceipt`] that can be used to find out when the value was received.
+	///
+	/// The receipt only holds on to the thread beam weakly, like a [`WeakThreadBeamTx`], so the sending side is dropped as usual once the value is sent, and the receiving side sees the thread beam as disconnected once any other sending sides are gone too.
+	///
+	/// Returns the value back in a [`SendError`] if the receiving side of the thread beam has been dropped, or if another sending side has already sent a value that is still waiting to be received.
+	pub fn send_with_receipt(self, value: T) -> Result<SendReceipt<T, Tag>, SendError<T>> {
+		self.send_open(value)?;
+		Ok(SendReceipt(self.downgrade()))
+	}
+
+	/// Send a value to the receiving side of the thread beam, returning a [`CompletionToken`] for signalling later that the work behind it is done.
+	///
+	/// The token keeps the sending side of the thread beam open, so the receiving side can receive the value straight away with [`recv_keep`](ThreadBeamRx::recv_keep) and then wait for the token with [`wait_for_completion`](ThreadBeamRx::wait_for_completion), e.g. to be handed a handle early and told once the worker has reached a steady state.
+	///
+	/// Returns the value back in a [`SendError`] if the receiving side of the thread beam has been dropped, or if another sending side has already sent a value that is still waiting to be received.
+	pub fn send_and_keep_alive(self, value: T) -> Result<CompletionToken<T, Tag>, SendError<T>> {
+		self.send_open(value)?;
+		Ok(CompletionToken(self))
+	}
+
+	/// Reserves the thread beam's slot for constructing the value in place with a [`SlotWriter`], instead of building it on this thread's stack and moving it in.
+	///
+	/// While the slot is reserved, other sending sides can't send. Dropping the writer without committing the value drops this sending side along with it, so the receiving side sees the thread beam as disconnected once any other sending sides are gone too, rather than waiting forever.
+	///
+	/// Returns `None` if the receiving side of the thread beam has been dropped, or if another sending side has already sent a value that is still waiting to be received.
+	///
+	/// Not available with the `safe` feature.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// let (tx, rx) = threadbeam::channel::<[u64; 4096]>();
+	///
+	/// # let j =
+	/// std::thread::spawn(move || {
+	///     let mut writer = tx.reserve().unwrap();
+	///     writer.with_slot(|slot| {
+	///         let table = slot.write([0; 4096]);
+	///         table[0] = 42;
+	///     });
+	///     // Safety: the slot was written by `with_slot` above
+	///     unsafe { writer.commit() };
+	/// });
+	///
+	/// assert_eq!(rx.recv().map(|table| table[0]), Some(42));
+	/// # j.join().unwrap();
+	/// ```
+	#[cfg(not(feature = "safe"))]
+	pub fn reserve(self) -> Option<SlotWriter<T, Tag>> {
+		let inner = self.inner();
+		let mut lock = lock_mutex!(inner.lock);
+		if !lock.can_send() {
+			return None;
+		}
+		lock.flags |= ThreadBeamFlags::RESERVED;
+		inner.publish(&lock);
+		drop(lock);
+		Some(SlotWriter(self))
+	}
+
+	// Sends without dropping the sending side, for the senders that hand it back wrapped up and for the raw interface
+	pub(crate) fn send_open(&self, value: T) -> Result<(), SendError<T>> {
+		let waker = {
+			let inner = self.inner();
+
+			let mut lock = lock_mutex!(inner.lock);
+			if !lock.can_send() {
+				return Err(SendError(value));
+			}
+			lock.set_data(value);
+
+			inner.changed(&lock);
+
+			lock.take_waker()
+		};
+		if let Some(waker) = waker {
+			waker.wake();
+		}
+		Ok(())
+	}
+
+	/// Returns whether the receiving side of the thread beam is still alive, the opposite of [`is_disconnected`](ThreadBeamTx::is_disconnected).
+	///
+	/// This reads a snapshot of the thread beam's flags and never touches its lock, so it doesn't contend with threads sending or waiting on it. The answer is only advisory: the receiving side may be dropped immediately after this returns `true`.
+	#[inline]
+	pub fn receiver_alive(&self) -> bool {
+		let inner = self.inner();
+		inner.flags().contains(ThreadBeamFlags::RX)
+	}
+
+	/// Returns whether the receiving side of the thread beam has been dropped.
+	///
+	/// This never blocks for long, but the answer is only advisory: the receiving side may be dropped immediately after this returns `false`.
+	pub fn is_disconnected(&self) -> bool {
+		let inner = self.inner();
+		!inner.flags().contains(ThreadBeamFlags::RX)
+	}
+
+	/// Returns whether the receiving side of the thread beam is currently blocked waiting for a value, so that sending now would wake it straight away.
+	///
+	/// Use this for heuristics such as finishing the current batch of work before sending while nobody is waiting yet. This never blocks, but the answer is only advisory: the receiving side may start waiting immediately after this returns `false`.
+	pub fn has_waiter(&self) -> bool {
+		let inner = self.inner();
+		inner.flags().contains(ThreadBeamFlags::WAITING)
+	}
+
+	/// Send a value to the receiving side of the thread beam while keeping the sending side open, so that more values can be sent after it.
+	///
+	/// The thread beam only holds one value at a time, so this blocks until the previously sent value, if any, has been received. The receiving side should receive each value with [`recv_streaming`](ThreadBeamRx::recv_streaming) (or [`try_recv`](ThreadBeamRx::try_recv) or [`recv_timeout`](ThreadBeamRx::recv_timeout)), and sees the thread beam as disconnected once the sending side is dropped.
+	///
+	/// Returns the value back in a [`SendError`] if the receiving side of the thread beam has been dropped, including while waiting for the previous value to be received.
+	pub fn send_streaming(&self, value: T) -> Result<(), SendError<T>> {
+		let inner = self.inner();
+
+		let mut lock = lock_mutex!(inner.lock);
+		loop {
+			if lock.hung_up() {
+				return Err(SendError(value));
+			} else if lock.can_send() {
+				break;
+			}
+			counted_wait!(lock, {
+				cvar_wait!(lock = inner);
+			});
+		}
+		inner.stream(lock, value);
+
+		Ok(())
+	}
+
+	/// Like [`send_streaming`](ThreadBeamTx::send_streaming), but gives up if the previously sent value hasn't been received within `timeout`.
+	///
+	/// Returns the value back in a [`SendTimeoutError`], as [`SendTimeoutError::Timeout`] if the previous value is still waiting to be received, or [`SendTimeoutError::Disconnected`] if the receiving side of the thread beam has been dropped, including while waiting.
+	pub fn send_timeout(&self, value: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+		let inner = self.inner();
+
+		let mut lock = lock_mutex!(inner.lock);
+		let timed_out = counted_wait!(lock, {
+			cvar_wait_while_timeout!(lock = inner, timeout, |state| !state.hung_up() && !state.can_send())
+		});
+		if lock.hung_up() {
+			return Err(SendTimeoutError::Disconnected(value));
+		} else if timed_out {
+			return Err(SendTimeoutError::Timeout(value));
+		}
+		inner.stream(lock, value);
+
+		Ok(())
+	}
+
+	/// Send every value in `values` to the receiving side of the thread beam in order, like [`send_streaming`](ThreadBeamTx::send_streaming) but taking the lock once for the whole batch.
+	///
+	/// The thread beam still only holds one value at a time, so this blocks until each value has been received before sending the next, but the lock is only given up while waiting for that, rather than being taken again for every value.
+	///
+	/// Returns the values that weren't sent back in a [`SendError`], starting with the one that was waiting to be sent, if the receiving side of the thread beam has been dropped, including while waiting.
+	pub fn send_all(&self, values: impl IntoIterator<Item = T>) -> Result<(), SendError<Vec<T>>> {
+		let inner = self.inner();
+		let mut values = values.into_iter();
+
+		let mut lock = lock_mutex!(inner.lock);
+		while let Some(value) = values.next() {
+			loop {
+				if lock.hung_up() {
+					return Err(SendError(core::iter::once(value).chain(values).collect()));
+				} else if lock.can_send() {
+					break;
+				}
+				// An async receiving side only takes the previous value once it is woken, and wakers are never woken under the lock
+				if let Some(waker) = lock.take_waker() {
+					drop(lock);
+					waker.wake();
+					lock = lock_mutex!(inner.lock);
+					continue;
+				}
+				counted_wait!(lock, {
+					cvar_wait!(lock = inner);
+				});
+			}
+			lock.set_data(value);
+			inner.changed(&lock);
+		}
+
+		let waker = lock.take_waker();
+		drop(lock);
+		if let Some(waker) = waker {
+			waker.wake();
+		}
+
+		Ok(())
+	}
+
+	/// Returns the id of this thread beam, as given to the hook set by [`set_unconsumed_hook`](crate::set_unconsumed_hook).
+	#[cfg(feature = "debug-unconsumed")]
+	pub fn id(&self) -> usize {
+		let inner = self.inner();
+		lock_mutex!(inner.lock).id
+	}
+}
+impl<T: Send, Tag> core::fmt::Debug for ThreadBeamTx<T, Tag> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		let inner = self.inner();
+		super::fmt_state("ThreadBeamTx", try_lock_mutex!(inner.lock).as_deref(), f)
 	}
 }
-impl<T: Send> Drop for ThreadBeamTx<T> {
+impl<T: Send, Tag> Drop for ThreadBeamTx<T, Tag> {
 	fn drop(&mut self) {
-		let deallocate = {
-			let inner = unsafe { self.0.as_ref() };
+		let (deallocate, waker) = {
+			let inner = self.inner();
 
 			let mut lock = lock_mutex!(inner.lock);
 			let deallocate = lock.drop_tx();
 
-			inner.cvar.notify_all();
+			inner.changed(&lock);
 
-			deallocate
+			(deallocate, lock.take_waker())
 		};
+		if let Some(waker) = waker {
+			waker.wake();
+		}
 		if deallocate {
-			unsafe { Box::from_raw(self.0.as_ptr()) };
+			deallocate!(self.0);
 		}
 	}
 }
 
-impl<T: Send> ThreadBeamRx<T> {
-	/// Receive the value sent by the sending side of the thread beam.
+impl<T: Send, Tag> ThreadBeamTx<T, Tag> {
+	/// Disconnects the sending side of the thread beam without dropping it, as if every sending side had been dropped.
 	///
-	/// Returns `None` if the sending side of the thread beam has been dropped.
-	pub fn recv(self) -> Option<T> {
-		let inner = unsafe { self.0.as_ref() };
+	/// A receiving side waiting for a value is woken and returns `None`, unless a value has already been sent, which can still be received. Every later send, from this or any clone of the sending side, fails. Dropping the sending side afterwards only frees the thread beam, if it's the last side left.
+	pub fn close(&self) {
+		let waker = {
+			let inner = self.inner();
 
-		let mut lock = lock_mutex!(inner.lock);
+			let mut lock = lock_mutex!(inner.lock);
+			lock.close_tx();
 
-		if lock.has_data() {
-			lock.flags &= !ThreadBeamFlags::HAS_DATA;
-			return Some(unsafe { lock.data.assume_init_read() });
-		} else if lock.hung_up() {
-			return None;
+			inner.changed(&lock);
+
+			lock.take_waker()
+		};
+		if let Some(waker) = waker {
+			waker.wake();
 		}
+	}
 
-		cvar_wait!(lock = inner.cvar);
+	/// Creates a [`WeakThreadBeamTx`] for this sending side of the thread beam.
+	///
+	/// The weak sending side can't send by itself and doesn't keep the receiving side waiting, but it can be upgraded back into a sending side for as long as this one (or another upgraded one) and the receiving side are alive.
+	pub fn downgrade(&self) -> WeakThreadBeamTx<T, Tag> {
+		let inner = self.inner();
+		lock_mutex!(inner.lock).downgrade_tx();
+		WeakThreadBeamTx(share(&self.0), PhantomData)
+	}
+}
 
-		if lock.has_data() {
-			lock.flags &= !ThreadBeamFlags::HAS_DATA;
-			Some(unsafe { lock.data.assume_init_read() })
-		} else {
-			None
-		}
+impl<T: Send, Tag> WeakThreadBeamTx<T, Tag> {
+	/// Upgrades into a sending side of the thread beam.
+	///
+	/// Returns `None` if the receiving side of the thread beam has been dropped, or if every sending side has already been dropped.
+	pub fn upgrade(&self) -> Option<ThreadBeamTx<T, Tag>> {
+		let inner = self.inner();
+		let upgraded = lock_mutex!(inner.lock).upgrade_tx();
+		upgraded.then(|| ThreadBeamTx(share(&self.0), PhantomData))
 	}
 }
-impl<T: Send> Drop for ThreadBeamRx<T> {
+impl<T: Send, Tag> core::fmt::Debug for WeakThreadBeamTx<T, Tag> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		let inner = self.inner();
+		super::fmt_state("WeakThreadBeamTx", try_lock_mutex!(inner.lock).as_deref(), f)
+	}
+}
+impl<T: Send, Tag> Drop for WeakThreadBeamTx<T, Tag> {
 	fn drop(&mut self) {
 		let deallocate = {
-			let inner = unsafe { self.0.as_ref() };
-			lock_mutex!(inner.lock).drop_rx()
+			let inner = self.inner();
+			lock_mutex!(inner.lock).drop_weak_tx()
 		};
 		if deallocate {
-			unsafe { Box::from_raw(self.0.as_ptr()) };
+			deallocate!(self.0);
 		}
 	}
 }
 
-/// Creates a new thread beam channel pair.
-///
-/// Also see [spawn] for a more convenient way to spawn a thread with a thread beam.
-///
-/// # Example
-///
-/// ```rust
-/// let (tx, rx) = threadbeam::channel();
-///
-/// # let j =
-/// std::thread::spawn(move || {
-///     tx.send(String::from("Hello, world!"));
-/// });
-///
-/// let hello = rx.recv();
-/// assert_eq!(hello.as_deref(), Some("Hello, world!"));
-///
-/// # j.join().unwrap();
-/// ```
-pub fn channel<T: Send>() -> (ThreadBeamTx<T>, ThreadBeamRx<T>) {
-	let inner = Box::into_raw(Box::new(ThreadBeamInner {
-		lock: Mutex::new(ThreadBeamState {
-			data: MaybeUninit::uninit(),
-			flags: ThreadBeamFlags::TX | ThreadBeamFlags::RX,
-		}),
-		cvar: Condvar::new(),
-	}));
-	let inner = unsafe { NonNull::new_unchecked(inner) };
-	(ThreadBeamTx(inner), ThreadBeamRx(inner))
-}
+impl<T: Send, Tag> ThreadBeamRx<T, Tag> {
+	/// Receive the value sent by the sending side of the thread beam.
+	///
+	/// Returns `None` if the sending side of the thread beam has been dropped.
+	///
+	/// This blocks until a value is sent or the sending side is dropped, so calling it on the thread that holds the sending side, before that thread has sent or dropped it, will deadlock.
+	///
+	/// A blocked receiving side is woken once by the send (or the drop of the sending side), without waking any other thread blocked on the thread beam, unless the receiving side has been cloned and several are blocked at once, in which case they're all woken.
+	#[must_use = "the beamed value is discarded if not used"]
+	#[inline]
+	#[track_caller]
+	pub fn recv(self) -> Option<T> {
+		self.recv_streaming()
+	}
 
-#[inline]
-/// Helper for spawning a new thread with a beam.
-///
-/// # Example
-///
-/// ```rust
-/// let (hello, thread) = threadbeam::spawn(move |tx| {
-///     tx.send(String::from("Hello, world!"));
-///     // your code...
-///     String::from("Thread completed!")
-/// });
-///
-/// assert_eq!(hello.as_deref(), Some("Hello, world!"));
-/// assert_eq!(thread.join().ok().as_deref(), Some("Thread completed!"));
-/// ```
-pub fn spawn<T, R, F>(spawn: F) -> (Option<T>, std::thread::JoinHandle<R>)
-where
-	F: FnOnce(ThreadBeamTx<T>) -> R,
-	F: Send + 'static,
-	T: Send + 'static,
-	R: Send + 'static,
-{
-	let (tx, rx) = channel();
-	let join = std::thread::spawn(move || spawn(tx));
-	(rx.recv(), join)
+	/// Receive the next value sent by the sending side of the thread beam, like [`recv_streaming`](ThreadBeamRx::recv_streaming), but spinning for a while before blocking if the sending side is usually quick about it.
+	///
+	/// The thread beam keeps a moving average of how long these receives have waited for a value. Each one spins for up to twice that average before blocking, so that a value from a sending side that is usually fast is picked up without paying for parking and waking the thread. Once the average grows past what a park and wake costs, about 20 microseconds, this blocks straight away like `recv_streaming`, and spins again once the sending side speeds back up.
+	///
+	/// The average is kept with the allocation, so thread beams from a [`ThreadBeamPool`] carry on from what the last thread beam in that allocation learnt, and a thread beam streaming many values learns from each of them. A new thread beam starts out blocking straight away.
+	///
+	/// Only available with the `pool` feature.
+	#[cfg(feature = "pool")]
+	#[track_caller]
+	pub fn recv_adaptive(&self) -> Option<T> {
+		let inner = self.inner();
+		let start = Instant::now();
+
+		let average = Duration::from_nanos(inner.wait_average.load(Ordering::Relaxed).into());
+		let budget = average * 2;
+		if budget <= ADAPTIVE_SPIN_LIMIT {
+			loop {
+				let flags = inner.flags();
+				if flags.contains(ThreadBeamFlags::HAS_DATA) || !flags.contains(ThreadBeamFlags::TX) || start.elapsed() >= budget {
+					break;
+				}
+				for _ in 0..64 {
+					core::hint::spin_loop();
+				}
+			}
+		}
+		let value = self.recv_streaming();
+
+		// Moves the average an eighth of the way towards this wait; receiving sides racing to update it only lose a sample
+		let waited = u32::try_from(start.elapsed().as_nanos()).unwrap_or(u32::MAX);
+		let average = inner.wait_average.load(Ordering::Relaxed);
+		inner.wait_average.store(average - average / 8 + waited / 8, Ordering::Relaxed);
+
+		value
+	}
+
+	/// Receive the next value sent with [`send_streaming`](ThreadBeamTx::send_streaming) by the sending side of the thread beam.
+	///
+	/// Returns `None` once the sending side of the thread beam has been dropped and every value sent before that has been received.
+	///
+	/// Like [`recv`](ThreadBeamRx::recv), this blocks until a value is sent or the sending side is dropped.
+	#[track_caller]
+	pub fn recv_streaming(&self) -> Option<T> {
+		let inner = self.inner();
+
+		let mut lock = lock_mutex!(inner.lock);
+
+		// The sender always sets the data and clears its flag under separate acquisitions of this lock, in that order,
+		// so checking for data before checking for a hang-up while holding the lock can never miss a value that was sent.
+		loop {
+			if let Poll::Ready(value) = inner.poll(&mut lock, &self.1) {
+				return value;
+			}
+
+			// Condvars can wake up spuriously, so only give up once the sender has actually gone away
+			rx_wait!(lock = inner, {
+				#[cfg(not(feature = "paranoid"))]
+				cvar_wait!(lock = inner);
+
+				// Wakes up regularly even if never notified, so that a lost notification panics instead of blocking forever
+				#[cfg(feature = "paranoid")]
+				{
+					cvar_wait_timeout!(lock = inner, PARANOID_WAKE_INTERVAL);
+					lock.check_notified();
+				}
+			});
+		}
+	}
+
+	/// Like [`recv_streaming`](ThreadBeamRx::recv_streaming), but fails fast on shutdown: returns `None` as soon as the thread beam has been disconnected, dropping any value that was sent before that but hasn't been received yet.
+	///
+	/// Every other receive drains the thread beam first, only reporting a disconnect once there is nothing left to receive.
+	#[track_caller]
+	pub fn recv_fail_fast(&self) -> Option<T> {
+		let inner = self.inner();
+
+		let mut lock = lock_mutex!(inner.lock);
+		loop {
+			if lock.hung_up() {
+				// Dropped once the lock has been released, in case dropping it uses the thread beam
+				let leftover = inner.poll(&mut lock, &self.1);
+				drop(lock);
+				drop(leftover);
+				return None;
+			}
+			if let Poll::Ready(value) = inner.poll(&mut lock, &self.1) {
+				return value;
+			}
+
+			rx_wait!(lock = inner, {
+				cvar_wait!(lock = inner);
+			});
+		}
+	}
+
+	/// Blocks until the sending side of the thread beam has been dropped, dropping any value it sent.
+	///
+	/// This uses the thread beam as a barrier, e.g. to wait for a thread to finish with its sending side without needing its `JoinHandle`. Unlike [`recv`](ThreadBeamRx::recv), a sent value doesn't end the wait, and a sending side streaming values with [`send_streaming`](ThreadBeamTx::send_streaming) isn't left waiting for them to be received.
+	pub fn wait_for_disconnect(self) {
+		let inner = self.inner();
+
+		let mut lock = lock_mutex!(inner.lock);
+		loop {
+			if let Poll::Ready(Some(value)) = inner.poll(&mut lock, &self.1) {
+				drop(value);
+			}
+			if !lock.tx_alive() {
+				return;
+			}
+
+			rx_wait!(lock = inner, {
+				cvar_wait!(lock = inner);
+			});
+		}
+	}
+
+	/// Blocks until the sending side of the thread beam has been dropped, including a [`CompletionToken`] being finished, leaving any value it sent to be received.
+	///
+	/// Unlike [`wait_for_disconnect`](ThreadBeamRx::wait_for_disconnect), a value that hasn't been received yet stays in the thread beam, and this doesn't count as waiting for a value as far as [`has_waiter`](ThreadBeamTx::has_waiter) is concerned.
+	pub fn wait_for_completion(&self) {
+		let inner = self.inner();
+
+		let mut lock = lock_mutex!(inner.lock);
+		while lock.tx_alive() {
+			counted_wait!(lock, {
+				cvar_wait!(lock = inner);
+			});
+		}
+	}
+
+	/// Receive the value sent by the sending side of the thread beam like [`recv`](ThreadBeamRx::recv), and report how the wait went.
+	///
+	/// Only available with the `debug-stats` feature, for tracking down wake-up storms.
+	#[cfg(feature = "debug-stats")]
+	pub fn recv_with_stats(self) -> (Option<T>, RecvStats) {
+		let inner = self.inner();
+
+		let mut stats = RecvStats::default();
+		let mut lock = lock_mutex!(inner.lock);
+		let mut woken = false;
+		loop {
+			if let Poll::Ready(value) = inner.poll(&mut lock, &self.1) {
+				return (value, stats);
+			}
+
+			if woken {
+				stats.spurious_wakeups += 1;
+			}
+			rx_wait!(lock = inner, {
+				cvar_wait!(lock = inner);
+			});
+			woken = true;
+		}
+	}
+
+	/// Receive the value sent by the sending side of the thread beam like [`recv`](ThreadBeamRx::recv), along with how long it spent blocked.
+	///
+	/// The duration adds up every wait on the condvar, from when this first had to wait until it woke up for the last time, so it is zero if a value had already been sent or the sending side had already been dropped. Only available with the `metrics` feature, for watching how long receivers wait on slow-to-start workers.
+	#[cfg(feature = "metrics")]
+	pub fn recv_timed(self) -> (Option<T>, Duration) {
+		let inner = self.inner();
+
+		let mut blocked = Duration::ZERO;
+		let mut lock = lock_mutex!(inner.lock);
+		loop {
+			if let Poll::Ready(value) = inner.poll(&mut lock, &self.1) {
+				return (value, blocked);
+			}
+
+			let waiting = Instant::now();
+			rx_wait!(lock = inner, {
+				cvar_wait!(lock = inner);
+			});
+			blocked += waiting.elapsed();
+		}
+	}
+
+	/// Receive the value sent by the sending side of the thread beam like [`recv`](ThreadBeamRx::recv), along with the id of the thread that sent it.
+	///
+	/// Only available with the `debug-sender` feature, for finding out which clone of the sending side won the race to send.
+	#[cfg(feature = "debug-sender")]
+	pub fn recv_with_sender(self) -> Option<(T, std::thread::ThreadId)> {
+		let inner = self.inner();
+
+		let mut lock = lock_mutex!(inner.lock);
+		loop {
+			let sender = lock.sender;
+			if let Poll::Ready(value) = inner.poll(&mut lock, &self.1) {
+				return value.map(|value| (value, sender.expect("thread beam value sent without recording its sender")));
+			}
+
+			rx_wait!(lock = inner, {
+				cvar_wait!(lock = inner);
+			});
+		}
+	}
+
+	/// Waits for the value sent by the sending side of the thread beam, then borrows it in place instead of moving it out.
+	///
+	/// The value is dropped when the returned guard is dropped. Returns `None` if the sending side of the thread beam has been dropped.
+	///
+	/// The guard keeps the thread beam locked, so the sending side blocks if it is dropped (or sends again with [`send_streaming`](ThreadBeamTx::send_streaming)) while the guard is alive.
+	pub fn recv_ref(&self) -> Option<BeamGuard<'_, T>> {
+		let inner = self.inner();
+
+		let mut lock = lock_mutex!(inner.lock);
+		loop {
+			if let Poll::Ready(has_data) = lock.poll_ready(&self.1) {
+				return has_data.then(|| BeamGuard { inner, lock, seen: &self.1 });
+			}
+
+			rx_wait!(lock = inner, {
+				cvar_wait!(lock = inner);
+			});
+		}
+	}
+
+	/// Attempts to receive the value sent by the sending side of the thread beam without blocking.
+	///
+	/// Returns [`TryRecvError::Empty`] if nothing has been sent yet, or [`TryRecvError::Disconnected`] if the sending side of the thread beam has been dropped.
+	///
+	/// This borrows the receiving side of the thread beam rather than consuming it, so it can be polled repeatedly. A received value is moved out of the thread beam, so it's never received twice: once the value sent with [`send`](ThreadBeamTx::send) has been received, later calls, and a later [`recv`](ThreadBeamRx::recv), see the thread beam as disconnected and return `None`. After a value sent with [`send_streaming`](ThreadBeamTx::send_streaming), they get the next one sent instead.
+	pub fn try_recv(&self) -> Result<T, TryRecvError> {
+		let inner = self.inner();
+
+		// Nothing to take and nobody has hung up, so there's no need for the lock
+		if inner.flags().difference(ThreadBeamFlags::WAITING) == ThreadBeamFlags::TX | ThreadBeamFlags::RX {
+			return Err(TryRecvError::Empty);
+		}
+
+		let mut lock = lock_mutex!(inner.lock);
+		match inner.poll(&mut lock, &self.1) {
+			Poll::Ready(Some(value)) => Ok(value),
+			Poll::Ready(None) => Err(TryRecvError::Disconnected),
+			Poll::Pending => Err(TryRecvError::Empty),
+		}
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, blocking for at most `timeout`, and report exactly what happened.
+	///
+	/// This is the primitive the other timeout methods are built on. Matching on the returned [`RecvStatus`] has the compiler check that each outcome is handled.
+	///
+	/// This borrows the receiving side of the thread beam rather than consuming it, so it can be retried after [`RecvStatus::TimedOut`]. Once the value has been received, later calls return [`RecvStatus::SenderDropped`].
+	pub fn recv_status(&self, timeout: Duration) -> RecvStatus<T> {
+		// A timeout too long to be a deadline is as good as none
+		self.recv_status_until(&SystemClock, |now| now.checked_add(timeout))
+	}
+
+	// Waits until the deadline that `deadline` works out from the time `clock` gives it, or for as long as it takes if it gives `None`. The
+	// clock is only read once there turns out to be nothing to receive yet, and then once per wakeup, so the wait is cheap however often
+	// it's retried.
+	pub(super) fn recv_status_until(&self, clock: &impl Clock, deadline: impl FnOnce(Instant) -> Option<Instant>) -> RecvStatus<T> {
+		let inner = self.inner();
+
+		let mut lock = lock_mutex!(inner.lock);
+		if let Poll::Ready(value) = inner.poll(&mut lock, &self.1) {
+			return value.map_or(RecvStatus::SenderDropped, RecvStatus::Received);
+		}
+
+		let mut now = clock.now();
+		let deadline = deadline(now);
+		loop {
+			let remaining = match deadline {
+				Some(deadline) if now >= deadline => return RecvStatus::TimedOut,
+				Some(deadline) => Some(deadline - now),
+				None => None,
+			};
+			// The condvar waits in real time, so a clock that doesn't keep pace with it is read again every so often
+			let remaining = [remaining, clock.poll_interval()].into_iter().flatten().min();
+			rx_wait!(lock = inner, {
+				if let Some(remaining) = remaining {
+					cvar_wait_timeout!(lock = inner, remaining);
+				} else {
+					cvar_wait!(lock = inner);
+				}
+			});
+
+			// Condvars can wake up spuriously, so keep waiting until there's a value or a hang-up, or the deadline has passed. A value that
+			// arrives just as the deadline passes is still received.
+			if let Poll::Ready(value) = inner.poll(&mut lock, &self.1) {
+				return value.map_or(RecvStatus::SenderDropped, RecvStatus::Received);
+			}
+			now = clock.now();
+		}
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, blocking for at most `timeout`.
+	///
+	/// Returns [`RecvTimeoutError::Disconnected`] if the sending side of the thread beam has been dropped, or [`RecvTimeoutError::Timeout`] if `timeout` elapses first.
+	///
+	/// This borrows the receiving side of the thread beam rather than consuming it, so it can be retried after a timeout. Once the value has been received, later calls return [`RecvTimeoutError::Disconnected`].
+	#[inline]
+	pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+		timeout_result(self.recv_status(timeout))
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, blocking until `deadline` at the latest.
+	///
+	/// This is the same as [`recv_timeout`](ThreadBeamRx::recv_timeout), but against an absolute deadline, for coordinating several waits without recomputing a shrinking timeout. A deadline that has already passed returns [`RecvTimeoutError::Timeout`] straight away, unless the value is already there.
+	#[inline]
+	pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+		timeout_result(self.recv_status_until(&SystemClock, |_| Some(deadline)))
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, blocking for at most `timeout`, and hand the receiving side back if nothing arrives.
+	///
+	/// This is the consuming counterpart of [`recv_timeout`](ThreadBeamRx::recv_timeout), for code that passes the receiving side along by value: on [`RecvTimeoutError::Timeout`], the returned receiving side can be retried. On [`RecvTimeoutError::Disconnected`], it is handed back too but will never receive anything.
+	///
+	/// Nothing is read out of the thread beam unless a value is returned, so the receiving side handed back is always intact.
+	pub fn recv_timeout_owned(self, timeout: Duration) -> Result<T, (Self, RecvTimeoutError)> {
+		match self.recv_timeout(timeout) {
+			Ok(value) => Ok(value),
+			Err(err) => Err((self, err)),
+		}
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, unless a signal is sent down `abort` first.
+	///
+	/// If the value and the abort signal are both there by the time this looks, the value wins. If the sending side of `abort` is dropped without sending anything, nothing can abort anymore, and this just waits for the value.
+	///
+	/// The current thread is parked while waiting, and unparked by either thread beam, so this doesn't spin.
+	pub fn recv_or_abort(self, abort: ThreadBeamRx<()>) -> RecvOrAbort<T> {
+		let waker = ThreadWaker::current();
+		let mut abort = Some(abort);
+		loop {
+			match self.poll_recv(&waker) {
+				Poll::Ready(Some(value)) => return RecvOrAbort::Value(value),
+				Poll::Ready(None) => return RecvOrAbort::Disconnected,
+				Poll::Pending => {}
+			}
+			if let Some(signal) = &abort {
+				match signal.poll_recv(&waker) {
+					Poll::Ready(Some(())) => return RecvOrAbort::Aborted,
+					Poll::Ready(None) => abort = None,
+					Poll::Pending => {}
+				}
+			}
+
+			// Parking can wake up spuriously, so both beams are always checked again
+			std::thread::park();
+		}
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, giving up once `cancel` is [cancelled](CancelFlag::cancel).
+	///
+	/// The current thread is parked while waiting, and unparked either by the thread beam or by cancelling `cancel`, so this returns as soon as the thread is scheduled again, without waking up in between. A value that has already been sent is returned even if `cancel` has been cancelled.
+	///
+	/// Returns `None` if the sending side of the thread beam has been dropped, or if it was cancelled. If `cancel` still isn't cancelled afterwards, `None` always means that the sending side was dropped; otherwise, a value sent later is dropped along with the thread beam.
+	pub fn recv_cancellable(self, cancel: &CancelFlag) -> Option<T> {
+		let waker = ThreadWaker::current();
+		lock_mutex!(cancel.wakers).push(waker.clone());
+
+		let received = loop {
+			// The waker is registered with both before either is checked, so a send or cancel after the check unparks this thread
+			match self.poll_recv(&waker) {
+				Poll::Ready(value) => break value,
+				Poll::Pending if cancel.is_cancelled() => break None,
+				Poll::Pending => {}
+			}
+
+			// Parking can wake up spuriously, so both are always checked again
+			std::thread::park();
+		};
+
+		lock_mutex!(cancel.wakers).retain(|registered| !registered.will_wake(&waker));
+		received
+	}
+
+	/// Splits this receiving side into a [`RecvHandle`] to wait on, and a [`CancelGuard`] that cancels the wait once it is dropped.
+	///
+	/// Dropping the guard wakes a thread blocked in [`RecvHandle::wait`] straight away, which then returns [`RecvCancelError::Cancelled`]. A value that has already been sent by the time the waiting thread wakes up wins over cancellation, and is returned instead; a value sent after that is dropped along with the thread beam.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// let (_tx, rx) = threadbeam::channel::<String>();
+	/// let (handle, guard) = rx.recv_guarded();
+	///
+	/// # let j =
+	/// std::thread::spawn(move || {
+	///     assert_eq!(handle.wait(), Err(threadbeam::RecvCancelError::Cancelled));
+	/// });
+	///
+	/// drop(guard);
+	/// # j.join().unwrap();
+	/// ```
+	pub fn recv_guarded(self) -> (RecvHandle<T, Tag>, CancelGuard<T, Tag>) {
+		let guard = CancelGuard(self.weak());
+		(RecvHandle(self), guard)
+	}
+
+	// A weak sending side, which keeps the allocation alive without counting as a sender
+	fn weak(&self) -> WeakThreadBeamTx<T, Tag> {
+		let inner = self.inner();
+		lock_mutex!(inner.lock).downgrade_tx();
+		WeakThreadBeamTx(share(&self.0), PhantomData)
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, or compute it locally with `fallback` if it doesn't arrive within `timeout`.
+	///
+	/// `fallback` is also used if the sending side of the thread beam is dropped without sending anything. If the value arrives at the same time as the timeout elapses, the sent value is preferred. A value that arrives after `fallback` has started is dropped.
+	pub fn recv_or_compute(self, timeout: Duration, fallback: impl FnOnce() -> T) -> T {
+		match self.recv_timeout(timeout) {
+			Ok(value) => value,
+			Err(_) => fallback(),
+		}
+	}
+
+	/// Calls `f` with the value sent by the sending side of the thread beam once it arrives, instead of blocking the current thread.
+	///
+	/// `f` is called on a newly spawned helper thread. It is given `None` if the sending side of the thread beam is dropped without sending anything.
+	pub fn on_value<F>(self, f: F)
+	where
+		F: FnOnce(Option<T>) + Send + 'static,
+		T: 'static,
+		Tag: 'static,
+	{
+		std::thread::spawn(move || f(self.recv()));
+	}
+
+	/// Converts the receiving side of the thread beam into a [`std::sync::mpsc::Receiver`].
+	///
+	/// The returned receiver yields the value sent by the sending side of the thread beam, if any, and then reports that it is disconnected.
+	///
+	/// If the value has already been sent, or the sending side has already been dropped, no thread is needed. Otherwise, a helper thread is spawned to forward the value once it arrives.
+	pub fn into_mpsc(self) -> std::sync::mpsc::Receiver<T>
+	where
+		T: 'static,
+		Tag: 'static,
+	{
+		let (tx, rx) = std::sync::mpsc::channel();
+		match self.try_recv() {
+			Ok(value) => {
+				let _ = tx.send(value);
+			}
+			Err(TryRecvError::Disconnected) => {}
+			Err(TryRecvError::Empty) => {
+				std::thread::spawn(move || {
+					if let Some(value) = self.recv() {
+						let _ = tx.send(value);
+					}
+				});
+			}
+		}
+		rx
+	}
+
+	/// Makes any value left in this thread beam when it is torn down get dropped on `drop_thread` instead of inline.
+	///
+	/// Use this when the value's `Drop` is slow or blocks.
+	pub fn defer_drops(self, drop_thread: &DropThread) -> Self
+	where
+		T: 'static,
+	{
+		{
+			let inner = self.inner();
+			lock_mutex!(inner.lock).deferred_drop = Some(DeferredDrop {
+				tx: drop_thread.tx.clone(),
+				boxed: |value| Box::new(value),
+			});
+		}
+		self
+	}
+
+	/// Returns a clone of the value sent by the sending side of the thread beam, without receiving it.
+	///
+	/// Returns `None` if nothing has been sent yet, or if the sending side of the thread beam was dropped without sending anything. This never blocks waiting for a value, and the value is left in place for a later call to [`recv`](ThreadBeamRx::recv).
+	#[inline]
+	pub fn peek(&self) -> Option<T>
+	where
+		T: Clone,
+	{
+		self.peek_with(T::clone)
+	}
+
+	/// Calls `f` with a reference to the value sent by the sending side of the thread beam, without receiving it or cloning it.
+	///
+	/// Returns `None` without calling `f` if nothing has been sent yet, or if the sending side of the thread beam was dropped without sending anything. This never blocks waiting for a value, but the thread beam stays locked while `f` runs.
+	///
+	/// Like [`Mutex`], this only needs `T: Send`: the reference can't escape `f`, and nothing else can touch the value until `f` returns.
+	pub fn peek_with<R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
+		let inner = self.inner();
+		lock_mutex!(inner.lock).peek_data().map(f)
+	}
+
+	/// Checks the thread beam once without blocking.
+	///
+	/// Returns `Poll::Ready(Some(value))` if a value has been sent, or `Poll::Ready(None)` if the sending side of the thread beam has been dropped.
+	///
+	/// Otherwise, `register` is called and `Poll::Pending` is returned. `register` is called after the lock has been released, so it may safely touch the thread beam again. The thread beam does not wake anything by itself, so `register` should arrange for this method to be called again later, e.g. on the next tick of an event loop.
+	pub fn poll_once(&self, register: impl FnOnce()) -> Poll<Option<T>> {
+		let inner = self.inner();
+
+		if inner.flags().difference(ThreadBeamFlags::WAITING) != ThreadBeamFlags::TX | ThreadBeamFlags::RX {
+			let mut lock = lock_mutex!(inner.lock);
+			if let Poll::Ready(value) = inner.poll(&mut lock, &self.1) {
+				return Poll::Ready(value);
+			}
+		}
+
+		register();
+		Poll::Pending
+	}
+
+	pub(crate) fn poll_recv(&self, waker: &core::task::Waker) -> Poll<Option<T>> {
+		let inner = self.inner();
+
+		let mut lock = lock_mutex!(inner.lock);
+		let poll = inner.poll(&mut lock, &self.1);
+		if poll.is_pending() {
+			// Registering under the same lock as the check above means a send or hang-up can't slip in between them unnoticed
+			lock.register_waker(waker);
+		}
+		poll
+	}
+
+	/// Returns whether a value has been sent and is waiting to be received.
+	///
+	/// This reads a snapshot of the thread beam's flags and never touches its lock, so it doesn't contend with threads sending or waiting on it. Once this returns `true`, the value stays put until it is received, so a following [`try_recv`](ThreadBeamRx::try_recv) is guaranteed to get it, unless a clone of this receiving side gets there first.
+	#[inline]
+	pub fn is_ready(&self) -> bool {
+		let inner = self.inner();
+		inner.flags().contains(ThreadBeamFlags::HAS_DATA)
+	}
+
+	/// Returns whether the sending side of the thread beam has been dropped.
+	///
+	/// A value sent before the sending side was dropped can still be received. This never blocks for long, but the answer is only advisory: the sending side may be dropped immediately after this returns `false`.
+	pub fn is_disconnected(&self) -> bool {
+		let inner = self.inner();
+		!inner.flags().contains(ThreadBeamFlags::TX)
+	}
+
+	/// Returns whether there's nothing left to receive and never will be: the sending side of the thread beam has been dropped, and any value it sent has been received.
+	///
+	/// This is when [`try_recv`](ThreadBeamRx::try_recv) returns [`TryRecvError::Disconnected`] rather than [`TryRecvError::Empty`], for ending a non-blocking streaming drain. While the sending side is alive, or once nothing is left in the thread beam, this reads a snapshot of the thread beam's flags and never touches its lock. The lock is only taken for a moment to check whether a value left behind by the sending side has already been received by this clone of the receiving side.
+	pub fn is_complete(&self) -> bool {
+		let inner = self.inner();
+		let flags = inner.flags();
+		if flags.contains(ThreadBeamFlags::TX | ThreadBeamFlags::RX) {
+			false
+		} else if !flags.contains(ThreadBeamFlags::HAS_DATA) {
+			true
+		} else {
+			lock_mutex!(inner.lock).poll_ready(&self.1) == Poll::Ready(false)
+		}
+	}
+
+	/// Returns the id of this thread beam, as given to the hook set by [`set_unconsumed_hook`](crate::set_unconsumed_hook).
+	#[cfg(feature = "debug-unconsumed")]
+	pub fn id(&self) -> usize {
+		let inner = self.inner();
+		lock_mutex!(inner.lock).id
+	}
+}
+impl<T: Send, Tag> core::fmt::Debug for ThreadBeamRx<T, Tag> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		let inner = self.inner();
+		super::fmt_state("ThreadBeamRx", try_lock_mutex!(inner.lock).as_deref(), f)
+	}
+}
+impl<T: Send, Tag> ThreadBeamRx<T, Tag> {
+	/// Disconnects the receiving side of the thread beam without dropping it, as if every receiving side had been dropped.
+	///
+	/// Every later send fails, and a sending side waiting for the previous value to be received is woken with an error. A value that has already been sent can still be received. Dropping the receiving side afterwards only frees the thread beam, if it's the last side left.
+	pub fn close(&self) {
+		let inner = self.inner();
+
+		let mut lock = lock_mutex!(inner.lock);
+		lock.close_rx();
+		drop(lock.take_waker());
+
+		inner.changed(&lock);
+	}
+
+	/// Drops a value that has been sent but not received straight away, on this thread, then disconnects the receiving side of the thread beam.
+	///
+	/// Just dropping the receiving side leaves a sent value in the thread beam until the sending side is dropped too, so its destructor runs on whichever thread drops the last side, whenever that is. Draining runs it now, and like [`close`](ThreadBeamRx::close), makes every later send fail, so no other value can take its place. Returns whether there was a value to drop.
+	///
+	/// With the `broadcast` feature, for a cloned receiving side, the value is only dropped if every other clone has already received it, and the thread beam is only disconnected if this is the last clone left. Otherwise, draining is the same as dropping it.
+	pub fn drain(self) -> bool {
+		let value = {
+			let inner = self.inner();
+
+			let mut lock = lock_mutex!(inner.lock);
+			// A clone of a broadcast receiving side only drops the value if the others have all had their copies, and only closes the thread
+			// beam if it's the last one left; otherwise it just lets go of its share of both when it's dropped below
+			let value = lock.holds_original(&self.1).then(|| lock.take_data());
+			if lock.last_receiver() {
+				lock.close_rx();
+				drop(lock.take_waker());
+			}
+
+			inner.changed(&lock);
+			value
+		};
+
+		// Outside the lock, in case the destructor takes a while
+		value.is_some()
+	}
+}
+impl<T: Send, Tag> Drop for ThreadBeamRx<T, Tag> {
+	fn drop(&mut self) {
+		let deallocate = {
+			let inner = self.inner();
+
+			let mut lock = lock_mutex!(inner.lock);
+			let deallocate = lock.drop_rx(&self.1);
+
+			// Nothing is waiting on this side anymore
+			if !lock.rx_alive() {
+				drop(lock.take_waker());
+			}
+
+			inner.changed(&lock);
+
+			deallocate
+		};
+		if deallocate {
+			deallocate!(self.0);
+		}
+	}
+}
+
+/// Cloning the sending side of a thread beam lets several producers race to send its value.
+///
+/// The first value sent wins: later sends from any clone return their value back in a [`SendError`] while the first one is still waiting to be received, and after [`recv`](ThreadBeamRx::recv) has consumed the receiving side. The receiving side only sees the thread beam as disconnected once every clone has been dropped, and the allocation is freed once every clone and the receiving side are gone.
+impl<T: Send, Tag> Clone for ThreadBeamTx<T, Tag> {
+	fn clone(&self) -> Self {
+		let inner = self.inner();
+		lock_mutex!(inner.lock).clone_tx();
+		ThreadBeamTx(share(&self.0), PhantomData)
+	}
+}
+
+/// Cloning the receiving side of a thread beam broadcasts the value sent by the sending side to every clone. Only available with the `broadcast` feature.
+///
+/// Each receiving side that is still alive when it receives gets a clone of the value, except the last one, which gets the original. Receiving sides that are dropped without receiving simply aren't counted anymore.
+///
+/// Each clone receives each value once, however it receives: the value stays in the thread beam until every receiving side has had it, and receiving it again through a borrowing method such as [`try_recv`](ThreadBeamRx::try_recv) or [`recv_keep`](ThreadBeamRx::recv_keep) finds nothing, just as if the original had been taken. Only one clone at a time can wait asynchronously or in [`recv_any`].
+#[cfg(feature = "broadcast")]
+impl<T: Send + Clone, Tag> Clone for ThreadBeamRx<T, Tag> {
+	fn clone(&self) -> Self {
+		let inner = self.inner();
+		lock_mutex!(inner.lock).clone_rx();
+		ThreadBeamRx(share(&self.0), Seen::new(), PhantomData)
+	}
+}
+
+/// How a wait went, as reported by [`ThreadBeamRx::recv_with_stats`].
+#[cfg(feature = "debug-stats")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RecvStats {
+	/// How many times the receiving side woke up without a value having been sent or the sending side having been dropped.
+	pub spurious_wakeups: usize,
+}
+
+/// What happened while waiting with [`ThreadBeamRx::recv_or_abort`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[must_use = "the beamed value is discarded if not used"]
+pub enum RecvOrAbort<T> {
+	/// The value sent by the sending side of the thread beam.
+	Value(T),
+
+	/// The abort signal was sent before the value.
+	Aborted,
+
+	/// The sending side of the thread beam was dropped without sending a value.
+	Disconnected,
+}
+
+/// A flag for cancelling [`ThreadBeamRx::recv_cancellable`], which wakes every thread waiting on it as it's cancelled.
+///
+/// Unlike a bare `AtomicBool`, cancelling notifies the waiting threads, so they don't have to keep checking the flag. The flag can be shared by any number of waits, on any number of thread beams.
+#[derive(Default)]
+pub struct CancelFlag {
+	cancelled: AtomicBool,
+	wakers: Mutex<Vec<std::task::Waker>>,
+}
+impl CancelFlag {
+	/// Creates a new flag that hasn't been cancelled.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Cancels every wait on this flag, now and later, until it's [`reset`](CancelFlag::reset).
+	pub fn cancel(&self) {
+		// Set under the lock, so a wait registering itself either sees the flag or is woken here
+		let wakers = {
+			let mut wakers = lock_mutex!(self.wakers);
+			self.cancelled.store(true, Ordering::Release);
+			core::mem::take(&mut *wakers)
+		};
+		for waker in wakers {
+			waker.wake();
+		}
+	}
+
+	/// Returns whether the flag has been cancelled.
+	#[inline]
+	pub fn is_cancelled(&self) -> bool {
+		self.cancelled.load(Ordering::Acquire)
+	}
+
+	/// Clears the flag, so that later waits on it aren't cancelled until it's cancelled again.
+	#[inline]
+	pub fn reset(&self) {
+		self.cancelled.store(false, Ordering::Release);
+	}
+}
+impl core::fmt::Debug for CancelFlag {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.debug_struct("CancelFlag")
+			.field("cancelled", &self.is_cancelled())
+			.finish_non_exhaustive()
+	}
+}
+
+/// A value received with [`ThreadBeamRx::recv_ref`], borrowed in place.
+///
+/// The value is dropped along with the guard. Like a [`MutexGuard`], the guard gives out `&T`, so it can only be shared between threads if `T: Sync`.
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>(_: &T) {}
+///
+/// let (tx, rx) = threadbeam::channel();
+/// tx.send(std::cell::Cell::new(42)).unwrap();
+/// assert_sync(&rx.recv_ref().unwrap());
+/// ```
+pub struct BeamGuard<'a, T: Send> {
+	inner: &'a ThreadBeamInner<T>,
+	lock: MutexGuard<'a, ThreadBeamState<T>>,
+	seen: &'a Seen,
+}
+impl<T: Send> core::ops::Deref for BeamGuard<'_, T> {
+	type Target = T;
+
+	#[inline]
+	fn deref(&self) -> &T {
+		// Guards are only handed out while there is data, and only dropping the guard takes it
+		self.lock.data_ref()
+	}
+}
+impl<T: Send> Drop for BeamGuard<'_, T> {
+	fn drop(&mut self) {
+		drop(self.lock.recv_data(self.seen));
+		self.inner.changed(&self.lock);
+	}
+}
+
+/// A token for a value sent with [`ThreadBeamTx::send_and_keep_alive`], which completes the thread beam once it is dropped or [`finish`](CompletionToken::finish)ed.
+///
+/// Completing is the sending side of the thread beam hanging up: there is no separate flag, so a worker that panics while holding the token completes it as it unwinds, and the receiving side sees the thread beam as disconnected afterwards, just as if the sending side had been dropped.
+pub struct CompletionToken<T: Send, Tag = ()>(ThreadBeamTx<T, Tag>);
+impl<T: Send, Tag> CompletionToken<T, Tag> {
+	/// Returns whether the value has been received by the receiving side of the thread beam.
+	///
+	/// This never blocks.
+	pub fn is_consumed(&self) -> bool {
+		let inner = self.0.inner();
+		!inner.flags().contains(ThreadBeamFlags::HAS_DATA)
+	}
+
+	/// Completes the thread beam, waking a receiving side waiting in [`wait_for_completion`](ThreadBeamRx::wait_for_completion).
+	///
+	/// This is the same as dropping the token, but says so.
+	#[inline]
+	pub fn finish(self) {
+		drop(self);
+	}
+}
+
+/// The receiving half of [`ThreadBeamRx::recv_guarded`], which waits until its [`CancelGuard`] is dropped at the latest.
+#[must_use = "dropping the receiving side of a thread beam makes every send to it fail"]
+pub struct RecvHandle<T: Send, Tag = ()>(ThreadBeamRx<T, Tag>);
+impl<T: Send, Tag> RecvHandle<T, Tag> {
+	/// Receive the value sent by the sending side of the thread beam, unless the [`CancelGuard`] is dropped first.
+	///
+	/// Returns [`RecvCancelError::Cancelled`] if the guard was dropped before a value was sent, or [`RecvCancelError::Disconnected`] if the sending side of the thread beam was dropped without sending a value.
+	pub fn wait(self) -> Result<T, RecvCancelError> {
+		let inner = self.0.inner();
+
+		let mut lock = lock_mutex!(inner.lock);
+		loop {
+			// A value wins over cancellation, and cancellation wins over the sending side hanging up
+			match inner.poll(&mut lock, &self.0 .1) {
+				Poll::Ready(Some(value)) => return Ok(value),
+				_ if lock.flags.contains(ThreadBeamFlags::CANCELLED) => return Err(RecvCancelError::Cancelled),
+				Poll::Ready(None) => return Err(RecvCancelError::Disconnected),
+				Poll::Pending => {}
+			}
+
+			rx_wait!(lock = inner, {
+				cvar_wait!(lock = inner);
+			});
+		}
+	}
+}
+impl<T: Send, Tag> core::fmt::Debug for RecvHandle<T, Tag> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.debug_tuple("RecvHandle").field(&self.0).finish()
+	}
+}
+
+/// The cancelling half of [`ThreadBeamRx::recv_guarded`], which cancels the wait in its [`RecvHandle`] once it is dropped.
+#[must_use = "dropping the guard cancels the receive straight away"]
+pub struct CancelGuard<T: Send, Tag = ()>(WeakThreadBeamTx<T, Tag>);
+impl<T: Send, Tag> CancelGuard<T, Tag> {
+	/// Cancels the wait in the [`RecvHandle`].
+	///
+	/// This is the same as dropping the guard, but says so.
+	#[inline]
+	pub fn cancel(self) {
+		drop(self);
+	}
+}
+impl<T: Send, Tag> core::fmt::Debug for CancelGuard<T, Tag> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.debug_struct("CancelGuard").finish_non_exhaustive()
+	}
+}
+impl<T: Send, Tag> Drop for CancelGuard<T, Tag> {
+	fn drop(&mut self) {
+		let inner = self.0.inner();
+		let mut lock = lock_mutex!(inner.lock);
+		lock.flags |= ThreadBeamFlags::CANCELLED;
+		inner.changed(&lock);
+	}
+}
+
+/// The thread beam's slot, reserved with [`ThreadBeamTx::reserve`] for constructing the value in place.
+#[cfg(not(feature = "safe"))]
+#[must_use = "dropping the writer without committing disconnects its sending side"]
+pub struct SlotWriter<T: Send, Tag = ()>(ThreadBeamTx<T, Tag>);
+#[cfg(not(feature = "safe"))]
+impl<T: Send, Tag> SlotWriter<T, Tag> {
+	/// Calls `f` with the slot, locked for as long as `f` runs, to write the value into it in place.
+	///
+	/// This can be called any number of times to write the value bit by bit. Until the value is committed, nothing reads the slot, and nothing in it is dropped.
+	pub fn with_slot<R>(&mut self, f: impl FnOnce(&mut MaybeUninit<T>) -> R) -> R {
+		let inner = self.0.inner();
+		f(&mut lock_mutex!(inner.lock).data)
+	}
+
+	/// Sends the value written into the slot to the receiving side of the thread beam.
+	///
+	/// If the receiving side has been dropped in the meantime, the value is dropped along with the thread beam instead.
+	///
+	/// # Safety
+	///
+	/// The slot must have been fully initialized with [`with_slot`](SlotWriter::with_slot).
+	pub unsafe fn commit(self) {
+		let waker = {
+			let inner = self.0.inner();
+
+			let mut lock = lock_mutex!(inner.lock);
+			lock.commit_reserved();
+
+			inner.changed(&lock);
+
+			lock.take_waker()
+		};
+		if let Some(waker) = waker {
+			waker.wake();
+		}
+	}
+
+	/// Writes `value` into the slot and sends it to the receiving side of the thread beam.
+	#[inline]
+	pub fn write(mut self, value: T) {
+		self.with_slot(|slot| {
+			slot.write(value);
+		});
+		// Safety: the slot was just written
+		unsafe { self.commit() };
+	}
+}
+#[cfg(not(feature = "safe"))]
+impl<T: Send, Tag> core::fmt::Debug for SlotWriter<T, Tag> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.debug_tuple("SlotWriter").field(&self.0).finish()
+	}
+}
+#[cfg(not(feature = "safe"))]
+impl<T: Send, Tag> Drop for SlotWriter<T, Tag> {
+	fn drop(&mut self) {
+		// Lets go of the slot if the value was never committed; the sending side is dropped (and the receiving side told) right after
+		let inner = self.0.inner();
+		let mut lock = lock_mutex!(inner.lock);
+		lock.flags &= !ThreadBeamFlags::RESERVED;
+		inner.publish(&lock);
+	}
+}
+
+/// A receipt for a value sent with [`ThreadBeamTx::send_with_receipt`].
+pub struct SendReceipt<T: Send, Tag = ()>(WeakThreadBeamTx<T, Tag>);
+impl<T: Send, Tag> SendReceipt<T, Tag> {
+	/// Returns whether the value has been received by the receiving side of the thread beam.
+	///
+	/// This never blocks.
+	pub fn is_consumed(&self) -> bool {
+		let inner = self.0.inner();
+		!inner.flags().contains(ThreadBeamFlags::HAS_DATA)
+	}
+
+	/// Blocks until the value has been received by the receiving side of the thread beam.
+	///
+	/// Returns `false` if the receiving side of the thread beam was dropped without receiving the value.
+	pub fn wait_consumed(&self) -> bool {
+		let inner = self.0.inner();
+
+		// The sending side is long gone, so only the receiving side letting go of the thread beam can end the wait early
+		let mut lock = lock_mutex!(inner.lock);
+		while lock.has_data() && lock.rx_alive() {
+			counted_wait!(lock, {
+				cvar_wait!(lock = inner);
+			});
+		}
+
+		!lock.has_data()
+	}
+}
+
+/// A one-shot thread beam that needs no allocation, so it can be declared in a `static`.
+///
+/// This is single-producer single-consumer: one thread [`set`](OnceBeam::set)s the value and one thread [`wait`](OnceBeam::wait)s for it. Only the first `set` succeeds; any later one gets its value back in a [`SendError`]. Only the first `wait` receives the value; any later one returns `None`.
+///
+/// There is no sending side to drop, so `wait` blocks forever if the value is never set.
+///
+/// # Example
+///
+/// ```rust
+/// static READY: threadbeam::OnceBeam<String> = threadbeam::OnceBeam::new();
+///
+/// std::thread::spawn(|| {
+///     READY.set(String::from("Hello, world!")).unwrap();
+/// });
+///
+/// let hello = READY.wait();
+/// assert_eq!(hello.as_deref(), Some("Hello, world!"));
+/// ```
+pub struct OnceBeam<T: Send> {
+	// `None` until set, then `Some(None)` once the value has been received
+	lock: Mutex<Option<Option<T>>>,
+	#[cfg(not(feature = "spin"))]
+	cvar: Condvar,
+}
+impl<T: Send> OnceBeam<T> {
+	/// Creates a new, empty once beam.
+	pub const fn new() -> Self {
+		Self {
+			lock: Mutex::new(None),
+			#[cfg(not(feature = "spin"))]
+			cvar: Condvar::new(),
+		}
+	}
+
+	/// Set the value of the once beam, waking the thread waiting for it.
+	///
+	/// Returns the value back in a [`SendError`] if the once beam has already been set, even if that value has since been received.
+	pub fn set(&self, value: T) -> Result<(), SendError<T>> {
+		let mut slot = lock_mutex!(self.lock);
+		if slot.is_some() {
+			return Err(SendError(value));
+		}
+		*slot = Some(Some(value));
+		#[cfg(not(feature = "spin"))]
+		self.cvar.notify_all();
+		Ok(())
+	}
+
+	/// Receive the value of the once beam, blocking until it is set.
+	///
+	/// Returns `None` if the value has already been received by an earlier call.
+	pub fn wait(&self) -> Option<T> {
+		let mut slot = lock_mutex!(self.lock);
+		loop {
+			if let Some(value) = &mut *slot {
+				return value.take();
+			}
+			cvar_wait!(slot = self);
+		}
+	}
+
+	/// Returns whether the once beam has been set.
+	///
+	/// This never blocks for long, and stays `true` once the value has been received.
+	pub fn is_set(&self) -> bool {
+		lock_mutex!(self.lock).is_some()
+	}
+}
+impl<T: Send> Default for OnceBeam<T> {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// A background thread that drops values discarded by thread beams.
+///
+/// See [`ThreadBeamRx::defer_drops`].
+///
+/// Discarded values are dropped one at a time, in the order their thread beams were torn down. Dropping the `DropThread` waits for all of the values sent to it so far to be dropped.
+pub struct DropThread {
+	tx: Option<std::sync::mpsc::Sender<Box<dyn Send>>>,
+	thread: Option<std::thread::JoinHandle<()>>,
+}
+impl DropThread {
+	/// Spawns a new background thread for dropping values.
+	pub fn new() -> Self {
+		let (tx, rx) = std::sync::mpsc::channel::<Box<dyn Send>>();
+		let thread = std::thread::spawn(move || rx.into_iter().for_each(drop));
+		Self {
+			tx: Some(tx),
+			thread: Some(thread),
+		}
+	}
+}
+impl Default for DropThread {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+impl Drop for DropThread {
+	fn drop(&mut self) {
+		drop(self.tx.take());
+		if let Some(thread) = self.thread.take() {
+			let _ = thread.join();
+		}
+	}
+}
+
+pub(super) struct DeferredDrop<T> {
+	tx: Option<std::sync::mpsc::Sender<Box<dyn Send>>>,
+	boxed: fn(T) -> Box<dyn Send>,
+}
+impl<T> DeferredDrop<T> {
+	pub(super) fn defer(self, value: T) {
+		if let Some(tx) = &self.tx {
+			// If the drop thread is gone, the value comes back and is dropped here instead
+			let _ = tx.send((self.boxed)(value));
+		}
+	}
+}
+
+/// Creates a new thread beam channel pair.
+///
+/// Also see [spawn] for a more convenient way to spawn a thread with a thread beam.
+///
+/// Zero-sized values, such as `()` used as a readiness signal, take up no space in the thread beam, and sending or receiving them only flips a flag. The mutex and condvar shared by both sides still need one heap allocation per channel.
+///
+/// # Example
+///
+/// ```rust
+/// let (tx, rx) = threadbeam::channel();
+///
+/// # let j =
+/// std::thread::spawn(move || {
+///     tx.send(String::from("Hello, world!")).unwrap();
+/// });
+///
+/// let hello = rx.recv();
+/// assert_eq!(hello.as_deref(), Some("Hello, world!"));
+///
+/// # j.join().unwrap();
+/// ```
+#[must_use = "a thread beam does nothing unless both of its sides are used"]
+pub fn channel<T: Send>() -> (ThreadBeamTx<T>, ThreadBeamRx<T>) {
+	split(ThreadBeamInner::new())
+}
+
+/// Creates a new thread beam channel pair, returning an [`AllocError`] instead of aborting if the allocation fails.
+///
+/// # Example
+///
+/// ```rust
+/// let Ok((tx, rx)) = threadbeam::try_channel() else {
+///     return; // Out of memory, so skip the work instead of aborting
+/// };
+///
+/// # let j =
+/// std::thread::spawn(move || {
+///     tx.send(String::from("Hello, world!")).unwrap();
+/// });
+///
+/// let hello = rx.recv();
+/// assert_eq!(hello.as_deref(), Some("Hello, world!"));
+/// # j.join().unwrap();
+/// ```
+#[cfg(not(feature = "safe"))]
+#[must_use = "a thread beam does nothing unless both of its sides are used"]
+pub fn try_channel<T: Send>() -> Result<(ThreadBeamTx<T>, ThreadBeamRx<T>), AllocError> {
+	super::try_box(ThreadBeamInner::unboxed()).map(split)
+}
+
+/// A pool of thread beam allocations, for creating many short-lived thread beams without allocating each time.
+///
+/// Thread beams created with [`channel`](ThreadBeamPool::channel) return their allocation to the pool once both of their sides have been dropped, and any value left in them is dropped then. Clones of the pool share the same allocations.
+///
+/// Only available with the `pool` feature.
+///
+/// # Example
+///
+/// ```rust
+/// let pool = threadbeam::ThreadBeamPool::new();
+///
+/// for i in 0..3 {
+///     let (tx, rx) = pool.channel();
+/// #   let j =
+///     std::thread::spawn(move || tx.send(i).unwrap());
+///     assert_eq!(rx.recv(), Some(i));
+/// #   j.join().unwrap();
+/// }
+///
+/// assert_eq!(pool.idle(), 1);
+/// ```
+#[cfg(feature = "pool")]
+pub struct ThreadBeamPool<T: Send>(std::sync::Arc<FreeList<T>>);
+#[cfg(feature = "pool")]
+impl<T: Send> ThreadBeamPool<T> {
+	/// Creates a new, empty pool.
+	pub fn new() -> Self {
+		Self(std::sync::Arc::new(Mutex::new(Vec::new())))
+	}
+
+	/// Creates a new thread beam channel pair, reusing an idle allocation from the pool if there is one.
+	#[must_use = "a thread beam does nothing unless both of its sides are used"]
+	pub fn channel(&self) -> (ThreadBeamTx<T>, ThreadBeamRx<T>) {
+		let recycled = lock_mutex!(self.0).pop();
+		let inner = match recycled {
+			Some(inner) => {
+				inner.reset();
+				inner
+			}
+			None => ThreadBeamInner::pooled(std::sync::Arc::downgrade(&self.0)),
+		};
+		split(inner)
+	}
+
+	/// Returns the number of idle allocations in the pool, waiting to be reused.
+	pub fn idle(&self) -> usize {
+		lock_mutex!(self.0).len()
+	}
+}
+#[cfg(feature = "pool")]
+impl<T: Send> Clone for ThreadBeamPool<T> {
+	#[inline]
+	fn clone(&self) -> Self {
+		Self(self.0.clone())
+	}
+}
+#[cfg(feature = "pool")]
+impl<T: Send> Default for ThreadBeamPool<T> {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[inline]
+#[must_use = "the beamed value is discarded if not used"]
+/// Helper for spawning a new thread with a beam.
+///
+/// This waits on the receiving side of the thread beam itself, so the closure's [`ThreadBeamTx::is_disconnected`] never returns `true`. To let the worker bail out early when its value is no longer wanted, use [spawn_checked] instead.
+///
+/// # Example
+///
+/// ```rust
+/// let (hello, thread) = threadbeam::spawn(move |tx| {
+///     tx.send(String::from("Hello, world!")).unwrap();
+///     // your code...
+///     String::from("Thread completed!")
+/// });
+///
+/// assert_eq!(hello.as_deref(), Some("Hello, world!"));
+/// assert_eq!(thread.join().ok().as_deref(), Some("Thread completed!"));
+/// ```
+pub fn spawn<T, R, F>(spawn: F) -> (Option<T>, std::thread::JoinHandle<R>)
+where
+	F: FnOnce(ThreadBeamTx<T>) -> R,
+	F: Send + 'static,
+	T: Send + 'static,
+	R: Send + 'static,
+{
+	let (tx, rx) = channel();
+	let join = std::thread::spawn(move || spawn(tx));
+	(rx.recv(), join)
+}
+
+/// Helper for spawning a new thread with a beam, handing the receiving side back to the caller instead of waiting on it.
+///
+/// If the receiving side is dropped before the thread gets to run the closure, the closure is skipped and the thread returns `None`. Once running, the closure can check [`ThreadBeamTx::is_disconnected`] itself to bail out of expensive work early.
+///
+/// # Example
+///
+/// ```rust
+/// let (rx, thread) = threadbeam::spawn_checked(move |tx| {
+///     // expensive setup...
+///     if tx.is_disconnected() {
+///         return;
+///     }
+///     let _ = tx.send(String::from("Hello, world!"));
+/// });
+///
+/// // No longer interested in the value
+/// drop(rx);
+///
+/// thread.join().unwrap();
+/// ```
+pub fn spawn_checked<T, R, F>(spawn: F) -> (ThreadBeamRx<T>, std::thread::JoinHandle<Option<R>>)
+where
+	F: FnOnce(ThreadBeamTx<T>) -> R,
+	F: Send + 'static,
+	T: Send + 'static,
+	R: Send + 'static,
+{
+	let (tx, rx) = channel();
+	let join = std::thread::spawn(move || if tx.is_disconnected() { None } else { Some(spawn(tx)) });
+	(rx, join)
+}
+
+/// Helper for spawning a new thread with a beam, handing the receiving side back to the caller instead of waiting on it.
+///
+/// Unlike [spawn], this doesn't block, so the caller decides when (and whether) to receive. Unlike [spawn_checked], the closure always runs, even if the receiving side has already been dropped.
+///
+/// # Example
+///
+/// ```rust
+/// let (rx, thread) = threadbeam::spawn_manual(move |tx| {
+///     tx.send(String::from("Hello, world!")).unwrap();
+///     // your code...
+///     String::from("Thread completed!")
+/// });
+///
+/// // other work...
+///
+/// assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+/// assert_eq!(thread.join().ok().as_deref(), Some("Thread completed!"));
+/// ```
+pub fn spawn_manual<T, R, F>(spawn: F) -> (ThreadBeamRx<T>, std::thread::JoinHandle<R>)
+where
+	F: FnOnce(ThreadBeamTx<T>) -> R,
+	F: Send + 'static,
+	T: Send + 'static,
+	R: Send + 'static,
+{
+	let (tx, rx) = channel();
+	let join = std::thread::spawn(move || spawn(tx));
+	(rx, join)
+}
+
+/// Helper for spawning a new thread with a beam, catching a panic in the closure and handing its payload back instead of `None`.
+///
+/// Returns `Ok(Some(value))` if the closure beamed a value, `Ok(None)` if it finished without beaming anything, or `Err(payload)` if it panicked first. The closure's sending side is dropped as it unwinds, like any other, so nothing is left waiting on it. The thread itself returns `None` if the closure panicked.
+///
+/// If the closure drops its sending side without sending, this waits for the closure to finish in order to tell whether it panicked. The closure is run as if it were [`UnwindSafe`](std::panic::UnwindSafe), just like the closure given to [`std::thread::spawn`].
+///
+/// # Example
+///
+/// ```rust
+/// let (hello, thread) = threadbeam::spawn_catching(move |tx: threadbeam::ThreadBeamTx<String>| {
+///     panic!("Oh no!");
+/// });
+///
+/// assert_eq!(hello.unwrap_err().downcast_ref::<&str>(), Some(&"Oh no!"));
+/// assert_eq!(thread.join().ok(), Some(None::<()>));
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn spawn_catching<T, R, F>(
+	spawn: F,
+) -> (
+	Result<Option<T>, Box<dyn std::any::Any + Send + 'static>>,
+	std::thread::JoinHandle<Option<R>>,
+)
+where
+	F: FnOnce(ThreadBeamTx<T>) -> R,
+	F: Send + 'static,
+	T: Send + 'static,
+	R: Send + 'static,
+{
+	let (tx, rx) = channel();
+	let (panic_tx, panic_rx) = channel();
+	let join = std::thread::spawn(move || match std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || spawn(tx))) {
+		Ok(result) => Some(result),
+		Err(panic) => {
+			let _ = panic_tx.send(panic);
+			None
+		}
+	});
+
+	let value = match rx.recv() {
+		Some(value) => Ok(Some(value)),
+		None => panic_rx.recv().map_or(Ok(None), Err),
+	};
+	(value, join)
+}
+
+/// Helper for spawning a new thread with a beam, using `builder` to configure the thread.
+///
+/// Returns an error if the thread could not be spawned, rather than panicking like [spawn].
+///
+/// # Example
+///
+/// ```rust
+/// let builder = std::thread::Builder::new().stack_size(8 * 1024 * 1024);
+///
+/// let (hello, thread) = threadbeam::spawn_builder(builder, move |tx| {
+///     tx.send(String::from("Hello, world!")).unwrap();
+///     // your code...
+///     String::from("Thread completed!")
+/// })
+/// .unwrap();
+///
+/// assert_eq!(hello.as_deref(), Some("Hello, world!"));
+/// assert_eq!(thread.join().ok().as_deref(), Some("Thread completed!"));
+/// ```
+pub fn spawn_builder<T, R, F>(builder: std::thread::Builder, spawn: F) -> std::io::Result<(Option<T>, std::thread::JoinHandle<R>)>
+where
+	F: FnOnce(ThreadBeamTx<T>) -> R,
+	F: Send + 'static,
+	T: Send + 'static,
+	R: Send + 'static,
+{
+	let (tx, rx) = channel();
+	let join = builder.spawn(move || spawn(tx))?;
+	Ok((rx.recv(), join))
+}
+
+#[inline]
+/// Helper for spawning a new named thread with a beam.
+///
+/// See [spawn_builder].
+///
+/// # Example
+///
+/// ```rust
+/// let (name, thread) = threadbeam::spawn_named("worker", move |tx| {
+///     tx.send(std::thread::current().name().map(String::from)).unwrap();
+/// })
+/// .unwrap();
+///
+/// assert_eq!(name.flatten().as_deref(), Some("worker"));
+/// # thread.join().unwrap();
+/// ```
+pub fn spawn_named<T, R, F>(name: impl Into<String>, spawn: F) -> std::io::Result<(Option<T>, std::thread::JoinHandle<R>)>
+where
+	F: FnOnce(ThreadBeamTx<T>) -> R,
+	F: Send + 'static,
+	T: Send + 'static,
+	R: Send + 'static,
+{
+	spawn_builder(std::thread::Builder::new().name(name.into()), spawn)
+}
+
+/// Which thread dropped its sending side without sending a value, as reported by [`spawn_diagnostic`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisconnectInfo {
+	name: Option<String>,
+	id: std::thread::ThreadId,
+}
+impl DisconnectInfo {
+	/// Returns the name of the thread, if it was given one.
+	#[inline]
+	pub fn name(&self) -> Option<&str> {
+		self.name.as_deref()
+	}
+
+	/// Returns the id of the thread.
+	#[inline]
+	pub fn id(&self) -> std::thread::ThreadId {
+		self.id
+	}
+}
+impl core::fmt::Display for DisconnectInfo {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match &self.name {
+			Some(name) => write!(f, "thread {name:?} ({:?}) dropped its thread beam without sending a value", self.id),
+			None => write!(f, "thread {:?} dropped its thread beam without sending a value", self.id),
+		}
+	}
+}
+impl std::error::Error for DisconnectInfo {}
+
+/// Helper for spawning a new thread with a beam, reporting which thread it was if nothing is beamed.
+///
+/// This is the same as [`spawn`], except that if the closure drops its sending side without sending a value, a [`DisconnectInfo`] naming the thread is returned instead of `None`.
+///
+/// # Example
+///
+/// ```rust
+/// let (hello, thread) = threadbeam::spawn_diagnostic(move |tx: threadbeam::ThreadBeamTx<String>| drop(tx));
+///
+/// let disconnected = hello.unwrap_err();
+/// assert_eq!(disconnected.id(), thread.thread().id());
+/// # thread.join().unwrap();
+/// ```
+#[inline]
+#[track_caller]
+pub fn spawn_diagnostic<T, R, F>(spawn: F) -> (Result<T, DisconnectInfo>, std::thread::JoinHandle<R>)
+where
+	F: FnOnce(ThreadBeamTx<T>) -> R,
+	F: Send + 'static,
+	T: Send + 'static,
+	R: Send + 'static,
+{
+	spawn_diagnostic_builder(std::thread::Builder::new(), spawn).expect("failed to spawn thread")
+}
+
+/// Helper for spawning a new thread with a beam using a [`std::thread::Builder`], reporting which thread it was if nothing is beamed.
+///
+/// See [`spawn_diagnostic`]. Giving the thread a name with the builder makes the [`DisconnectInfo`] easier to act on.
+///
+/// # Example
+///
+/// ```rust
+/// let builder = std::thread::Builder::new().name(String::from("worker"));
+/// let (hello, thread) = threadbeam::spawn_diagnostic_builder(builder, move |tx: threadbeam::ThreadBeamTx<String>| drop(tx)).unwrap();
+///
+/// assert_eq!(hello.unwrap_err().name(), Some("worker"));
+/// # thread.join().unwrap();
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn spawn_diagnostic_builder<T, R, F>(
+	builder: std::thread::Builder,
+	spawn: F,
+) -> std::io::Result<(Result<T, DisconnectInfo>, std::thread::JoinHandle<R>)>
+where
+	F: FnOnce(ThreadBeamTx<T>) -> R,
+	F: Send + 'static,
+	T: Send + 'static,
+	R: Send + 'static,
+{
+	let (tx, rx) = channel();
+	let join = builder.spawn(move || spawn(tx))?;
+	let value = rx.recv().ok_or_else(|| DisconnectInfo {
+		name: join.thread().name().map(String::from),
+		id: join.thread().id(),
+	});
+	Ok((value, join))
+}
+
+#[inline]
+#[must_use = "the beamed value is discarded if not used"]
+/// Helper for spawning a new scoped thread with a beam.
+///
+/// Unlike [spawn], the closure and the beamed value only need to live as long as `scope`, so they may borrow from the caller's stack.
+///
+/// # Example
+///
+/// ```rust
+/// let greetings = [String::from("Hello"), String::from("world")];
+///
+/// std::thread::scope(|scope| {
+///     let (first, thread) = threadbeam::spawn_scoped(scope, |tx| {
+///         tx.send(&greetings[0]).unwrap();
+///         // your code...
+///     });
+///
+///     assert_eq!(first.map(String::as_str), Some("Hello"));
+///     thread.join().unwrap();
+/// });
+/// ```
+pub fn spawn_scoped<'scope, 'env, T, R, F>(
+	scope: &'scope std::thread::Scope<'scope, 'env>,
+	spawn: F,
+) -> (Option<T>, std::thread::ScopedJoinHandle<'scope, R>)
+where
+	F: FnOnce(ThreadBeamTx<T>) -> R,
+	F: Send + 'scope,
+	T: Send + 'scope,
+	R: Send + 'scope,
+{
+	let (tx, rx) = channel();
+	let join = scope.spawn(move || spawn(tx));
+	(rx.recv(), join)
+}
+
+// Unparks the thread that created it, for waiting on several thread beams at once
+struct ThreadWaker(std::thread::Thread);
+impl ThreadWaker {
+	fn current() -> std::task::Waker {
+		std::task::Waker::from(std::sync::Arc::new(ThreadWaker(std::thread::current())))
+	}
+}
+impl std::task::Wake for ThreadWaker {
+	fn wake(self: std::sync::Arc<Self>) {
+		self.0.unpark();
+	}
+}
+
+/// Waits on several thread beams at once, returning the index and result of whichever is ready first.
+///
+/// A thread beam is ready once a value has been sent down it, or its sending side has been dropped, in which case its result is `None`. If several are ready, the one with the lowest index wins. The others are dropped.
+///
+/// The current thread is parked while waiting, and unparked by the thread beams themselves, so this doesn't spin.
+///
+/// # Panics
+///
+/// Panics if `beams` is empty, since it would never return.
+///
+/// # Example
+///
+/// ```rust
+/// let (fast_tx, fast) = threadbeam::channel();
+/// let (slow_tx, slow) = threadbeam::channel();
+///
+/// # let j = [
+/// std::thread::spawn(move || {
+///     std::thread::sleep(std::time::Duration::from_millis(500));
+///     let _ = slow_tx.send("slow");
+/// }),
+/// std::thread::spawn(move || {
+///     let _ = fast_tx.send("fast");
+/// }),
+/// # ];
+///
+/// assert_eq!(threadbeam::recv_any(vec![slow, fast]), (1, Some("fast")));
+/// # j.map(|j| j.join().unwrap());
+/// ```
+#[track_caller]
+pub fn recv_any<T: Send>(beams: Vec<ThreadBeamRx<T>>) -> (usize, Option<T>) {
+	assert!(!beams.is_empty(), "recv_any called without any thread beams to wait on");
+
+	let waker = ThreadWaker::current();
+	loop {
+		// Every beam that isn't ready has the waker registered by the time it's checked, so a value sent after that unparks this thread
+		for (i, beam) in beams.iter().enumerate() {
+			if let Poll::Ready(value) = beam.poll_recv(&waker) {
+				return (i, value);
+			}
+		}
+
+		// Parking can wake up spuriously, so the beams are always checked again
+		std::thread::park();
+	}
+}
+
+/// Helper for spawning a new thread with a beam, then waiting for the thread to finish.
+///
+/// Returns the beamed value along with the thread's result, so by the time the value is available the thread has been joined. If the thread panicked, its panic payload is returned in place of its result.
+///
+/// Because the thread is always joined before returning, the closure may borrow from the caller's stack.
+///
+/// # Example
+///
+/// ```rust
+/// let greeting = String::from("Hello, world!");
+///
+/// let (hello, completed) = threadbeam::spawn_sync(|tx| {
+///     tx.send(greeting.as_str()).unwrap();
+///     // your code...
+///     String::from("Thread completed!")
+/// });
+///
+/// assert_eq!(hello, Some("Hello, world!"));
+/// assert_eq!(completed.ok().as_deref(), Some("Thread completed!"));
+/// ```
+pub fn spawn_sync<T, R, F>(spawn: F) -> (Option<T>, std::thread::Result<R>)
+where
+	F: FnOnce(ThreadBeamTx<T>) -> R,
+	F: Send,
+	T: Send,
+	R: Send,
+{
+	std::thread::scope(|scope| {
+		let (tx, rx) = channel();
+		let join = scope.spawn(move || spawn(tx));
+		(rx.recv(), join.join())
+	})
+}
+
+/// What came out of a thread spawned with [`spawn_or_join`].
+#[derive(Debug)]
+pub enum SpawnOutcome<T, R> {
+	/// The thread beamed a value. The thread may still be running, so its handle is handed back too.
+	Beamed(T, std::thread::JoinHandle<R>),
+
+	/// The thread finished without beaming a value, and this is what it returned.
+	Returned(R),
+
+	/// The thread panicked before beaming a value, and this is its panic payload.
+	Panicked(Box<dyn std::any::Any + Send + 'static>),
+}
+
+/// Helper for spawning a new thread with a beam, falling back to the thread's return value if it doesn't beam one.
+///
+/// This waits for the beamed value like [spawn]. If the sending side is dropped without sending anything, the thread is joined and its return value (or panic payload) is returned instead.
+///
+/// # Example
+///
+/// ```rust
+/// use threadbeam::SpawnOutcome;
+///
+/// let outcome = threadbeam::spawn_or_join(move |tx| {
+///     if false {
+///         tx.send(String::from("Early result")).unwrap();
+///     }
+///     String::from("Final result")
+/// });
+///
+/// assert!(matches!(outcome, SpawnOutcome::Returned(result) if result == "Final result"));
+/// ```
+pub fn spawn_or_join<T, R, F>(spawn: F) -> SpawnOutcome<T, R>
+where
+	F: FnOnce(ThreadBeamTx<T>) -> R,
+	F: Send + 'static,
+	T: Send + 'static,
+	R: Send + 'static,
+{
+	match self::spawn(spawn) {
+		(Some(value), join) => SpawnOutcome::Beamed(value, join),
+		(None, join) => match join.join() {
+			Ok(result) => SpawnOutcome::Returned(result),
+			Err(panic) => SpawnOutcome::Panicked(panic),
+		},
+	}
+}
+
+/// Helper for spawning a new thread with a beam, folding a thread that doesn't beam a value into a single [`Result`].
+///
+/// This is [`spawn_or_join`] for callers that only want the beamed value: if the thread beams one, it is returned and the thread is left to run to completion on its own; otherwise, the thread is joined and what it returned, or its panic payload, is returned in a [`SpawnError`]. Use [spawn] to keep the thread's handle.
+///
+/// # Example
+///
+/// ```rust
+/// let hello = threadbeam::spawn_joined(move |tx| {
+///     tx.send(String::from("Hello, world!")).unwrap();
+/// });
+///
+/// assert_eq!(hello.ok().as_deref(), Some("Hello, world!"));
+/// ```
+pub fn spawn_joined<T, R, F>(spawn: F) -> Result<T, SpawnError<R>>
+where
+	F: FnOnce(ThreadBeamTx<T>) -> R,
+	F: Send + 'static,
+	T: Send + 'static,
+	R: Send + 'static,
+{
+	match spawn_or_join(spawn) {
+		SpawnOutcome::Beamed(value, _) => Ok(value),
+		SpawnOutcome::Returned(result) => Err(SpawnError::NoValue(result)),
+		SpawnOutcome::Panicked(panic) => Err(SpawnError::Panicked(panic)),
+	}
 }
@@ -14,23 +14,25 @@
 //! ## Examples
 //!
 //! ```rust
+//! # #[cfg(any(feature = "alloc", not(feature = "no_std")))] {
 //! let (tx, rx) = threadbeam::channel();
 //!
 //! # let j =
 //! std::thread::spawn(move || {
-//!     tx.send(String::from("Hello, world!"));
+//!     tx.send(String::from("Hello, world!")).unwrap();
 //! });
 //!
 //! let hello = rx.recv();
 //! assert_eq!(hello.as_deref(), Some("Hello, world!"));
 //!
 //! # j.join().unwrap();
+//! # }
 //! ```
 //!
 //! ```rust
 //! # #[cfg(not(feature = "no_std"))]
 //! let (hello, thread) = threadbeam::spawn(move |tx| {
-//!     tx.send(String::from("Hello, world!"));
+//!     tx.send(String::from("Hello, world!")).unwrap();
 //!     // your code...
 //!     String::from("Thread completed!")
 //! });
@@ -60,17 +62,133 @@
 //! [dependencies]
 //! threadbeam = { version = "0", features = ["no_std"] }
 //! ```
+//!
+//! Waiting receivers spin with an exponential backoff. On a cooperative scheduler, register a yield function with `set_yield_hook` so that they yield to other tasks instead. Debug builds panic if a receiver spins without one for so long that it is likely starving the sender of its only core; `set_spin_patience` sets how long that is, or turns the check off. From an interrupt handler, where nothing may spin, poll with `ThreadBeamRx::try_recv_from_interrupt` instead.
+//!
+//! ## `alloc`
+//!
+//! On by default. Under `no_std`, turning off default features drops everything that puts the thread beam on the heap, such as `channel`, and leaves only `ThreadBeamInline`, for targets without a global allocator:
+//!
+//! ```toml
+//! [dependencies]
+//! threadbeam = { version = "0", default-features = false, features = ["no_std"] }
+//! ```
+//!
+//! With `std`, an allocator is always there, so this does nothing.
+//!
+//! ## `spin`
+//!
+//! To have waiting receivers spin instead of parking on a `Condvar` while keeping the standard library, enable the `spin` feature in your Cargo.toml:
+//!
+//! ```toml
+//! [dependencies]
+//! threadbeam = { version = "0", features = ["spin"] }
+//! ```
+//!
+//! Each wait spins for a moment and then yields to the OS scheduler, and sending never has to wake anybody. That saves the park and unpark round trip when the value is sent within microseconds of the receiver starting to wait, at the cost of burning a core for as long as it isn't. Run the `latency` bench with and without the feature to see which wins on your machine. This can be combined with `parking_lot`, and has no effect with `no_std`, which always spins.
+//!
+//! ## `safe`
+//!
+//! To build threadbeam with `#![forbid(unsafe_code)]`, enable the `safe` feature in your Cargo.toml:
+//!
+//! ```toml
+//! [dependencies]
+//! threadbeam = { version = "0", features = ["safe"] }
+//! ```
+//!
+//! Each side of the thread beam then holds an `Arc` of the shared state instead of a pointer that the last side frees by hand, and the value is kept in an `Option` instead of alongside a flag saying whether it's there. That costs an extra atomic reference count on every clone and drop of a side, and a word of space for any value without a niche. The `raw` module, `try_channel`, `AllocError` and `ThreadBeamTx::reserve` can't be written without `unsafe`, so they aren't available with this feature; everything else behaves the same. This can be combined with any other feature.
+//!
+//! ## `pool`
+//!
+//! To reuse thread beam allocations instead of allocating every time, for workloads creating thousands of short-lived thread beams, enable the `pool` feature and create them with `ThreadBeamPool::channel`:
+//!
+//! ```toml
+//! [dependencies]
+//! threadbeam = { version = "0", features = ["pool"] }
+//! ```
+//!
+//! Each allocation then also remembers which pool to return to, so without the feature, thread beams from `channel` stay as small as they were and are simply freed. Run the `pool` bench to see the savings. This is only available without the `no_std` feature.
+//!
+//! Allocations that are reused also get to learn how quickly their sending side tends to send: `ThreadBeamRx::recv_adaptive` spins for a while before blocking while the sending side is usually fast, based on a moving average of its recent waits that is kept with the allocation. The `adaptive` bench compares it against always blocking.
+//!
+//! ## `broadcast`
+//!
+//! To hand one value to several receivers, enable the `broadcast` feature and clone the receiving side of a thread beam of a `Clone` type:
+//!
+//! ```toml
+//! [dependencies]
+//! threadbeam = { version = "0", features = ["broadcast"] }
+//! ```
+//!
+//! Every receiving side then gets its own clone of each value, except the last one to receive it, which gets the original. The thread beam has to count the receiving sides and which of them have had their clone, so without the feature, it stays as small as it was and moving the value out is all a receive does.
+//!
+//! ## `debug-unconsumed`
+//!
+//! To find values that are sent down a thread beam but never received, enable the `debug-unconsumed` feature and register a hook with `set_unconsumed_hook`:
+//!
+//! ```toml
+//! [dependencies]
+//! threadbeam = { version = "0", features = ["debug-unconsumed"] }
+//! ```
+//!
+//! ## `debug-stats`
+//!
+//! To see how many times a receiver was woken up for nothing while waiting, enable the `debug-stats` feature and receive with `ThreadBeamRx::recv_with_stats`. With the `no_std` feature, receive with `ThreadBeamRx::recv_counted` instead, to see how many times a receiver backed off while spinning.
+//!
+//! ```toml
+//! [dependencies]
+//! threadbeam = { version = "0", features = ["debug-stats"] }
+//! ```
+//!
+//! ## `metrics`
+//!
+//! To see how long receivers spend blocked, for example to catch slow-to-start workers in aggregate, enable the `metrics` feature and receive with `ThreadBeamRx::recv_timed`. Nothing is timed without the feature. This is only available without the `no_std` feature.
+//!
+//! ```toml
+//! [dependencies]
+//! threadbeam = { version = "0", features = ["metrics"] }
+//! ```
+//!
+//! ## `paranoid`
+//!
+//! To check the wake-up protocol itself, enable the `paranoid` feature. Every change a blocked `ThreadBeamRx::recv` waits for is then counted, along with the count its waiters were last notified at, and `recv` wakes up regularly to check that the two match, panicking if a notification was lost rather than blocking forever. This slows `recv` down, and is meant for testing rather than release builds. This is only available without the `no_std` feature.
+//!
+//! ```toml
+//! [dependencies]
+//! threadbeam = { version = "0", features = ["paranoid"] }
+//! ```
+//!
+//! ## `debug-sender`
+//!
+//! To find out which thread won the race when several clones of the sending side send, enable the `debug-sender` feature and receive with `ThreadBeamRx::recv_with_sender`. Each send records the sending thread's `ThreadId` alongside the value, so without the feature nothing is recorded. This is only available without the `no_std` feature.
+//!
+//! ```toml
+//! [dependencies]
+//! threadbeam = { version = "0", features = ["debug-sender"] }
+//! ```
+//!
+//! ## `async`
+//!
+//! To await a value from an async runtime instead of blocking the executor, enable the `async` feature and use `ThreadBeamRx::recv_async`:
+//!
+//! ```toml
+//! [dependencies]
+//! threadbeam = { version = "0", features = ["async"] }
+//! ```
 
 #![cfg_attr(all(feature = "no_std", not(test)), no_std)]
+#![cfg_attr(feature = "safe", forbid(unsafe_code))]
 #![deny(missing_docs)]
 #![deny(clippy::tabs_in_doc_comments)]
 
 #[cfg(all(feature = "no_std", feature = "parking_lot"))]
 compile_error!("Cannot use `parking_lot` feature with `no_std` feature");
 
-#[cfg(feature = "no_std")]
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
 extern crate alloc;
-#[cfg(all(test, feature = "no_std"))]
+#[cfg(all(feature = "no_std", feature = "alloc"))]
+use alloc::boxed::Box;
+#[cfg(all(test, feature = "no_std", feature = "alloc"))]
 use alloc::string::String;
 
 #[cfg(feature = "no_std")]
@@ -79,155 +197,4295 @@ mod no_std;
 use no_std as r#impl;
 
 #[cfg(not(feature = "no_std"))]
-mod r#impl;
+mod r#impl;
+
+#[cfg(feature = "debug-unconsumed")]
+mod debug_unconsumed;
+#[cfg(feature = "debug-unconsumed")]
+pub use debug_unconsumed::set_unconsumed_hook;
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+use r#impl::ThreadBeamInner;
+pub use r#impl::*;
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+mod tagged;
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+pub use tagged::*;
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+mod shared;
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+pub use shared::SharedRx;
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+pub mod oneshot;
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+pub mod local;
+
+#[cfg(not(feature = "no_std"))]
+mod wait;
+#[cfg(not(feature = "no_std"))]
+pub use wait::WaitStrategy;
+
+#[cfg(not(feature = "no_std"))]
+mod clock;
+#[cfg(not(feature = "no_std"))]
+pub use clock::{Clock, SystemClock};
+
+#[cfg(all(any(feature = "alloc", not(feature = "no_std")), not(feature = "safe")))]
+pub mod raw;
+
+#[cfg(all(feature = "async", any(feature = "alloc", not(feature = "no_std"))))]
+mod future;
+#[cfg(all(feature = "async", any(feature = "alloc", not(feature = "no_std"))))]
+pub use future::RecvFuture;
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+use core::marker::PhantomData;
+#[cfg(not(feature = "safe"))]
+use core::mem::MaybeUninit;
+#[cfg(all(any(feature = "alloc", not(feature = "no_std")), not(feature = "safe")))]
+use core::ptr::NonNull;
+
+// Each side of a thread beam points at the shared state, which is freed by hand by whichever side is dropped last. With the `safe`
+// feature, each side holds a reference count on it instead, and the state keeps its value in an `Option` rather than tracking it by hand.
+#[cfg(all(any(feature = "alloc", not(feature = "no_std")), not(feature = "safe")))]
+type Shared<T> = NonNull<ThreadBeamInner<T>>;
+#[cfg(all(feature = "safe", any(feature = "alloc", not(feature = "no_std"))))]
+type Shared<T> = alloc::sync::Arc<ThreadBeamInner<T>>;
+
+// What the shared state lives in until it is split into sides
+#[cfg(all(any(feature = "alloc", not(feature = "no_std")), not(feature = "safe")))]
+type Allocation<T> = Box<ThreadBeamInner<T>>;
+#[cfg(all(feature = "safe", any(feature = "alloc", not(feature = "no_std"))))]
+type Allocation<T> = alloc::sync::Arc<ThreadBeamInner<T>>;
+
+#[cfg(not(feature = "safe"))]
+type Slot<T> = MaybeUninit<T>;
+#[cfg(feature = "safe")]
+type Slot<T> = Option<T>;
+
+/// The sending side of a thread beam.
+///
+/// `Tag` is a marker that [`channel_tagged`] brands both sides with, so that sides of thread beams meant for different things can't be
+/// mixed up even when they carry the same `T`. It is never constructed and defaults to `()`, the tag of every untagged thread beam.
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+pub struct ThreadBeamTx<T: Send, Tag = ()>(Shared<T>, PhantomData<fn() -> Tag>);
+
+/// The receiving side of a thread beam.
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[must_use = "dropping the receiving side of a thread beam makes every send to it fail"]
+pub struct ThreadBeamRx<T: Send, Tag = ()>(Shared<T>, Seen, PhantomData<fn() -> Tag>);
+
+// Which value a receiving side last took a clone of, counting the values sent down the thread beam, so that a receiving side that has been
+// cloned to broadcast the value receives each value once, however it receives. Only touched under the lock. Without the `broadcast`
+// feature, there is only ever one receiving side, so there is nothing to keep track of and this takes no space.
+#[cfg(feature = "broadcast")]
+struct Seen(core::sync::atomic::AtomicUsize);
+#[cfg(not(feature = "broadcast"))]
+struct Seen;
+impl Seen {
+	#[inline(always)]
+	const fn new() -> Self {
+		#[cfg(feature = "broadcast")]
+		return Self(core::sync::atomic::AtomicUsize::new(0));
+
+		#[cfg(not(feature = "broadcast"))]
+		return Self;
+	}
+}
+#[cfg(feature = "broadcast")]
+impl Seen {
+	#[inline(always)]
+	fn get(&self) -> usize {
+		self.0.load(core::sync::atomic::Ordering::Relaxed)
+	}
+
+	#[inline(always)]
+	fn set(&self, generation: usize) {
+		self.0.store(generation, core::sync::atomic::Ordering::Relaxed);
+	}
+}
+
+// Both sides only ever move the value in and out under the lock, or lend it out while holding the lock, so like `Mutex`, they only need `T: Send`.
+// Anything that hands out `&T` beyond that, such as `BeamGuard`, must only be `Sync` if `T: Sync`. With the `safe` feature, the same
+// bounds follow from the `Arc` and the lock.
+#[cfg(all(any(feature = "alloc", not(feature = "no_std")), not(feature = "safe")))]
+unsafe impl<T: Send, Tag> Sync for ThreadBeamTx<T, Tag> {}
+#[cfg(all(any(feature = "alloc", not(feature = "no_std")), not(feature = "safe")))]
+unsafe impl<T: Send, Tag> Send for ThreadBeamTx<T, Tag> {}
+
+#[cfg(all(any(feature = "alloc", not(feature = "no_std")), not(feature = "safe")))]
+unsafe impl<T: Send, Tag> Sync for ThreadBeamRx<T, Tag> {}
+#[cfg(all(any(feature = "alloc", not(feature = "no_std")), not(feature = "safe")))]
+unsafe impl<T: Send, Tag> Send for ThreadBeamRx<T, Tag> {}
+
+/// A sending side of a thread beam that can't send until it is upgraded.
+///
+/// See [`ThreadBeamTx::downgrade`].
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+pub struct WeakThreadBeamTx<T: Send, Tag = ()>(Shared<T>, PhantomData<fn() -> Tag>);
+
+#[cfg(all(any(feature = "alloc", not(feature = "no_std")), not(feature = "safe")))]
+unsafe impl<T: Send, Tag> Sync for WeakThreadBeamTx<T, Tag> {}
+#[cfg(all(any(feature = "alloc", not(feature = "no_std")), not(feature = "safe")))]
+unsafe impl<T: Send, Tag> Send for WeakThreadBeamTx<T, Tag> {}
+
+// Another handle on the same shared state, for a new side of the thread beam; the caller must count the new side in the state
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[inline(always)]
+fn share<T>(shared: &Shared<T>) -> Shared<T> {
+	#[cfg(not(feature = "safe"))]
+	{
+		*shared
+	}
+	#[cfg(feature = "safe")]
+	{
+		shared.clone()
+	}
+}
+
+// Splits freshly allocated shared state into the two sides of a thread beam
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[inline]
+fn split<T: Send, Tag>(inner: Allocation<T>) -> (ThreadBeamTx<T, Tag>, ThreadBeamRx<T, Tag>) {
+	#[cfg(not(feature = "safe"))]
+	let inner = unsafe { NonNull::new_unchecked(Box::into_raw(inner)) };
+	(ThreadBeamTx(share(&inner), PhantomData), ThreadBeamRx(inner, Seen::new(), PhantomData))
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+macro_rules! endpoint_inner {
+	($($endpoint:ident),*) => {$(
+		impl<T: Send, Tag> $endpoint<T, Tag> {
+			#[inline(always)]
+			fn inner(&self) -> &ThreadBeamInner<T> {
+				#[cfg(not(feature = "safe"))]
+				{
+					// Safety: the shared state isn't freed until every side of the thread beam, including this one, is gone
+					unsafe { self.0.as_ref() }
+				}
+				#[cfg(feature = "safe")]
+				{
+					&self.0
+				}
+			}
+		}
+	)*};
+}
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+endpoint_inner!(ThreadBeamTx, ThreadBeamRx, WeakThreadBeamTx);
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+impl<T: Send, Tag> ThreadBeamTx<T, Tag> {
+	/// Converts `value` into the thread beam's value type with `f`, then sends it to the receiving side of the thread beam.
+	///
+	/// The conversion always runs on the sending thread, so `value` itself doesn't need to be `Send`. This is useful for building the value out of thread-local types such as `Rc`.
+	///
+	/// Returns the converted value back in a [`SendError`] if the receiving side of the thread beam has been dropped.
+	#[inline]
+	pub fn send_converting<U>(self, value: U, f: impl FnOnce(U) -> T) -> Result<(), SendError<T>> {
+		self.send(f(value))
+	}
+
+	/// Sends a clone of `value` to the receiving side of the thread beam, leaving the original with the caller.
+	///
+	/// Returns the clone back in a [`SendError`] if the receiving side of the thread beam has been dropped.
+	#[inline]
+	pub fn send_cloned(self, value: &T) -> Result<(), SendError<T>>
+	where
+		T: Clone,
+	{
+		self.send(value.clone())
+	}
+
+	/// Converts `value` into the thread beam's value type with [`From`], then sends it to the receiving side of the thread beam.
+	///
+	/// For `#[repr(transparent)]` newtypes, the conversion compiles away.
+	///
+	/// Returns the converted value back in a [`SendError`] if the receiving side of the thread beam has been dropped.
+	#[inline]
+	pub fn send_unwrap<W>(self, value: W) -> Result<(), SendError<T>>
+	where
+		T: From<W>,
+	{
+		self.send(T::from(value))
+	}
+
+	/// Builds a value with `f` and sends it to the receiving side of the thread beam, but only if the receiving side is still there to receive it.
+	///
+	/// If the receiving side of the thread beam has already been dropped, `f` is never called and `SendError(None)` is returned. The thread beam isn't locked while `f` runs, so the receiving side may still be dropped in the meantime, in which case the built value is handed back in `SendError(Some(value))`.
+	#[inline]
+	pub fn send_with(self, f: impl FnOnce() -> T) -> Result<(), SendError<Option<T>>> {
+		if self.is_disconnected() {
+			return Err(SendError(None));
+		}
+		self.send(f()).map_err(|SendError(value)| SendError(Some(value)))
+	}
+
+	/// Attempts to send a value to the receiving side of the thread beam without blocking.
+	///
+	/// This never blocks, just like [`send`](ThreadBeamTx::send), but reports failure with the same vocabulary as `std::sync::mpsc`.
+	///
+	/// Returns the value back in [`TrySendError::Disconnected`] if the receiving side of the thread beam has been dropped.
+	#[inline]
+	pub fn try_send(self, value: T) -> Result<(), TrySendError<T>> {
+		self.send(value).map_err(TrySendError::from)
+	}
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+impl<T: Send> ThreadBeamRx<T> {
+	/// Creates the receiving side of a thread beam whose sending side has already been dropped, so that [`recv`](ThreadBeamRx::recv) returns `None` straight away.
+	///
+	/// This is handy for testing how code that takes a receiving side handles a sender that never sends.
+	pub fn disconnected() -> Self {
+		let (tx, rx) = channel();
+		drop(tx);
+		rx
+	}
+
+	/// Creates the receiving side of a thread beam that `value` has already been sent down, with its sending side already dropped, so that [`recv`](ThreadBeamRx::recv) returns it straight away.
+	///
+	/// This is the counterpart of [`disconnected`](ThreadBeamRx::disconnected), for code that takes a receiving side when the value is sometimes available up front. If it's never received, the value is dropped along with the receiving side.
+	pub fn ready(value: T) -> Self {
+		let (tx, rx) = channel();
+		if tx.send(value).is_err() {
+			unreachable!("the receiving side of a new thread beam is alive");
+		}
+		rx
+	}
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+impl<T: Send, Tag> ThreadBeamRx<T, Tag> {
+	/// Receive the value sent by the sending side of the thread beam.
+	///
+	/// Returns [`RecvError`] if the sending side of the thread beam has been dropped without sending anything. This is the same as [`recv`](ThreadBeamRx::recv), but works with `?`.
+	///
+	/// # Example
+	///
+	/// Without the `no_std` feature, [`RecvError`] is a [`std::error::Error`], so a worker that dies before its startup handshake can be
+	/// propagated straight out of `main`:
+	///
+	/// ```rust
+	/// # #[cfg(not(feature = "no_std"))]
+	/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+	///     let (tx, rx) = threadbeam::channel();
+	///
+	///     let worker = std::thread::spawn(move || {
+	///         tx.send(std::process::id()).unwrap();
+	///         // ... the worker's actual work
+	///     });
+	///
+	///     let pid = rx.recv_result()?;
+	///     println!("worker started in process {pid}");
+	///
+	///     worker.join().unwrap();
+	///     Ok(())
+	/// }
+	/// # #[cfg(feature = "no_std")]
+	/// # fn main() {}
+	/// ```
+	#[inline]
+	#[track_caller]
+	pub fn recv_result(self) -> Result<T, RecvError> {
+		self.recv().ok_or(RecvError)
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, blocking like [`recv`](ThreadBeamRx::recv), but without consuming the receiving side.
+	///
+	/// The receiving side can still be queried afterwards, e.g. with [`is_disconnected`](ThreadBeamRx::is_disconnected). This is the same as [`recv_streaming`](ThreadBeamRx::recv_streaming): once the value has been received, later calls return `None` as soon as the sending side is dropped, straight away if it already has been, as it has after [`send`](ThreadBeamTx::send). That holds for each clone of a receiving side too, which only ever receives one clone of each value.
+	#[inline]
+	pub fn recv_keep(&self) -> Option<T> {
+		self.recv_streaming()
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, blocking like [`recv_streaming`](ThreadBeamRx::recv_streaming), and append its contents to `buf`.
+	///
+	/// Returns `false`, leaving `buf` untouched, if the sending side of the thread beam has been dropped. Receiving a `Vec<u8>` into the same buffer each time reuses its allocation once it has grown large enough, and the bytes are copied straight across.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// let (tx, rx) = threadbeam::channel::<Vec<u8>>();
+	///
+	/// # let j =
+	/// std::thread::spawn(move || {
+	///     tx.send_streaming(b"Hello, ".to_vec()).unwrap();
+	///     tx.send_streaming(b"world!".to_vec()).unwrap();
+	/// });
+	///
+	/// let mut buf = Vec::new();
+	/// while rx.recv_into(&mut buf) {}
+	/// assert_eq!(buf, b"Hello, world!");
+	/// # j.join().unwrap();
+	/// ```
+	#[inline]
+	pub fn recv_into<B>(&self, buf: &mut B) -> bool
+	where
+		T: IntoIterator,
+		B: Extend<T::Item>,
+	{
+		match self.recv_streaming() {
+			Some(value) => {
+				buf.extend(value);
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, or compute one with `on_disconnect` if the sending side was dropped without sending anything.
+	#[inline]
+	pub fn recv_or_else(self, on_disconnect: impl FnOnce() -> T) -> T {
+		self.recv().unwrap_or_else(on_disconnect)
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, or `default` if the sending side was dropped without sending anything.
+	#[inline]
+	pub fn recv_or(self, default: T) -> T {
+		self.recv().unwrap_or(default)
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, or `T::default()` if the sending side was dropped without sending anything.
+	///
+	/// Paired with `spawn_manual`, this suits readiness signals whose default is a sensible "not ready", such as `false`.
+	#[inline]
+	pub fn recv_or_default(self) -> T
+	where
+		T: Default,
+	{
+		self.recv().unwrap_or_default()
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, converting it into `W` with [`From`].
+	///
+	/// Returns `None` if the sending side of the thread beam has been dropped.
+	#[inline]
+	pub fn recv_wrap<W: From<T>>(self) -> Option<W> {
+		self.recv().map(W::from)
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, writing it into `out`.
+	///
+	/// Returns `true` if a value was received. If the sending side of the thread beam has been dropped, `out` is left untouched and `false` is returned.
+	#[inline]
+	pub fn recv_write(self, out: &mut Option<T>) -> bool {
+		match self.recv() {
+			Some(value) => {
+				*out = Some(value);
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Transforms the value sent by the sending side of the thread beam with `f` once it is received.
+	///
+	/// `f` runs on the receiving thread when [`MappedRx::recv`] is called, and only if a value actually arrives, so the sending thread doesn't pay for the transformation.
+	#[inline]
+	pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> MappedRx<T, F, Tag> {
+		MappedRx { rx: self, f }
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, running tasks popped from `deque` while it isn't ready yet.
+	///
+	/// The thread beam is checked again after every task. Once `deque` is empty, this blocks like [`recv`](ThreadBeamRx::recv) until the value arrives, even if more work is pushed to `deque` in the meantime.
+	///
+	/// Returns `None` if the sending side of the thread beam has been dropped.
+	pub fn recv_or_work<D: WorkDeque + ?Sized>(self, deque: &D) -> Option<T> {
+		loop {
+			match self.poll_once(|| ()) {
+				core::task::Poll::Ready(value) => return value,
+				core::task::Poll::Pending => match deque.pop() {
+					Some(task) => task(),
+					None => return self.recv(),
+				},
+			}
+		}
+	}
+}
+
+/// The receiving side of a thread beam whose value is transformed once it is received.
+///
+/// See [`ThreadBeamRx::map`].
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+pub struct MappedRx<T: Send, F, Tag = ()> {
+	rx: ThreadBeamRx<T, Tag>,
+	f: F,
+}
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+impl<T: Send, U, F: FnOnce(T) -> U, Tag> MappedRx<T, F, Tag> {
+	/// Receive the value sent by the sending side of the thread beam, transformed by the mapping function.
+	///
+	/// Returns `None` if the sending side of the thread beam has been dropped, in which case the mapping function is never called.
+	#[inline]
+	pub fn recv(self) -> Option<U> {
+		self.rx.recv().map(self.f)
+	}
+
+	/// Transforms the already transformed value further with `g` once it is received.
+	#[inline]
+	pub fn map<V>(self, g: impl FnOnce(U) -> V) -> MappedRx<T, impl FnOnce(T) -> V, Tag> {
+		let f = self.f;
+		self.rx.map(move |value| g(f(value)))
+	}
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+impl<T: Send, Tag> IntoIterator for ThreadBeamRx<T, Tag> {
+	type Item = T;
+	type IntoIter = IntoIter<T, Tag>;
+
+	/// Turns the receiving side of the thread beam into a one-shot iterator.
+	///
+	/// The first call to `next` blocks like [`recv`](ThreadBeamRx::recv), and the iterator ends straight after it.
+	#[inline]
+	fn into_iter(self) -> IntoIter<T, Tag> {
+		IntoIter(Some(self))
+	}
+}
+
+/// A one-shot iterator over the value sent down a thread beam.
+///
+/// Yields the value sent by the sending side of the thread beam, if any, and then ends. The first call to `next` blocks until a value is sent or the sending side is dropped.
+///
+/// See [`ThreadBeamRx::into_iter`](IntoIterator::into_iter).
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+pub struct IntoIter<T: Send, Tag = ()>(Option<ThreadBeamRx<T, Tag>>);
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+impl<T: Send, Tag> Iterator for IntoIter<T, Tag> {
+	type Item = T;
+
+	#[inline]
+	fn next(&mut self) -> Option<T> {
+		self.0.take()?.recv()
+	}
+
+	#[inline]
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		(0, Some(self.0.is_some() as usize))
+	}
+}
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+impl<T: Send, Tag> core::iter::FusedIterator for IntoIter<T, Tag> {}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+impl<T: Send, E: Send, Tag> ThreadBeamTx<Result<T, E>, Tag> {
+	/// Send a successful result to the receiving side of the thread beam.
+	///
+	/// Returns the result back in a [`SendError`] if the receiving side of the thread beam has been dropped.
+	#[inline]
+	pub fn send_ok(self, value: T) -> Result<(), SendError<Result<T, E>>> {
+		self.send(Ok(value))
+	}
+
+	/// Send an error to the receiving side of the thread beam.
+	///
+	/// Returns the result back in a [`SendError`] if the receiving side of the thread beam has been dropped.
+	#[inline]
+	pub fn send_err(self, error: E) -> Result<(), SendError<Result<T, E>>> {
+		self.send(Err(error))
+	}
+}
+
+/// Creates a new thread beam channel pair for beaming a worker's result.
+///
+/// The receiving side's `recv` returns `None` if the sending side was dropped without sending a result, and `Some(Err(...))` if the worker sent an error.
+///
+/// # Example
+///
+/// ```rust
+/// let (tx, rx) = threadbeam::try_channel_result::<u32, String>();
+///
+/// # let j =
+/// std::thread::spawn(move || match "42".parse() {
+///     Ok(value) => tx.send_ok(value),
+///     Err(_) => tx.send_err(String::from("not a number")),
+/// });
+///
+/// assert_eq!(rx.recv(), Some(Ok(42)));
+/// # j.join().unwrap();
+/// ```
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[inline]
+#[allow(clippy::type_complexity)]
+pub fn try_channel_result<T: Send, E: Send>() -> (ThreadBeamTx<Result<T, E>>, ThreadBeamRx<Result<T, E>>) {
+	channel()
+}
+
+// `Box::new`, but handing allocation failure back instead of aborting
+#[cfg(all(any(feature = "alloc", not(feature = "no_std")), not(feature = "safe")))]
+fn try_box<T>(value: T) -> Result<Box<T>, AllocError> {
+	let layout = core::alloc::Layout::new::<T>();
+	if layout.size() == 0 {
+		return Ok(Box::new(value));
+	}
+	let ptr = NonNull::new(unsafe { alloc::alloc::alloc(layout) }.cast::<T>()).ok_or(AllocError)?;
+	unsafe {
+		ptr.as_ptr().write(value);
+		Ok(Box::from_raw(ptr.as_ptr()))
+	}
+}
+
+/// Creates a new thread beam channel pair for beaming a large value behind a [`Box`].
+///
+/// A value is moved into the thread beam when it is sent and moved out again when it is received, so a large value is copied twice on its way through. Boxing it means only a pointer moves through the thread beam, at the cost of an allocation on the sending side.
+///
+/// # Example
+///
+/// ```rust
+/// let (tx, rx) = threadbeam::channel_boxed::<[u64; 4096]>();
+///
+/// # let j =
+/// std::thread::spawn(move || {
+///     tx.send(Box::new([42; 4096])).unwrap();
+/// });
+///
+/// assert_eq!(rx.recv().map(|table| table[0]), Some(42));
+/// # j.join().unwrap();
+/// ```
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[inline]
+pub fn channel_boxed<T: Send>() -> (ThreadBeamTx<Box<T>>, ThreadBeamRx<Box<T>>) {
+	channel()
+}
+
+/// Creates a new thread beam channel pair, failing to compile if `T` is larger than `N` bytes.
+///
+/// This is [`channel`] for performance-sensitive code that wants the cost of moving the value through the thread beam spelled out in its type: a payload that grows past the budget is a compile error rather than a silent slowdown. Beam a payload that doesn't fit with [`channel_boxed`] instead, so that only a pointer moves through the thread beam, which always fits a budget of `size_of::<usize>()` bytes.
+///
+/// # Example
+///
+/// ```rust
+/// let (tx, rx) = threadbeam::channel_inline::<u64, 8>();
+///
+/// # let j =
+/// std::thread::spawn(move || tx.send(42).unwrap());
+///
+/// assert_eq!(rx.recv(), Some(42));
+/// # j.join().unwrap();
+/// ```
+///
+/// A payload over budget doesn't compile:
+///
+/// ```rust,compile_fail
+/// let (tx, rx) = threadbeam::channel_inline::<[u64; 4096], 64>();
+/// ```
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[inline]
+pub fn channel_inline<T: Send, const N: usize>() -> (ThreadBeamTx<T>, ThreadBeamRx<T>) {
+	const {
+		assert!(
+			core::mem::size_of::<T>() <= N,
+			"thread beam payload is larger than its inline budget; use `channel_boxed` instead"
+		)
+	};
+	channel()
+}
+
+/// Creates a new one-shot channel pair shaped like those of `tokio` and `futures`.
+///
+/// This is [`oneshot::channel`], named for swapping in where `oneshot::channel()` was used. See the [`oneshot`](mod@oneshot) module for how it differs.
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[inline]
+#[must_use = "a thread beam does nothing unless both of its sides are used"]
+pub fn oneshot<T: Send>() -> (oneshot::Sender<T>, oneshot::Receiver<T>) {
+	oneshot::channel()
+}
+
+/// A queue of work that [`ThreadBeamRx::recv_or_work`] can run while it waits for a value.
+pub trait WorkDeque {
+	/// A unit of work.
+	type Task: FnOnce();
+
+	/// Pops a unit of work from the queue, or returns `None` if the queue is empty.
+	fn pop(&self) -> Option<Self::Task>;
+}
+
+/// An error returned from [`ThreadBeamTx::send`] when the receiving side of the thread beam has been dropped.
+///
+/// Contains the value that could not be sent.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+impl<T> core::fmt::Debug for SendError<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.debug_struct("SendError").finish_non_exhaustive()
+	}
+}
+impl<T> core::fmt::Display for SendError<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.write_str("sending on a thread beam with no receiver")
+	}
+}
+#[cfg(not(feature = "no_std"))]
+impl<T> std::error::Error for SendError<T> {}
+
+/// An error returned from [`ThreadBeamTx::try_send`].
+///
+/// A thread beam only ever carries one value, so it can never be full; the only way for sending to fail is for the receiving side to have been dropped.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+	/// The receiving side of the thread beam has been dropped. Contains the value that could not be sent.
+	Disconnected(T),
+}
+impl<T> core::fmt::Debug for TrySendError<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			TrySendError::Disconnected(_) => f.write_str("Disconnected(..)"),
+		}
+	}
+}
+impl<T> core::fmt::Display for TrySendError<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			TrySendError::Disconnected(_) => f.write_str("sending on a thread beam with no receiver"),
+		}
+	}
+}
+impl<T> From<SendError<T>> for TrySendError<T> {
+	#[inline]
+	fn from(SendError(value): SendError<T>) -> Self {
+		TrySendError::Disconnected(value)
+	}
+}
+#[cfg(not(feature = "no_std"))]
+impl<T> std::error::Error for TrySendError<T> {}
+
+/// An error returned from `ThreadBeamTx::send_timeout`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SendTimeoutError<T> {
+	/// The previously sent value still hadn't been received once the timeout elapsed. Contains the value that could not be sent.
+	Timeout(T),
+
+	/// The receiving side of the thread beam has been dropped. Contains the value that could not be sent.
+	Disconnected(T),
+}
+impl<T> SendTimeoutError<T> {
+	/// Returns the value that could not be sent.
+	#[inline]
+	pub fn into_inner(self) -> T {
+		match self {
+			SendTimeoutError::Timeout(value) | SendTimeoutError::Disconnected(value) => value,
+		}
+	}
+}
+impl<T> core::fmt::Debug for SendTimeoutError<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			SendTimeoutError::Timeout(_) => f.write_str("Timeout(..)"),
+			SendTimeoutError::Disconnected(_) => f.write_str("Disconnected(..)"),
+		}
+	}
+}
+impl<T> core::fmt::Display for SendTimeoutError<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			SendTimeoutError::Timeout(_) => f.write_str("timed out waiting on thread beam"),
+			SendTimeoutError::Disconnected(_) => f.write_str("sending on a thread beam with no receiver"),
+		}
+	}
+}
+impl<T> From<SendError<T>> for SendTimeoutError<T> {
+	#[inline]
+	fn from(SendError(value): SendError<T>) -> Self {
+		SendTimeoutError::Disconnected(value)
+	}
+}
+#[cfg(not(feature = "no_std"))]
+impl<T> std::error::Error for SendTimeoutError<T> {}
+
+/// An error returned from [`ThreadBeamRx::recv_result`] when the sending side of the thread beam was dropped without sending a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+impl core::fmt::Display for RecvError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.write_str("thread beam sender disconnected")
+	}
+}
+#[cfg(not(feature = "no_std"))]
+impl std::error::Error for RecvError {}
+
+/// An error returned from [`try_channel`] when the thread beam couldn't be allocated.
+#[cfg(not(feature = "safe"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocError;
+#[cfg(not(feature = "safe"))]
+impl core::fmt::Display for AllocError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.write_str("failed to allocate a thread beam")
+	}
+}
+#[cfg(all(not(feature = "no_std"), not(feature = "safe")))]
+impl std::error::Error for AllocError {}
+
+/// An error returned from [`ThreadBeamRx::try_recv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+	/// Nothing has been sent yet, but the sending side of the thread beam is still alive.
+	Empty,
+
+	/// The sending side of the thread beam was dropped without sending a value.
+	Disconnected,
+}
+impl core::fmt::Display for TryRecvError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			TryRecvError::Empty => f.write_str("receiving on an empty thread beam"),
+			TryRecvError::Disconnected => f.write_str("thread beam sender disconnected"),
+		}
+	}
+}
+#[cfg(not(feature = "no_std"))]
+impl std::error::Error for TryRecvError {}
+
+/// An error returned from [`ThreadBeamRx::recv_timeout`] and `ThreadBeamRx::recv_deadline`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvTimeoutError {
+	/// The timeout elapsed before a value was sent.
+	Timeout,
+
+	/// The sending side of the thread beam was dropped without sending a value.
+	Disconnected,
+}
+impl core::fmt::Display for RecvTimeoutError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			RecvTimeoutError::Timeout => f.write_str("timed out waiting on thread beam"),
+			RecvTimeoutError::Disconnected => f.write_str("thread beam sender disconnected"),
+		}
+	}
+}
+#[cfg(not(feature = "no_std"))]
+impl std::error::Error for RecvTimeoutError {}
+
+/// An error returned from `RecvHandle::wait`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvCancelError {
+	/// The `CancelGuard` was dropped before a value was sent.
+	Cancelled,
+
+	/// The sending side of the thread beam was dropped without sending a value.
+	Disconnected,
+}
+impl core::fmt::Display for RecvCancelError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			RecvCancelError::Cancelled => f.write_str("receiving from thread beam was cancelled"),
+			RecvCancelError::Disconnected => f.write_str("thread beam sender disconnected"),
+		}
+	}
+}
+#[cfg(not(feature = "no_std"))]
+impl std::error::Error for RecvCancelError {}
+
+/// An error returned from `spawn_joined` when the thread didn't beam a value.
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[derive(Debug)]
+pub enum SpawnError<R> {
+	/// The thread finished without beaming a value, and this is what it returned.
+	NoValue(R),
+
+	/// The thread panicked before beaming a value, and this is its panic payload.
+	Panicked(Box<dyn core::any::Any + Send + 'static>),
+}
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+impl<R> core::fmt::Display for SpawnError<R> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			SpawnError::NoValue(_) => f.write_str("thread finished without beaming a value"),
+			SpawnError::Panicked(_) => f.write_str("thread panicked before beaming a value"),
+		}
+	}
+}
+#[cfg(not(feature = "no_std"))]
+impl<R: core::fmt::Debug> std::error::Error for SpawnError<R> {}
+
+/// What happened while waiting with `ThreadBeamRx::recv_status`, or `ThreadBeamRx::recv_spin_timeout` in `no_std`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[must_use = "the beamed value is discarded if not used"]
+pub enum RecvStatus<T> {
+	/// The value sent by the sending side of the thread beam.
+	Received(T),
+
+	/// The timeout elapsed before a value was sent.
+	TimedOut,
+
+	/// The sending side of the thread beam was dropped without sending a value.
+	SenderDropped,
+}
+
+// Shared by the backends' `Debug` impls; `state` is `None` if the lock couldn't be taken without blocking
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+fn fmt_state<T>(name: &str, state: Option<&ThreadBeamState<T>>, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+	let mut debug = f.debug_struct(name);
+	match state {
+		Some(state) => debug
+			.field("has_data", &state.has_data())
+			.field("tx_alive", &state.tx_alive())
+			.field("rx_alive", &state.rx_alive())
+			.finish(),
+		None => debug.finish_non_exhaustive(),
+	}
+}
+
+bitflags::bitflags! {
+	struct ThreadBeamFlags: u8 {
+		// Option<T> but packed into a bitflag
+		const HAS_DATA = 0b10000000;
+
+		// Whether the sending side is closed
+		const TX = 0b01000000;
+
+		// Whether the receiving side is closed
+		const RX = 0b00100000;
+
+		// Whether a receiving side is blocked waiting for the sending side
+		const WAITING = 0b00010000;
+
+		// Whether the `CancelGuard` for a guarded receive has been dropped
+		const CANCELLED = 0b00001000;
+
+		// Whether a `SlotWriter` is constructing the value in place, so nobody else may send
+		const RESERVED = 0b00000100;
+
+		// Whether the receiving side has been closed but not dropped yet; with the `broadcast` feature, the receiving sides are counted instead
+		const RX_CLOSED = 0b00000010;
+	}
+}
+struct ThreadBeamState<T> {
+	// For zero-sized types, reads and writes of this slot compile away, leaving HAS_DATA as a pure signal
+	data: Slot<T>,
+	flags: ThreadBeamFlags,
+
+	// The TX flag is set for as long as this is non-zero
+	senders: usize,
+	weak_senders: usize,
+
+	// Only broadcasts need to count the receiving sides, so a thread beam that can't have more than one doesn't pay for it
+	#[cfg(feature = "broadcast")]
+	broadcast: Broadcast<T>,
+
+	#[cfg(feature = "debug-unconsumed")]
+	id: usize,
+
+	#[cfg(not(feature = "no_std"))]
+	deferred_drop: Option<r#impl::DeferredDrop<T>>,
+
+	// How many threads are blocked on the condvar, so that a change wakes nobody, just the one, or everybody
+	#[cfg(not(feature = "no_std"))]
+	cvar_waiters: usize,
+
+	// The thread that sent the value, if any
+	#[cfg(all(feature = "debug-sender", not(feature = "no_std")))]
+	sender: Option<std::thread::ThreadId>,
+
+	#[cfg(any(feature = "async", not(feature = "no_std")))]
+	waker: Option<core::task::Waker>,
+
+	// Bumped by every change a receiving side waits for, and copied to `notified_seq` whenever the waiters are notified of it
+	#[cfg(all(feature = "paranoid", not(feature = "no_std")))]
+	seq: u64,
+	#[cfg(all(feature = "paranoid", not(feature = "no_std")))]
+	notified_seq: core::cell::Cell<u64>,
+
+	#[cfg(feature = "no_std")]
+	on_ready: Option<fn()>,
+}
+#[cfg(feature = "broadcast")]
+struct Broadcast<T> {
+	// The RX flag is set for as long as this is non-zero; every receiving side but the last gets a clone made with `clone_data`
+	receivers: usize,
+	clone_data: Option<fn(&T) -> T>,
+
+	// Counts the values sent, and how many receiving sides have taken a clone of the current one
+	generation: usize,
+	cloned: usize,
+}
+#[cfg(feature = "broadcast")]
+impl<T> Broadcast<T> {
+	#[inline(always)]
+	const fn new(receivers: usize) -> Self {
+		Self {
+			receivers,
+			clone_data: None,
+			generation: 0,
+			cloned: 0,
+		}
+	}
+}
+
+// The flags share a word with any payload small enough to fit in the padding after them, so only larger payloads grow the state,
+// and then only by their own size
+#[cfg(not(feature = "safe"))]
+const _: () = {
+	use core::mem::size_of;
+	assert!(size_of::<ThreadBeamFlags>() == 1);
+	assert!(size_of::<ThreadBeamState<[u8; 7]>>() == size_of::<ThreadBeamState<()>>());
+	assert!(size_of::<ThreadBeamState<u64>>() == size_of::<ThreadBeamState<()>>() + size_of::<u64>());
+	assert!(size_of::<ThreadBeamState<[u64; 4]>>() == size_of::<ThreadBeamState<()>>() + size_of::<[u64; 4]>());
+};
+// Without broadcasts, a receiving side has nothing to remember but where the thread beam is, just like a sending side
+#[cfg(all(any(feature = "alloc", not(feature = "no_std")), not(feature = "broadcast")))]
+const _: () = assert!(core::mem::size_of::<ThreadBeamRx<u64>>() == core::mem::size_of::<ThreadBeamTx<u64>>());
+
+// Without the `alloc` feature, only the inline thread beam is left, and it only needs a few of these
+#[cfg_attr(all(feature = "no_std", not(feature = "alloc")), allow(dead_code))]
+impl<T> ThreadBeamState<T> {
+	// Neither side exists yet, so this can be torn down without either side having been dropped
+	#[cfg(any(feature = "no_std", feature = "pool", test))]
+	#[inline(always)]
+	const fn unsplit() -> Self {
+		Self {
+			#[cfg(not(feature = "safe"))]
+			data: MaybeUninit::uninit(),
+			#[cfg(feature = "safe")]
+			data: None,
+			flags: ThreadBeamFlags::empty(),
+			senders: 0,
+			weak_senders: 0,
+			#[cfg(feature = "broadcast")]
+			broadcast: Broadcast::new(0),
+
+			#[cfg(feature = "debug-unconsumed")]
+			id: 0,
+
+			#[cfg(not(feature = "no_std"))]
+			deferred_drop: None,
+			#[cfg(not(feature = "no_std"))]
+			cvar_waiters: 0,
+
+			#[cfg(all(feature = "debug-sender", not(feature = "no_std")))]
+			sender: None,
+
+			#[cfg(any(feature = "async", not(feature = "no_std")))]
+			waker: None,
+
+			#[cfg(all(feature = "paranoid", not(feature = "no_std")))]
+			seq: 0,
+			#[cfg(all(feature = "paranoid", not(feature = "no_std")))]
+			notified_seq: core::cell::Cell::new(0),
+
+			#[cfg(feature = "no_std")]
+			on_ready: None,
+		}
+	}
+
+	#[inline(always)]
+	fn new() -> Self {
+		Self {
+			#[cfg(not(feature = "safe"))]
+			data: MaybeUninit::uninit(),
+			#[cfg(feature = "safe")]
+			data: None,
+			flags: ThreadBeamFlags::TX | ThreadBeamFlags::RX,
+			senders: 1,
+			weak_senders: 0,
+			#[cfg(feature = "broadcast")]
+			broadcast: Broadcast::new(1),
+
+			#[cfg(feature = "debug-unconsumed")]
+			id: debug_unconsumed::next_id(),
+
+			#[cfg(not(feature = "no_std"))]
+			deferred_drop: None,
+			#[cfg(not(feature = "no_std"))]
+			cvar_waiters: 0,
+
+			#[cfg(all(feature = "debug-sender", not(feature = "no_std")))]
+			sender: None,
+
+			#[cfg(any(feature = "async", not(feature = "no_std")))]
+			waker: None,
+
+			#[cfg(all(feature = "paranoid", not(feature = "no_std")))]
+			seq: 0,
+			#[cfg(all(feature = "paranoid", not(feature = "no_std")))]
+			notified_seq: core::cell::Cell::new(0),
+
+			#[cfg(feature = "no_std")]
+			on_ready: None,
+		}
+	}
+
+	#[inline(always)]
+	pub fn set_data(&mut self, value: T) {
+		// Senders check `can_send` first, but if a value ever does get overwritten, it's dropped rather than leaked
+		#[cfg(not(feature = "safe"))]
+		{
+			if self.has_data() {
+				unsafe { core::ptr::drop_in_place(self.data.as_mut_ptr()) };
+			}
+			self.data = MaybeUninit::new(value);
+		}
+		#[cfg(feature = "safe")]
+		{
+			self.data = Some(value);
+		}
+		self.sent();
+	}
+
+	// Marks the slot as holding a sent value, once it has been written
+	#[inline(always)]
+	fn sent(&mut self) {
+		self.flags |= ThreadBeamFlags::HAS_DATA;
+		#[cfg(feature = "broadcast")]
+		{
+			self.broadcast.generation = self.broadcast.generation.wrapping_add(1);
+			self.broadcast.cloned = 0;
+		}
+		self.bump_seq();
+
+		#[cfg(all(feature = "debug-sender", not(feature = "no_std")))]
+		{
+			self.sender = Some(std::thread::current().id());
+		}
+	}
+
+	#[inline(always)]
+	fn bump_seq(&mut self) {
+		#[cfg(all(feature = "paranoid", not(feature = "no_std")))]
+		{
+			self.seq += 1;
+		}
+	}
+
+	// Records that the waiters have been notified of every change so far
+	#[cfg(all(feature = "paranoid", not(feature = "no_std")))]
+	#[inline(always)]
+	fn notified(&self) {
+		self.notified_seq.set(self.seq);
+	}
+
+	// Every change is notified under the same acquisition of the lock that made it, so a waiter can never see one that hasn't been
+	#[cfg(all(feature = "paranoid", not(feature = "no_std")))]
+	#[track_caller]
+	fn check_notified(&self) {
+		assert_eq!(
+			self.seq,
+			self.notified_seq.get(),
+			"thread beam lost a notification: the state changed without waking the receiving side"
+		);
+	}
+
+	// Must only be called while RESERVED is set, with the slot fully written
+	#[cfg(all(not(feature = "safe"), not(feature = "no_std")))]
+	#[inline(always)]
+	unsafe fn commit_reserved(&mut self) {
+		debug_assert!(self.flags.contains(ThreadBeamFlags::RESERVED));
+		self.flags &= !ThreadBeamFlags::RESERVED;
+		self.sent();
+	}
+
+	#[inline(always)]
+	pub fn take_data(&mut self) -> T {
+		debug_assert!(self.has_data());
+		self.flags &= !ThreadBeamFlags::HAS_DATA;
+		#[cfg(not(feature = "safe"))]
+		{
+			unsafe { self.data.assume_init_read() }
+		}
+		#[cfg(feature = "safe")]
+		{
+			self.data.take().expect("thread beam has no value to take")
+		}
+	}
+
+	// Must only be called while HAS_DATA is set, and `seen` hasn't seen the value yet
+	#[cfg_attr(not(feature = "broadcast"), allow(unused_variables))]
+	#[inline(always)]
+	pub fn recv_data(&mut self, seen: &Seen) -> T {
+		// The value stays in the slot until the last receiving side that hasn't had a clone of it takes it
+		#[cfg(feature = "broadcast")]
+		if let Some(clone) = self.broadcast.clone_data.filter(|_| self.uncloned_receivers() > 1) {
+			self.broadcast.cloned += 1;
+			seen.set(self.broadcast.generation);
+			return clone(self.data_ref());
+		}
+		self.take_data()
+	}
+
+	// Whether receiving through `seen` would move the value out of the slot rather than take a clone of it
+	#[inline(always)]
+	pub fn holds_original(&self, seen: &Seen) -> bool {
+		self.has_data() && !self.has_seen(seen) && self.uncloned_receivers() == 1
+	}
+
+	// How many receiving sides are yet to take a clone of the value in the slot, or the value itself
+	#[inline(always)]
+	fn uncloned_receivers(&self) -> usize {
+		#[cfg(feature = "broadcast")]
+		return self.broadcast.receivers - self.broadcast.cloned;
+
+		#[cfg(not(feature = "broadcast"))]
+		return 1;
+	}
+
+	#[inline(always)]
+	pub fn last_receiver(&self) -> bool {
+		#[cfg(feature = "broadcast")]
+		return self.broadcast.receivers == 1;
+
+		#[cfg(not(feature = "broadcast"))]
+		return true;
+	}
+
+	// Whether the receiving side that `seen` belongs to has already had a clone of the value in the slot
+	#[cfg_attr(not(feature = "broadcast"), allow(unused_variables))]
+	#[inline(always)]
+	fn has_seen(&self, seen: &Seen) -> bool {
+		#[cfg(feature = "broadcast")]
+		return self.has_data() && self.broadcast.cloned != 0 && seen.get() == self.broadcast.generation;
+
+		#[cfg(not(feature = "broadcast"))]
+		return false;
+	}
+
+	#[inline(always)]
+	pub fn peek_data(&self) -> Option<&T> {
+		// HAS_DATA stays set, so the value is still moved out (or dropped) exactly once later on
+		self.has_data().then(|| self.data_ref())
+	}
+
+	// Must only be called while HAS_DATA is set
+	#[inline(always)]
+	pub fn data_ref(&self) -> &T {
+		debug_assert!(self.has_data());
+		#[cfg(not(feature = "safe"))]
+		{
+			unsafe { self.data.assume_init_ref() }
+		}
+		#[cfg(feature = "safe")]
+		{
+			self.data.as_ref().expect("thread beam has no value to lend")
+		}
+	}
+
+	#[inline(always)]
+	pub fn has_data(&self) -> bool {
+		self.flags & ThreadBeamFlags::HAS_DATA != ThreadBeamFlags::empty()
+	}
+
+	#[inline(always)]
+	pub fn tx_alive(&self) -> bool {
+		self.flags.contains(ThreadBeamFlags::TX)
+	}
+
+	#[inline(always)]
+	pub fn rx_alive(&self) -> bool {
+		self.flags.contains(ThreadBeamFlags::RX)
+	}
+
+	#[cfg(feature = "no_std")]
+	#[inline(always)]
+	pub fn has_waiter(&self) -> bool {
+		self.flags.contains(ThreadBeamFlags::WAITING)
+	}
+
+	#[inline(always)]
+	pub fn set_waiting(&mut self, waiting: bool) {
+		self.flags.set(ThreadBeamFlags::WAITING, waiting);
+	}
+
+	#[inline(always)]
+	pub fn can_send(&self) -> bool {
+		// With more than one sending side, the first value sent wins, and once either side has closed, nothing more can be sent
+		!self.hung_up() && !self.flags.intersects(ThreadBeamFlags::HAS_DATA | ThreadBeamFlags::RESERVED)
+	}
+
+	// What a receiving side finds: a value that has been sent always wins over the sending side having hung up, since the sending side
+	// may well send and then drop before the receiving side gets to look. Receives go through this rather than checking `hung_up` first,
+	// and those that borrow the value in place instead of taking it go through `poll_ready`, which makes the same call. Only
+	// `recv_fail_fast` checks for a hang-up first, by asking to.
+	#[inline(always)]
+	pub fn poll_data(&mut self, seen: &Seen) -> core::task::Poll<Option<T>> {
+		self.poll_ready(seen).map(|has_data| has_data.then(|| self.recv_data(seen)))
+	}
+
+	// Whether there is a value for the receiving side that `seen` belongs to, once there is one or the thread beam has hung up
+	#[inline(always)]
+	pub fn poll_ready(&self, seen: &Seen) -> core::task::Poll<bool> {
+		if self.has_data() && !self.has_seen(seen) {
+			core::task::Poll::Ready(true)
+		} else if self.hung_up() {
+			core::task::Poll::Ready(false)
+		} else {
+			core::task::Poll::Pending
+		}
+	}
+
+	#[inline(always)]
+	pub fn hung_up(&self) -> bool {
+		self.flags & (ThreadBeamFlags::TX | ThreadBeamFlags::RX) != (ThreadBeamFlags::TX | ThreadBeamFlags::RX)
+	}
+
+	#[cfg(any(feature = "async", not(feature = "no_std")))]
+	#[inline(always)]
+	pub fn register_waker(&mut self, waker: &core::task::Waker) {
+		if !matches!(&self.waker, Some(registered) if registered.will_wake(waker)) {
+			self.waker = Some(waker.clone());
+		}
+	}
+
+	// Taken under the lock, but should be woken after releasing it, in case waking polls a future inline
+	#[must_use]
+	#[inline(always)]
+	pub fn take_waker(&mut self) -> Option<core::task::Waker> {
+		#[cfg(any(feature = "async", not(feature = "no_std")))]
+		return self.waker.take();
+
+		#[cfg(not(any(feature = "async", not(feature = "no_std"))))]
+		return None;
+	}
+
+	#[must_use]
+	#[inline(always)]
+	pub fn drop_tx(&mut self) -> bool {
+		debug_assert!(self.senders > 0, "sending side of thread beam dropped twice");
+		self.senders -= 1;
+		if self.senders == 0 {
+			self.flags &= !ThreadBeamFlags::TX;
+			self.bump_seq();
+		}
+		self.unreferenced()
+	}
+
+	#[must_use]
+	#[cfg_attr(not(feature = "broadcast"), allow(unused_variables))]
+	#[inline(always)]
+	pub fn drop_rx(&mut self, seen: &Seen) -> bool {
+		#[cfg(feature = "broadcast")]
+		{
+			debug_assert!(self.broadcast.receivers > 0, "receiving side of thread beam dropped twice");
+			if self.has_seen(seen) {
+				self.broadcast.cloned -= 1;
+			}
+			self.broadcast.receivers -= 1;
+			if self.broadcast.receivers == 0 {
+				self.flags &= !ThreadBeamFlags::RX;
+			}
+		}
+		#[cfg(not(feature = "broadcast"))]
+		{
+			debug_assert!(self.has_receiver(), "receiving side of thread beam dropped twice");
+			self.flags &= !(ThreadBeamFlags::RX | ThreadBeamFlags::RX_CLOSED);
+		}
+		self.unreferenced()
+	}
+
+	// Hangs up every sending side at once, but leaves them counted until they are dropped, so that closing never frees the thread beam
+	#[inline(always)]
+	pub fn close_tx(&mut self) {
+		self.flags &= !ThreadBeamFlags::TX;
+		self.bump_seq();
+	}
+
+	#[inline(always)]
+	pub fn close_rx(&mut self) {
+		// Without a count of the receiving sides, a closed one has to be remembered until it's dropped
+		#[cfg(not(feature = "broadcast"))]
+		if self.rx_alive() {
+			self.flags |= ThreadBeamFlags::RX_CLOSED;
+		}
+		self.flags &= !ThreadBeamFlags::RX;
+	}
+
+	#[cfg(feature = "broadcast")]
+	#[inline(always)]
+	pub fn clone_rx(&mut self)
+	where
+		T: Clone,
+	{
+		self.broadcast.receivers += 1;
+		self.broadcast.clone_data = Some(T::clone);
+	}
+
+	#[inline(always)]
+	pub fn clone_tx(&mut self) {
+		self.senders += 1;
+	}
+
+	#[inline(always)]
+	pub fn downgrade_tx(&mut self) {
+		self.weak_senders += 1;
+	}
+
+	#[must_use]
+	#[inline(always)]
+	pub fn upgrade_tx(&mut self) -> bool {
+		// Like `Weak::upgrade`, a sending side can't be brought back once they have all been dropped
+		if self.senders == 0 || self.hung_up() {
+			return false;
+		}
+		self.senders += 1;
+		true
+	}
+
+	#[must_use]
+	#[inline(always)]
+	pub fn drop_weak_tx(&mut self) -> bool {
+		self.weak_senders -= 1;
+		self.unreferenced()
+	}
+
+	// Only checked under the lock, in the same critical section that lets go of a side, so however the sides race to drop, exactly one of
+	// them sees the thread beam become unreferenced and frees it, and only after the others have released the lock for the last time.
+	// This counts the sides rather than checking their flags, since a side that has been closed is still around until it is dropped.
+	#[inline(always)]
+	fn unreferenced(&self) -> bool {
+		self.weak_senders == 0 && self.senders == 0 && !self.has_receiver()
+	}
+
+	// Whether any receiving side is still around, closed or not
+	#[inline(always)]
+	fn has_receiver(&self) -> bool {
+		#[cfg(feature = "broadcast")]
+		return self.broadcast.receivers != 0;
+
+		#[cfg(not(feature = "broadcast"))]
+		return self.flags.intersects(ThreadBeamFlags::RX | ThreadBeamFlags::RX_CLOSED);
+	}
+}
+impl<T> Drop for ThreadBeamState<T> {
+	#[inline(always)]
+	fn drop(&mut self) {
+		debug_assert!(
+			!self.flags.intersects(ThreadBeamFlags::TX | ThreadBeamFlags::RX),
+			"thread beam deallocated while one of its sides is still alive"
+		);
+
+		if self.has_data() {
+			#[cfg(feature = "debug-unconsumed")]
+			debug_unconsumed::unconsumed(self.id, core::any::type_name::<T>());
+
+			#[cfg(not(feature = "no_std"))]
+			if let Some(deferred_drop) = self.deferred_drop.take() {
+				deferred_drop.defer(self.take_data());
+				return;
+			}
+
+			drop(self.take_data());
+		}
+	}
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_thread_beam() {
+	let (tx, rx) = channel::<String>();
+
+	let t = std::thread::spawn(move || {
+		tx.send(String::from("Hello, world!")).unwrap();
+	});
+
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+	t.join().unwrap();
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_dropped_thread_beam() {
+	let (tx, rx) = channel::<String>();
+	drop(tx);
+	assert_eq!(rx.recv(), None);
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_dropped_thread_beam_2() {
+	let (tx, rx) = channel::<String>();
+
+	let t = std::thread::spawn(move || {
+		std::thread::sleep(std::time::Duration::from_secs(1));
+		drop(tx);
+	});
+
+	assert_eq!(rx.recv(), None);
+	t.join().unwrap();
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_dropped_thread_beam_3() {
+	let (tx, rx) = channel::<String>();
+
+	let t = std::thread::spawn(move || {
+		drop(tx);
+	});
+
+	assert_eq!(rx.recv(), None);
+	t.join().unwrap();
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_weird_usage() {
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_weird_usage_2() {
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || {
+		assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+	});
+	tx.send(String::from("Hello, world!")).unwrap();
+	t.join().unwrap();
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_never_recv() {
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	drop(rx);
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_drop_rx_then_send() {
+	let (tx, rx) = channel::<String>();
+	drop(rx);
+	assert_eq!(tx.send(String::from("Hello, world!")), Err(SendError(String::from("Hello, world!"))));
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_send_at_scope_end() {
+	let mut rxs = Vec::new();
+	std::thread::scope(|s| {
+		for i in 0..64 {
+			let (tx, rx) = channel::<String>();
+			rxs.push(rx);
+			s.spawn(move || {
+				// Send as the very last thing the scoped thread does
+				tx.send(i.to_string()).unwrap();
+			});
+		}
+	});
+	for (i, rx) in rxs.into_iter().enumerate() {
+		assert_eq!(rx.recv(), Some(i.to_string()));
+	}
+
+	// Receiver dropped while the scoped thread is still sending
+	std::thread::scope(|s| {
+		let (tx, rx) = channel::<String>();
+		s.spawn(move || {
+			let _ = tx.send(String::from("Hello, world!"));
+		});
+		drop(rx);
+	});
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_poll_once() {
+	use core::task::Poll;
+
+	let (tx, rx) = channel::<String>();
+
+	let mut registered = 0;
+	assert_eq!(rx.poll_once(|| registered += 1), Poll::Pending);
+	assert_eq!(registered, 1);
+
+	let t = std::thread::spawn(move || {
+		std::thread::sleep(std::time::Duration::from_millis(100));
+		tx.send(String::from("Hello, world!")).unwrap();
+	});
+
+	let hello = loop {
+		match rx.poll_once(|| registered += 1) {
+			Poll::Ready(hello) => break hello,
+			Poll::Pending => std::thread::yield_now(),
+		}
+	};
+	assert_eq!(hello.as_deref(), Some("Hello, world!"));
+	assert!(registered > 1);
+
+	// The value has been taken and the sender is gone
+	assert_eq!(rx.poll_once(|| unreachable!()), Poll::Ready(None));
+	t.join().unwrap();
+}
+
+#[cfg(feature = "no_std")]
+#[test]
+fn test_inline_thread_beam() {
+	let mut beam = ThreadBeamInline::<String>::new();
+
+	let (tx, rx) = beam.split();
+	std::thread::scope(|s| {
+		s.spawn(move || tx.send(String::from("Hello, world!")).unwrap());
+		assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+	});
+
+	// Storage can be reused once both sides are gone
+	let (tx, rx) = beam.split();
+	tx.send(String::from("Hello, world!")).unwrap();
+	drop(rx);
+
+	let (tx, rx) = beam.split();
+	drop(tx);
+	assert_eq!(rx.recv(), None);
+}
+
+#[cfg(all(feature = "no_std", feature = "alloc"))]
+#[test]
+fn test_yield_hook() {
+	use std::{
+		sync::atomic::{AtomicUsize, Ordering},
+		time::{Duration, Instant},
+	};
+
+	static YIELDS: AtomicUsize = AtomicUsize::new(0);
+	set_yield_hook(|| {
+		YIELDS.fetch_add(1, Ordering::Relaxed);
+		std::thread::yield_now();
+	});
+
+	let (tx, rx) = channel::<String>();
+	let sent = std::thread::spawn(move || {
+		std::thread::sleep(Duration::from_millis(100));
+		tx.send(String::from("Hello, world!")).unwrap();
+		Instant::now()
+	});
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+	let received = Instant::now();
+
+	assert!(received.duration_since(sent.join().unwrap()) < Duration::from_millis(500));
+	assert!(YIELDS.load(Ordering::Relaxed) > 0);
+}
+
+#[cfg(all(feature = "no_std", debug_assertions))]
+#[test]
+fn test_spin_starvation() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	static YIELDS: AtomicUsize = AtomicUsize::new(0);
+	fn mock_yield() {
+		YIELDS.fetch_add(1, Ordering::Relaxed);
+	}
+
+	// With a yield hook, every snooze once the backoff has topped out yields, however long the wait
+	let mut backoff = r#impl::Backoff::with_patience(4);
+	for _ in 0..100 {
+		backoff.snooze_with(Some(mock_yield));
+	}
+	assert_eq!(YIELDS.load(Ordering::Relaxed), 100 - r#impl::Backoff::SPIN_LIMIT as usize);
+
+	// Without one, a wait that tops out the backoff more times than it has patience for is taken for starvation
+	let mut backoff = r#impl::Backoff::with_patience(4);
+	for _ in 0..r#impl::Backoff::SPIN_LIMIT + 3 {
+		backoff.snooze_with(None);
+	}
+	let panic = std::panic::catch_unwind(move || backoff.snooze_with(None)).unwrap_err();
+	let message = panic.downcast_ref::<&str>().copied().unwrap_or_default();
+	assert_eq!(message, "possible single-core spin starvation: did you forget to set a yield hook?");
+
+	// No patience at all turns the check off
+	let mut backoff = r#impl::Backoff::with_patience(0);
+	for _ in 0..100 {
+		backoff.snooze_with(None);
+	}
+}
+
+#[cfg(all(feature = "no_std", feature = "alloc"))]
+#[test]
+fn test_recv_deadline() {
+	let (tx, rx) = channel::<String>();
+
+	let mut ticks = 0;
+	assert_eq!(
+		rx.recv_deadline(|| {
+			ticks += 1;
+			ticks > 10
+		}),
+		Err(RecvTimeoutError::Timeout)
+	);
+	assert_eq!(ticks, 11);
+
+	// A value that is already there wins over an expired deadline
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv_deadline(|| true).as_deref(), Ok("Hello, world!"));
+	assert_eq!(rx.recv_deadline(|| true), Err(RecvTimeoutError::Disconnected));
+}
+
+#[cfg(all(feature = "no_std", feature = "alloc"))]
+#[test]
+fn test_on_ready() {
+	use core::sync::atomic::{AtomicUsize, Ordering};
+
+	static READY: AtomicUsize = AtomicUsize::new(0);
+	fn ready() {
+		READY.fetch_add(1, Ordering::SeqCst);
+	}
+
+	// Fired once by the send, not again by the drop that follows it
+	let (tx, rx) = channel::<String>();
+	rx.on_ready(ready);
+	assert_eq!(READY.load(Ordering::SeqCst), 0);
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(READY.load(Ordering::SeqCst), 1);
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+
+	let (tx, rx) = channel::<String>();
+	rx.on_ready(ready);
+	drop(tx);
+	assert_eq!(READY.load(Ordering::SeqCst), 2);
+	assert_eq!(rx.recv(), None);
+
+	// Already ready, so it's called straight away
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	rx.on_ready(ready);
+	assert_eq!(READY.load(Ordering::SeqCst), 3);
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_send_receipt() {
+	let (tx, rx) = channel::<String>();
+
+	let receipt = tx.send_with_receipt(String::from("Hello, world!")).unwrap();
+	assert!(!receipt.is_consumed());
+
+	// The receipt doesn't count as a sending side
+	assert!(rx.is_disconnected());
+
+	let t = std::thread::spawn(move || {
+		std::thread::sleep(std::time::Duration::from_millis(100));
+		assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+	});
+
+	assert!(receipt.wait_consumed());
+	assert!(receipt.is_consumed());
+	t.join().unwrap();
+
+	let (tx, rx) = channel::<String>();
+	let receipt = tx.send_with_receipt(String::from("Hello, world!")).unwrap();
+	drop(rx);
+	assert!(!receipt.wait_consumed());
+	assert!(!receipt.is_consumed());
+}
+
+#[cfg(all(feature = "debug-unconsumed", any(feature = "alloc", not(feature = "no_std"))))]
+#[test]
+fn test_unconsumed_hook() {
+	static UNCONSUMED: std::sync::Mutex<Vec<(usize, &'static str)>> = std::sync::Mutex::new(Vec::new());
+	set_unconsumed_hook(|id, type_name| UNCONSUMED.lock().unwrap().push((id, type_name)));
+
+	let (tx, rx) = channel::<String>();
+	let received = rx.id();
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+
+	let (tx, rx) = channel::<String>();
+	let unconsumed = tx.id();
+	assert_eq!(unconsumed, rx.id());
+	tx.send(String::from("Hello, world!")).unwrap();
+	drop(rx);
+
+	let unconsumed_ids = UNCONSUMED.lock().unwrap();
+	assert!(unconsumed_ids.contains(&(unconsumed, core::any::type_name::<String>())));
+	assert!(!unconsumed_ids.iter().any(|(id, _)| *id == received));
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_on_value() {
+	let (tx, rx) = channel::<String>();
+	let (done_tx, done_rx) = std::sync::mpsc::channel();
+	rx.on_value(move |hello| done_tx.send(hello).unwrap());
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(done_rx.recv().unwrap().as_deref(), Some("Hello, world!"));
+
+	let (tx, rx) = channel::<String>();
+	let (done_tx, done_rx) = std::sync::mpsc::channel();
+	rx.on_value(move |hello| done_tx.send(hello).unwrap());
+	drop(tx);
+	assert_eq!(done_rx.recv().unwrap(), None);
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_send_converting() {
+	use std::{rc::Rc, sync::Arc};
+
+	let (tx, rx) = channel::<Arc<str>>();
+
+	let t = std::thread::spawn(move || {
+		let local: Rc<str> = Rc::from("Hello, world!");
+		tx.send_converting(local, |local| Arc::from(&*local)).unwrap();
+	});
+
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+	t.join().unwrap();
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_send_cloned() {
+	let hello = String::from("Hello, world!");
+
+	let (tx, rx) = channel::<String>();
+	tx.send_cloned(&hello).unwrap();
+	assert_eq!(rx.recv().as_deref(), Some(hello.as_str()));
+
+	let (tx, rx) = channel::<String>();
+	drop(rx);
+	assert_eq!(tx.send_cloned(&hello).unwrap_err().0, hello);
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_defer_drops() {
+	use std::{
+		sync::{
+			atomic::{AtomicBool, Ordering},
+			Arc,
+		},
+		time::{Duration, Instant},
+	};
+
+	struct SlowDrop(Arc<AtomicBool>);
+	impl Drop for SlowDrop {
+		fn drop(&mut self) {
+			std::thread::sleep(Duration::from_millis(500));
+			self.0.store(true, Ordering::SeqCst);
+		}
+	}
+
+	let drop_thread = DropThread::new();
+	let dropped = Arc::new(AtomicBool::new(false));
+
+	let (tx, rx) = channel::<SlowDrop>();
+	let rx = rx.defer_drops(&drop_thread);
+	tx.send(SlowDrop(dropped.clone())).unwrap();
+
+	let start = Instant::now();
+	drop(rx);
+	assert!(start.elapsed() < Duration::from_millis(250));
+	assert!(!dropped.load(Ordering::SeqCst));
+
+	drop(drop_thread);
+	assert!(dropped.load(Ordering::SeqCst));
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_newtype_conversions() {
+	#[repr(transparent)]
+	#[derive(Debug, PartialEq)]
+	struct UserId(u64);
+	impl From<u64> for UserId {
+		fn from(id: u64) -> Self {
+			Self(id)
+		}
+	}
+	impl From<UserId> for u64 {
+		fn from(id: UserId) -> Self {
+			id.0
+		}
+	}
+
+	let (tx, rx) = channel::<u64>();
+	tx.send(42).unwrap();
+	assert_eq!(rx.recv_wrap::<UserId>(), Some(UserId(42)));
+
+	let (tx, rx) = channel::<u64>();
+	tx.send_unwrap(UserId(42)).unwrap();
+	assert_eq!(rx.recv(), Some(42));
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_recv_or_work() {
+	use std::{cell::RefCell, collections::VecDeque};
+
+	struct Deque(RefCell<VecDeque<Box<dyn FnOnce()>>>);
+	impl WorkDeque for Deque {
+		type Task = Box<dyn FnOnce()>;
+
+		fn pop(&self) -> Option<Self::Task> {
+			self.0.borrow_mut().pop_front()
+		}
+	}
+
+	let (tx, rx) = channel::<String>();
+	let (go_tx, go_rx) = std::sync::mpsc::channel::<()>();
+	let t = std::thread::spawn(move || {
+		go_rx.recv().unwrap();
+		tx.send(String::from("Hello, world!")).unwrap();
+	});
+
+	let ran = std::rc::Rc::new(RefCell::new(Vec::new()));
+	let deque = Deque(RefCell::new(VecDeque::new()));
+	for i in 0..3 {
+		let ran = ran.clone();
+		let go_tx = go_tx.clone();
+		deque.0.borrow_mut().push_back(Box::new(move || {
+			ran.borrow_mut().push(i);
+			if i == 2 {
+				// The value can only be sent once all queued work has run
+				go_tx.send(()).unwrap();
+			}
+		}));
+	}
+
+	assert_eq!(rx.recv_or_work(&deque).as_deref(), Some("Hello, world!"));
+	assert_eq!(*ran.borrow(), [0, 1, 2]);
+	t.join().unwrap();
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_same_thread_orderings() {
+	use core::task::Poll;
+
+	// Send, then receive
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+
+	// Drop the sender, then receive
+	let (tx, rx) = channel::<String>();
+	drop(tx);
+	assert_eq!(rx.recv(), None);
+
+	// Poll while nothing has been sent yet, then send and receive
+	let (tx, rx) = channel::<String>();
+	assert_eq!(rx.poll_once(|| ()), Poll::Pending);
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+
+	// Receive through a non-blocking poll, then a blocking receive sees nothing left
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.poll_once(|| ()), Poll::Ready(Some(String::from("Hello, world!"))));
+	assert_eq!(rx.recv(), None);
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_recv_or_compute() {
+	use std::time::Duration;
+
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || tx.send(String::from("Hello from the worker!")).unwrap());
+	let hello = rx.recv_or_compute(Duration::from_secs(5), || String::from("Hello from the fallback!"));
+	assert_eq!(hello, "Hello from the worker!");
+	t.join().unwrap();
+
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || {
+		std::thread::sleep(Duration::from_millis(500));
+		// The receiver has given up by now
+		assert!(tx.send(String::from("Hello from the worker!")).is_err());
+	});
+	let hello = rx.recv_or_compute(Duration::from_millis(50), || String::from("Hello from the fallback!"));
+	assert_eq!(hello, "Hello from the fallback!");
+	t.join().unwrap();
+
+	let (tx, rx) = channel::<String>();
+	drop(tx);
+	let hello = rx.recv_or_compute(Duration::from_secs(5), || String::from("Hello from the fallback!"));
+	assert_eq!(hello, "Hello from the fallback!");
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_channel_tagged() {
+	struct Config;
+
+	let (tx, rx) = channel_tagged::<Config, String>();
+	assert_eq!(core::mem::size_of_val(&tx), core::mem::size_of::<ThreadBeamTx<String>>());
+	assert_eq!(core::mem::size_of_val(&rx), core::mem::size_of::<ThreadBeamRx<String>>());
+	assert_eq!(rx.poll_once(|| ()), core::task::Poll::Pending);
+
+	// The tag survives downgrading and upgrading the sending side
+	let weak: WeakThreadBeamTx<String, Config> = tx.downgrade();
+	let upgraded: ThreadBeamTx<String, Config> = weak.upgrade().unwrap();
+	drop(upgraded);
+
+	tx.send(String::from("Hello, world!")).unwrap();
+	let mut iter: IntoIter<String, Config> = rx.into_iter();
+	assert_eq!(iter.next().as_deref(), Some("Hello, world!"));
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_spawn_sync() {
+	use std::sync::atomic::{AtomicBool, Ordering};
+
+	let finished = AtomicBool::new(false);
+	let (hello, completed) = spawn_sync(|tx| {
+		tx.send(String::from("Hello, world!")).unwrap();
+		std::thread::sleep(std::time::Duration::from_millis(100));
+		finished.store(true, Ordering::SeqCst);
+		42
+	});
+	assert!(finished.load(Ordering::SeqCst));
+	assert_eq!(hello.as_deref(), Some("Hello, world!"));
+	assert_eq!(completed.ok(), Some(42));
+
+	let (hello, completed) = spawn_sync(|tx: ThreadBeamTx<String>| {
+		drop(tx);
+		panic!("worker failed");
+	});
+	assert_eq!(hello, None);
+	assert!(completed.is_err());
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_recv_write() {
+	let mut out = None;
+
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert!(rx.recv_write(&mut out));
+	assert_eq!(out.as_deref(), Some("Hello, world!"));
+
+	let (tx, rx) = channel::<String>();
+	drop(tx);
+	assert!(!rx.recv_write(&mut out));
+	assert_eq!(out.as_deref(), Some("Hello, world!"));
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_send_then_drop_race() {
+	for i in 0..2000 {
+		let (tx, rx) = channel::<usize>();
+		let t = std::thread::spawn(move || {
+			tx.send(i).unwrap();
+		});
+		assert_eq!(rx.recv(), Some(i));
+		t.join().unwrap();
+	}
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_send_then_drop_while_waiting() {
+	// The receiving side is already waiting (spinning, with `no_std`) when the sending side sends and hangs up straight afterwards, so
+	// the hang-up can land between two of its checks, and the value must still win
+	let (beams_tx, beams_rx) = std::sync::mpsc::channel::<(usize, ThreadBeamTx<usize>)>();
+	let sender = std::thread::spawn(move || {
+		for (i, tx) in beams_rx {
+			while !tx.has_waiter() {
+				std::thread::yield_now();
+			}
+			tx.send(i).unwrap();
+		}
+	});
+	// Without a yield hook, a spinning receiving side only gives the sending thread its core back when it's preempted
+	let iterations = if cfg!(feature = "no_std") { 250 } else { 5000 };
+	for i in 0..iterations {
+		let (tx, rx) = channel::<usize>();
+		beams_tx.send((i, tx)).unwrap();
+		assert_eq!(rx.recv(), Some(i), "value lost to the hang-up on iteration {i}");
+	}
+	drop(beams_tx);
+	sender.join().unwrap();
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_send_then_drop_delivers() {
+	// The value is there and the sending side has hung up by the time anything looks, and the value must win
+	let mut state = ThreadBeamState::new();
+	state.set_data(42);
+	let _ = state.drop_tx();
+	assert!(state.hung_up());
+	assert_eq!(state.poll_data(&Seen::new()), core::task::Poll::Ready(Some(42)));
+	assert_eq!(state.poll_data(&Seen::new()), core::task::Poll::Ready(None));
+	let _ = state.drop_rx(&Seen::new());
+
+	let sent = || {
+		let (tx, rx) = channel::<u32>();
+		// Sending consumes the sending side, so it has already been dropped by the time this returns
+		tx.send(42).unwrap();
+		rx
+	};
+	assert_eq!(sent().recv(), Some(42));
+	assert_eq!(sent().recv_streaming(), Some(42));
+	assert_eq!(sent().try_recv(), Ok(42));
+	#[cfg(not(feature = "no_std"))]
+	assert_eq!(sent().recv_timeout(std::time::Duration::ZERO), Ok(42));
+
+	let (tx, rx) = channel::<u32>();
+	tx.send_streaming(42).unwrap();
+	drop(tx);
+	assert_eq!(rx.recv_streaming(), Some(42));
+	assert_eq!(rx.recv_streaming(), None);
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_zst_signal() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	let (tx, rx) = channel::<()>();
+	let t = std::thread::spawn(move || tx.send(()).unwrap());
+	assert_eq!(rx.recv(), Some(()));
+	t.join().unwrap();
+
+	// Zero-sized types with a destructor are still dropped exactly once
+	static DROPS: AtomicUsize = AtomicUsize::new(0);
+	struct Signal;
+	impl Drop for Signal {
+		fn drop(&mut self) {
+			DROPS.fetch_add(1, Ordering::SeqCst);
+		}
+	}
+
+	let (tx, rx) = channel::<Signal>();
+	tx.send(Signal).unwrap();
+	assert!(rx.recv().is_some());
+	assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+
+	let (tx, rx) = channel::<Signal>();
+	tx.send(Signal).unwrap();
+	drop(rx);
+	assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_drop_orderings() {
+	for send in [false, true] {
+		for tx_first in [false, true] {
+			let (tx, rx) = channel::<String>();
+			let tx = if send {
+				tx.send(String::from("Hello, world!")).unwrap();
+				None
+			} else {
+				Some(tx)
+			};
+			if tx_first {
+				drop(tx);
+				drop(rx);
+			} else {
+				drop(rx);
+				drop(tx);
+			}
+		}
+	}
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_try_channel_result() {
+	let (tx, rx) = try_channel_result::<u32, String>();
+	tx.send_ok(42).unwrap();
+	assert_eq!(rx.recv(), Some(Ok(42)));
+
+	let (tx, rx) = try_channel_result::<u32, String>();
+	tx.send_err(String::from("Goodbye, world!")).unwrap();
+	assert_eq!(rx.recv(), Some(Err(String::from("Goodbye, world!"))));
+
+	let (tx, rx) = try_channel_result::<u32, String>();
+	drop(tx);
+	assert_eq!(rx.recv(), None);
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_channel_inline() {
+	let (tx, rx) = channel_inline::<u64, 8>();
+	tx.send(42).unwrap();
+	assert_eq!(rx.recv(), Some(42));
+
+	// Boxing a large payload always fits a pointer-sized budget
+	let (tx, rx) = channel_inline::<Box<[u64; 4096]>, { core::mem::size_of::<usize>() }>();
+	tx.send(Box::new([42; 4096])).unwrap();
+	assert_eq!(rx.recv().map(|table| table[0]), Some(42));
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_delayed_send_never_none() {
+	let threads = (0..4)
+		.map(|_| {
+			std::thread::spawn(|| {
+				for i in 0..50 {
+					let (tx, rx) = channel::<u64>();
+					let t = std::thread::spawn(move || {
+						std::thread::sleep(std::time::Duration::from_micros(i % 10 * 100));
+						tx.send(i).unwrap();
+					});
+					assert_eq!(rx.recv(), Some(i));
+					t.join().unwrap();
+				}
+			})
+		})
+		.collect::<Vec<_>>();
+
+	for t in threads {
+		t.join().unwrap();
+	}
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_recv_timeout() {
+	use std::time::{Duration, Instant};
+
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || {
+		std::thread::sleep(Duration::from_millis(300));
+		tx.send(String::from("Hello, world!")).unwrap();
+	});
+
+	let start = Instant::now();
+	assert_eq!(rx.recv_timeout(Duration::from_millis(50)), Err(RecvTimeoutError::Timeout));
+	assert!(start.elapsed() >= Duration::from_millis(50));
+
+	// The receiver can be retried after a timeout
+	assert_eq!(rx.recv_timeout(Duration::from_secs(5)).as_deref(), Ok("Hello, world!"));
+	assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Err(RecvTimeoutError::Disconnected));
+	t.join().unwrap();
+
+	let (tx, rx) = channel::<String>();
+	drop(tx);
+	assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Err(RecvTimeoutError::Disconnected));
+
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv_timeout(Duration::MAX).as_deref(), Ok("Hello, world!"));
+
+	// Actually waiting with a timeout too large to represent as a deadline
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || {
+		std::thread::sleep(Duration::from_millis(50));
+		tx.send(String::from("Hello, world!")).unwrap();
+	});
+	assert_eq!(rx.recv_timeout(Duration::MAX).as_deref(), Ok("Hello, world!"));
+	t.join().unwrap();
+
+	#[cfg(feature = "broadcast")]
+	{
+		// Wakeups that bring nothing for this receiver, here another clone receiving its copy, neither end the wait early nor restart it
+		let (tx, rx) = channel::<String>();
+		let other = rx.clone();
+		tx.send_streaming(String::from("Hello, world!")).unwrap();
+		assert_eq!(rx.try_recv().as_deref(), Ok("Hello, world!"));
+		let t = std::thread::spawn(move || {
+			std::thread::sleep(Duration::from_millis(150));
+			other.recv_streaming()
+		});
+		let start = Instant::now();
+		assert_eq!(rx.recv_timeout(Duration::from_millis(200)), Err(RecvTimeoutError::Timeout));
+		let elapsed = start.elapsed();
+		assert!(
+			elapsed >= Duration::from_millis(200) && elapsed < Duration::from_millis(340),
+			"{elapsed:?}"
+		);
+		assert_eq!(t.join().unwrap().as_deref(), Some("Hello, world!"));
+		drop(tx);
+	}
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_recv_deadline() {
+	use std::time::{Duration, Instant};
+
+	let (tx, rx) = channel::<String>();
+	let deadline = Instant::now() + Duration::from_millis(50);
+	assert_eq!(rx.recv_deadline(deadline), Err(RecvTimeoutError::Timeout));
+	assert!(Instant::now() >= deadline);
+
+	// A deadline in the past gives up straight away, unless the value is already there
+	let start = Instant::now();
+	assert_eq!(rx.recv_deadline(start - Duration::from_secs(1)), Err(RecvTimeoutError::Timeout));
+	assert!(start.elapsed() < Duration::from_secs(1));
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv_deadline(start - Duration::from_secs(1)).as_deref(), Ok("Hello, world!"));
+	assert_eq!(
+		rx.recv_deadline(Instant::now() + Duration::from_secs(5)),
+		Err(RecvTimeoutError::Disconnected)
+	);
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_recv_with_clock() {
+	use std::{
+		sync::atomic::{AtomicU32, Ordering},
+		time::{Duration, Instant},
+	};
+
+	// A clock that moves forward by `step` every time it's read, and otherwise stands still
+	struct MockClock {
+		start: Instant,
+		reads: AtomicU32,
+		step: Duration,
+	}
+	impl Clock for MockClock {
+		fn now(&self) -> Instant {
+			self.start + self.step * self.reads.fetch_add(1, Ordering::Relaxed)
+		}
+
+		fn poll_interval(&self) -> Option<Duration> {
+			Some(Duration::from_millis(1))
+		}
+	}
+	let clock = |step| MockClock {
+		start: Instant::now(),
+		reads: AtomicU32::new(0),
+		step,
+	};
+
+	// The timeout fires once the clock has been read past it, however little real time has passed
+	let (tx, rx) = channel::<u32>();
+	let ticking = clock(Duration::from_secs(60));
+	let start = Instant::now();
+	assert_eq!(
+		rx.recv_timeout_with_clock(Duration::from_secs(3600), &ticking),
+		Err(RecvTimeoutError::Timeout)
+	);
+	assert!(start.elapsed() < Duration::from_secs(60));
+	assert_eq!(ticking.reads.load(Ordering::Relaxed), 61);
+
+	let ticking = clock(Duration::from_secs(1));
+	assert_eq!(
+		rx.recv_deadline_with_clock(ticking.start + Duration::from_secs(5), &ticking),
+		Err(RecvTimeoutError::Timeout)
+	);
+	assert_eq!(ticking.reads.load(Ordering::Relaxed), 6);
+
+	// With the clock standing still, even the shortest timeout never fires, so a value that takes a while still arrives before it
+	let frozen = clock(Duration::ZERO);
+	let t = std::thread::spawn(move || {
+		std::thread::sleep(Duration::from_millis(50));
+		tx.send(42).unwrap();
+	});
+	assert_eq!(rx.recv_timeout_with_clock(Duration::from_nanos(1), &frozen), Ok(42));
+	t.join().unwrap();
+	assert_eq!(
+		rx.recv_timeout_with_clock(Duration::from_nanos(1), &frozen),
+		Err(RecvTimeoutError::Disconnected)
+	);
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_recv_timeout_owned() {
+	use std::time::Duration;
+
+	let (tx, mut rx) = channel::<String>();
+	let mut tx = Some(tx);
+	let mut timeouts = 0;
+	let hello = loop {
+		match rx.recv_timeout_owned(Duration::from_millis(10)) {
+			Ok(value) => break value,
+			Err((retry, err)) => {
+				assert_eq!(err, RecvTimeoutError::Timeout);
+				timeouts += 1;
+				if timeouts == 2 {
+					tx.take().unwrap().send(String::from("Hello, world!")).unwrap();
+				}
+				rx = retry;
+			}
+		}
+	};
+	assert_eq!(hello, "Hello, world!");
+	assert_eq!(timeouts, 2);
+
+	let (tx, rx) = channel::<String>();
+	drop(tx);
+	assert!(matches!(
+		rx.recv_timeout_owned(Duration::from_secs(5)),
+		Err((_, RecvTimeoutError::Disconnected))
+	));
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_try_recv() {
+	let (tx, rx) = channel::<String>();
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.try_recv().as_deref(), Ok("Hello, world!"));
+
+	// The value has been moved out, so neither side may read it again
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+	assert_eq!(rx.recv(), None);
+
+	let (tx, rx) = channel::<String>();
+	drop(tx);
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_recv_after_try_recv() {
+	use std::sync::Arc;
+
+	// The value is moved out by `try_recv`, so a later `recv` finds nothing and nothing is dropped twice
+	let value = Arc::new(1);
+	let (tx, rx) = channel::<Arc<u32>>();
+	tx.send(value.clone()).unwrap();
+	let received = rx.try_recv().unwrap();
+	assert_eq!(Arc::strong_count(&value), 2);
+	assert_eq!(rx.recv(), None);
+	assert_eq!(Arc::strong_count(&value), 2);
+	drop(received);
+	assert_eq!(Arc::strong_count(&value), 1);
+
+	#[cfg(feature = "broadcast")]
+	{
+		// A clone that took its copy with `try_recv` finds nothing more, while the other clone still gets its own
+		let (tx, rx) = channel::<Arc<u32>>();
+		tx.send(value.clone()).unwrap();
+		let other = rx.clone();
+		drop(rx.try_recv().unwrap());
+		assert!(matches!(rx.try_recv(), Err(TryRecvError::Disconnected)));
+		assert!(rx.recv().is_none());
+		assert!(other.recv().is_some());
+		assert_eq!(Arc::strong_count(&value), 1);
+	}
+
+	// While streaming, a later `recv` gets the next value rather than the one already received
+	let (tx, rx) = channel::<u32>();
+	tx.send_streaming(1).unwrap();
+	assert_eq!(rx.try_recv(), Ok(1));
+	let t = std::thread::spawn(move || tx.send_streaming(2).unwrap());
+	assert_eq!(rx.recv(), Some(2));
+	t.join().unwrap();
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_is_complete() {
+	// Empty but open: more may come
+	let (tx, rx) = channel::<u32>();
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+	assert!(!rx.is_complete());
+
+	// Buffered but open
+	tx.send_streaming(1).unwrap();
+	assert!(!rx.is_complete());
+	assert_eq!(rx.try_recv(), Ok(1));
+	assert!(!rx.is_complete());
+
+	// Buffered with the sending side gone: the last value still has to be drained
+	tx.send_streaming(2).unwrap();
+	drop(tx);
+	assert!(!rx.is_complete());
+	assert_eq!(rx.try_recv(), Ok(2));
+
+	// Closed and drained
+	assert!(rx.is_complete());
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+
+	#[cfg(feature = "broadcast")]
+	{
+		// A clone that has had its copy of the last value is done with it, even while another clone has yet to receive it
+		let (tx, rx) = channel::<u32>();
+		let other = rx.clone();
+		tx.send(3).unwrap();
+		assert_eq!(rx.try_recv(), Ok(3));
+		assert!(rx.is_complete());
+		assert!(!other.is_complete());
+		assert_eq!(other.try_recv(), Ok(3));
+		assert!(other.is_complete());
+	}
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_drain_first() {
+	// One-shot: a value sent right before hanging up is still received, by every kind of receive
+	let (tx, rx) = channel::<u32>();
+	tx.send(1).unwrap();
+	assert_eq!(rx.try_recv(), Ok(1));
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+
+	let (tx, rx) = channel::<u32>();
+	tx.send(2).unwrap();
+	assert_eq!(rx.recv_streaming(), Some(2));
+	assert_eq!(rx.recv_streaming(), None);
+
+	let (tx, rx) = channel::<u32>();
+	tx.send(3).unwrap();
+	assert_eq!(rx.recv(), Some(3));
+
+	// Streaming: whatever is still buffered when the sending side goes is drained before the disconnect shows
+	let (tx, rx) = channel::<u32>();
+	let t = std::thread::spawn(move || {
+		for i in 0..4 {
+			tx.send_streaming(i).unwrap();
+		}
+	});
+	let mut received = Vec::new();
+	while let Some(value) = rx.recv_streaming() {
+		received.push(value);
+	}
+	assert_eq!(received, [0, 1, 2, 3]);
+	t.join().unwrap();
+
+	// Closing the receiving side itself doesn't throw away what was sent either
+	let (tx, rx) = channel::<u32>();
+	tx.send_streaming(4).unwrap();
+	rx.close();
+	assert_eq!(rx.try_recv(), Ok(4));
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_recv_fail_fast() {
+	use std::sync::Arc;
+
+	// While the sending side is alive, it receives like `recv_streaming`
+	let (tx, rx) = channel::<u32>();
+	tx.send_streaming(1).unwrap();
+	assert_eq!(rx.recv_fail_fast(), Some(1));
+
+	// The sending side is handed back rather than dropped, so that it can't hang up before the value is received
+	let t = std::thread::spawn(move || {
+		tx.send_streaming(2).unwrap();
+		tx
+	});
+	assert_eq!(rx.recv_fail_fast(), Some(2));
+	drop(t.join().unwrap());
+	assert_eq!(rx.recv_fail_fast(), None);
+
+	// Once the sending side has gone, a buffered value is dropped rather than received
+	let value = Arc::new(3);
+	let (tx, rx) = channel::<Arc<u32>>();
+	tx.send_streaming(value.clone()).unwrap();
+	drop(tx);
+	assert_eq!(rx.recv_fail_fast(), None);
+	assert_eq!(Arc::strong_count(&value), 1);
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+
+	// Same for a one-shot value
+	let (tx, rx) = channel::<u32>();
+	tx.send(4).unwrap();
+	assert_eq!(rx.recv_fail_fast(), None);
+	assert!(rx.is_complete());
+}
+
+#[cfg(all(feature = "async", any(feature = "alloc", not(feature = "no_std"))))]
+#[test]
+fn test_recv_async() {
+	use std::{
+		future::Future,
+		sync::{
+			atomic::{AtomicUsize, Ordering},
+			Arc,
+		},
+		task::{Context, Poll, Wake, Waker},
+		time::Duration,
+	};
+
+	struct ThreadWaker(std::thread::Thread);
+	impl Wake for ThreadWaker {
+		fn wake(self: Arc<Self>) {
+			self.0.unpark();
+		}
+	}
+
+	struct CountingWaker(AtomicUsize);
+	impl Wake for CountingWaker {
+		fn wake(self: Arc<Self>) {
+			self.0.fetch_add(1, Ordering::SeqCst);
+		}
+	}
+
+	fn block_on<F: Future>(future: F) -> F::Output {
+		let mut future = std::pin::pin!(future);
+		let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+		let mut cx = Context::from_waker(&waker);
+		loop {
+			match future.as_mut().poll(&mut cx) {
+				Poll::Ready(value) => return value,
+				Poll::Pending => std::thread::park(),
+			}
+		}
+	}
+
+	// Data already present on the first poll
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(block_on(rx.recv_async()).as_deref(), Some("Hello, world!"));
+
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || {
+		std::thread::sleep(Duration::from_millis(50));
+		tx.send(String::from("Hello, world!")).unwrap();
+	});
+	assert_eq!(block_on(rx.recv_async()).as_deref(), Some("Hello, world!"));
+	t.join().unwrap();
+
+	// Sender dropped while the future is pending
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || {
+		std::thread::sleep(Duration::from_millis(50));
+		drop(tx);
+	});
+	assert_eq!(block_on(rx.recv_async()), None);
+	t.join().unwrap();
+
+	// Only the most recently registered waker is woken
+	let (tx, rx) = channel::<String>();
+	let mut future = rx.recv_async();
+	let first = Arc::new(CountingWaker(AtomicUsize::new(0)));
+	let second = Arc::new(CountingWaker(AtomicUsize::new(0)));
+	assert!(std::pin::Pin::new(&mut future)
+		.poll(&mut Context::from_waker(&Waker::from(first.clone())))
+		.is_pending());
+	assert!(std::pin::Pin::new(&mut future)
+		.poll(&mut Context::from_waker(&Waker::from(second.clone())))
+		.is_pending());
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(first.0.load(Ordering::SeqCst), 0);
+	assert_eq!(second.0.load(Ordering::SeqCst), 1);
+	assert_eq!(block_on(future).as_deref(), Some("Hello, world!"));
+
+	// Polling by hand, and again after the value has been returned
+	let (tx, rx) = channel::<String>();
+	let mut future = RecvFuture::from(rx);
+	let waker = Waker::from(Arc::new(CountingWaker(AtomicUsize::new(0))));
+	let mut cx = Context::from_waker(&waker);
+	assert!(std::pin::Pin::new(&mut future).poll_recv(&mut cx).is_pending());
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(
+		std::pin::Pin::new(&mut future).poll_recv(&mut cx),
+		Poll::Ready(Some(String::from("Hello, world!")))
+	);
+	assert_eq!(std::pin::Pin::new(&mut future).poll_recv(&mut cx), Poll::Ready(None));
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_try_send() {
+	let (tx, rx) = channel::<String>();
+	assert_eq!(tx.try_send(String::from("Hello, world!")), Ok(()));
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+
+	let (tx, rx) = channel::<String>();
+	drop(rx);
+	match tx.try_send(String::from("Hello, world!")) {
+		Err(TrySendError::Disconnected(value)) => assert_eq!(value, "Hello, world!"),
+		Ok(()) => panic!("sent to a dropped receiver"),
+	}
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_is_disconnected() {
+	let (tx, rx) = channel::<String>();
+	assert!(!tx.is_disconnected());
+	assert!(!rx.is_disconnected());
+	drop(rx);
+	assert!(tx.is_disconnected());
+
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert!(rx.is_disconnected());
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_debug() {
+	let (tx, rx) = channel::<String>();
+	assert_eq!(format!("{tx:?}"), "ThreadBeamTx { has_data: false, tx_alive: true, rx_alive: true }");
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(format!("{rx:?}"), "ThreadBeamRx { has_data: true, tx_alive: false, rx_alive: true }");
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_spawn_named() {
+	let (name, thread) = spawn_named("threadbeam-test", |tx| {
+		tx.send(std::thread::current().name().map(String::from)).unwrap();
+		42
+	})
+	.unwrap();
+
+	assert_eq!(name.flatten().as_deref(), Some("threadbeam-test"));
+	assert_eq!(thread.join().ok(), Some(42));
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_peek() {
+	let (tx, rx) = channel::<String>();
+	assert_eq!(rx.peek(), None);
+
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.peek().as_deref(), Some("Hello, world!"));
+	assert_eq!(rx.peek().as_deref(), Some("Hello, world!"));
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+
+	let (tx, rx) = channel::<String>();
+	drop(tx);
+	assert_eq!(rx.peek(), None);
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_into_iter() {
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+
+	let mut iter = rx.into_iter();
+	assert_eq!(iter.next().as_deref(), Some("Hello, world!"));
+	assert_eq!(iter.next(), None);
+
+	let (tx, rx) = channel::<String>();
+	drop(tx);
+	assert_eq!(rx.into_iter().count(), 0);
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_map() {
+	let (tx, rx) = channel::<&str>();
+	tx.send("42").unwrap();
+	assert_eq!(rx.map(str::parse::<u32>).map(Result::ok).recv(), Some(Some(42)));
+
+	let (tx, rx) = channel::<&str>();
+	drop(tx);
+	assert_eq!(rx.map(|_| -> u32 { panic!("mapped a value that was never sent") }).recv(), None);
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_into_mpsc() {
+	use std::sync::mpsc::RecvError;
+
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	let rx = rx.into_mpsc();
+	assert_eq!(rx.recv().as_deref(), Ok("Hello, world!"));
+	assert_eq!(rx.recv(), Err(RecvError));
+
+	let (tx, rx) = channel::<String>();
+	let rx = rx.into_mpsc();
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv().as_deref(), Ok("Hello, world!"));
+	assert_eq!(rx.recv(), Err(RecvError));
+
+	let (tx, rx) = channel::<String>();
+	let rx = rx.into_mpsc();
+	drop(tx);
+	assert_eq!(rx.recv(), Err(RecvError));
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_streaming() {
+	let (tx, rx) = channel::<u32>();
+	let t = std::thread::spawn(move || {
+		for i in 0..5 {
+			tx.send_streaming(i).unwrap();
+		}
+	});
+
+	let mut received = [0; 5];
+	let mut count = 0;
+	while let Some(value) = rx.recv_streaming() {
+		received[count] = value;
+		count += 1;
+	}
+	assert_eq!(received[..count], [0, 1, 2, 3, 4]);
+	t.join().unwrap();
+
+	// A sender waiting for the slot to free up gives up once the receiver is dropped
+	let (tx, rx) = channel::<u32>();
+	tx.send_streaming(0).unwrap();
+	let t = std::thread::spawn(move || tx.send_streaming(1));
+	std::thread::sleep(core::time::Duration::from_millis(50));
+	drop(rx);
+	assert_eq!(t.join().unwrap(), Err(SendError(1)));
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_send_all() {
+	let (tx, rx) = channel::<u32>();
+	let t = std::thread::spawn(move || tx.send_all(0..100));
+
+	let mut expected = 0;
+	while let Some(value) = rx.recv_streaming() {
+		assert_eq!(value, expected);
+		expected += 1;
+	}
+	assert_eq!(expected, 100);
+	assert_eq!(t.join().unwrap(), Ok(()));
+
+	// Once the receiver is gone, the value that was waiting to be sent comes back along with everything after it
+	let (tx, rx) = channel::<u32>();
+	let t = std::thread::spawn(move || tx.send_all(0..5));
+	assert_eq!(rx.recv_streaming(), Some(0));
+	assert_eq!(rx.recv_streaming(), Some(1));
+	drop(rx);
+	match t.join().unwrap() {
+		Err(SendError(unsent)) => assert!(unsent == [2, 3, 4] || unsent == [3, 4], "{unsent:?}"),
+		Ok(()) => panic!("sent everything to a dropped receiver"),
+	}
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_spawn_checked() {
+	let (rx, thread) = spawn_checked(|tx| {
+		tx.send(String::from("Hello, world!")).unwrap();
+		42
+	});
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+	assert_eq!(thread.join().ok(), Some(Some(42)));
+
+	// Whether the closure runs at all depends on when the thread starts, but if it does, it gets to see the receiver go away
+	let (rx, thread) = spawn_checked(|tx: ThreadBeamTx<String>| {
+		while !tx.is_disconnected() {
+			std::thread::yield_now();
+		}
+	});
+	drop(rx);
+	assert!(thread.join().is_ok());
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_spawn_manual() {
+	let (rx, thread) = spawn_manual(|tx| {
+		tx.send(String::from("Hello, world!")).unwrap();
+		42
+	});
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+	assert_eq!(thread.join().ok(), Some(42));
+
+	// The closure runs even if nobody ends up receiving
+	let (rx, thread) = spawn_manual(|tx: ThreadBeamTx<String>| tx.send(String::from("Hello, world!")).is_err());
+	drop(rx);
+	assert!(thread.join().is_ok());
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_send_with() {
+	let (tx, rx) = channel::<String>();
+	tx.send_with(|| String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+
+	let (tx, rx) = channel::<String>();
+	drop(rx);
+	assert_eq!(tx.send_with(|| panic!("built a value nobody is waiting for")), Err(SendError(None)));
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_spawn_scoped() {
+	let greeting = String::from("Hello, world!");
+	let mut completed = false;
+
+	std::thread::scope(|scope| {
+		let (hello, thread) = spawn_scoped(scope, |tx| {
+			tx.send(greeting.as_str()).unwrap();
+			completed = true;
+		});
+		assert_eq!(hello, Some("Hello, world!"));
+		thread.join().unwrap();
+	});
+
+	assert!(completed);
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_recv_ref() {
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	{
+		let hello = rx.recv_ref().unwrap();
+		assert_eq!(hello.as_str(), "Hello, world!");
+	}
+	assert!(rx.recv_ref().is_none());
+	assert_eq!(rx.recv(), None);
+
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || tx.send(String::from("Hello, world!")).unwrap());
+	assert_eq!(rx.recv_ref().as_deref().map(String::as_str), Some("Hello, world!"));
+	t.join().unwrap();
+
+	let (tx, rx) = channel::<String>();
+	drop(tx);
+	assert!(rx.recv_ref().is_none());
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_recv_cancellable() {
+	use std::time::Duration;
+
+	let cancel = CancelFlag::new();
+
+	let (tx, rx) = channel::<String>();
+	std::thread::scope(|s| {
+		let cancelled = s.spawn(|| {
+			std::thread::sleep(Duration::from_millis(50));
+			cancel.cancel();
+			std::time::Instant::now()
+		});
+		assert_eq!(rx.recv_cancellable(&cancel), None);
+
+		// Woken by the cancel itself, give or take scheduling
+		let latency = cancelled.join().unwrap().elapsed();
+		assert!(latency < Duration::from_millis(500), "{latency:?}");
+	});
+	assert!(tx.send(String::from("Hello, world!")).is_err());
+
+	// A flag that is already cancelled doesn't wait at all
+	let (_tx, rx) = channel::<String>();
+	assert_eq!(rx.recv_cancellable(&cancel), None);
+
+	// A value that is already there wins over cancellation
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv_cancellable(&cancel).as_deref(), Some("Hello, world!"));
+
+	cancel.reset();
+	let (tx, rx) = channel::<String>();
+	drop(tx);
+	assert_eq!(rx.recv_cancellable(&cancel), None);
+	assert!(!cancel.is_cancelled());
+
+	// A value sent while waiting is received
+	let (tx, rx) = channel::<String>();
+	std::thread::scope(|s| {
+		s.spawn(|| {
+			std::thread::sleep(Duration::from_millis(50));
+			tx.send(String::from("Hello, world!")).unwrap();
+		});
+		assert_eq!(rx.recv_cancellable(&cancel).as_deref(), Some("Hello, world!"));
+	});
+}
+
+#[cfg(all(not(feature = "no_std"), not(feature = "safe")))]
+#[test]
+fn test_reserve() {
+	let (tx, rx) = channel::<[u64; 4096]>();
+	let mut writer = tx.reserve().unwrap();
+	writer.with_slot(|slot| {
+		slot.write([0; 4096]);
+	});
+	writer.with_slot(|slot| unsafe { slot.assume_init_mut()[0] = 42 });
+	assert!(!rx.is_ready());
+	unsafe { writer.commit() };
+	assert_eq!(rx.recv().map(|table| table[0]), Some(42));
+
+	// Other senders can't send while the slot is reserved
+	let (tx, rx) = channel::<String>();
+	let other = tx.clone();
+	let writer = tx.reserve().unwrap();
+	assert!(other.send(String::from("Not yet")).is_err());
+	writer.write(String::from("Hello, world!"));
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+
+	// Dropping the writer without committing disconnects instead of leaving the receiver waiting
+	let (tx, rx) = channel::<String>();
+	drop(tx.reserve().unwrap());
+	assert_eq!(rx.recv(), None);
+
+	let (tx, rx) = channel::<String>();
+	drop(rx);
+	assert!(tx.reserve().is_none());
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_recv_guarded() {
+	let (tx, rx) = channel::<String>();
+	let (handle, guard) = rx.recv_guarded();
+	let thread = std::thread::spawn(move || handle.wait());
+	while !tx.has_waiter() {
+		std::thread::yield_now();
+	}
+	guard.cancel();
+	assert_eq!(thread.join().unwrap(), Err(RecvCancelError::Cancelled));
+	assert!(tx.send(String::from("Too late")).is_err());
+
+	// A value that is already there wins over cancellation
+	let (tx, rx) = channel();
+	let (handle, guard) = rx.recv_guarded();
+	tx.send(String::from("Hello, world!")).unwrap();
+	drop(guard);
+	assert_eq!(handle.wait().as_deref(), Ok("Hello, world!"));
+
+	let (tx, rx) = channel::<String>();
+	let (handle, guard) = rx.recv_guarded();
+	drop(tx);
+	assert_eq!(handle.wait(), Err(RecvCancelError::Disconnected));
+	drop(guard);
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_weak_tx() {
+	// Weak sending sides don't keep the receiving side waiting
+	let (tx, rx) = channel::<String>();
+	let weak = tx.downgrade();
+	drop(tx);
+	assert!(weak.upgrade().is_none());
+	assert_eq!(rx.recv(), None);
+	drop(weak);
+
+	// ...and can't be upgraded once the receiving side is gone
+	let (tx, rx) = channel::<String>();
+	let weak = tx.downgrade();
+	drop(rx);
+	assert!(weak.upgrade().is_none());
+	drop(weak);
+	drop(tx);
+
+	// The first value sent wins
+	let (tx, rx) = channel::<String>();
+	let weak = tx.downgrade();
+	let upgraded = weak.upgrade().unwrap();
+	drop(weak);
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(
+		upgraded.send(String::from("Goodbye, world!")),
+		Err(SendError(String::from("Goodbye, world!")))
+	);
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+
+	// The receiving side only sees a hang-up once every upgraded sending side is gone
+	let (tx, rx) = channel::<String>();
+	let weak = tx.downgrade();
+	let upgraded = weak.upgrade().unwrap();
+	drop(tx);
+	assert!(!rx.is_disconnected());
+	drop(upgraded);
+	assert!(rx.is_disconnected());
+	drop(rx);
+	drop(weak);
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_recv_any() {
+	use std::time::Duration;
+
+	let (txs, rxs): (Vec<_>, Vec<_>) = (0..4).map(|_| channel::<usize>()).unzip();
+	let threads = txs
+		.into_iter()
+		.enumerate()
+		.map(|(i, tx)| {
+			std::thread::spawn(move || {
+				if i != 2 {
+					std::thread::sleep(Duration::from_millis(200));
+				}
+				let _ = tx.send(i);
+			})
+		})
+		.collect::<Vec<_>>();
+	assert_eq!(recv_any(rxs), (2, Some(2)));
+	threads.into_iter().for_each(|t| t.join().unwrap());
+
+	// A hang-up counts as ready
+	let (tx, rx) = channel::<usize>();
+	let (_pending_tx, pending_rx) = channel::<usize>();
+	let t = std::thread::spawn(move || {
+		std::thread::sleep(Duration::from_millis(50));
+		drop(tx);
+	});
+	assert_eq!(recv_any(vec![pending_rx, rx]), (1, None));
+	t.join().unwrap();
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_recv_result() {
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv_result().as_deref(), Ok("Hello, world!"));
+
+	let (tx, rx) = channel::<String>();
+	drop(tx);
+	assert_eq!(rx.recv_result(), Err(RecvError));
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_recv_or_else() {
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv_or_else(|| panic!("sender hung up")), "Hello, world!");
+
+	let (tx, rx) = channel::<String>();
+	drop(tx);
+	assert_eq!(rx.recv_or_else(|| String::from("Goodbye, world!")), "Goodbye, world!");
+
+	let (tx, rx) = channel::<u32>();
+	drop(tx);
+	assert_eq!(rx.recv_or(42), 42);
+
+	let (tx, rx) = channel::<bool>();
+	tx.send(true).unwrap();
+	assert!(rx.recv_or_default());
+
+	let (tx, rx) = channel::<bool>();
+	drop(tx);
+	assert!(!rx.recv_or_default());
+}
+
+#[cfg(all(feature = "pool", not(feature = "no_std")))]
+#[test]
+fn test_pool() {
+	let pool = ThreadBeamPool::<String>::new();
+
+	let (tx, rx) = pool.channel();
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+	assert_eq!(pool.idle(), 1);
+
+	// Recycled beams start out fresh, and leftover values are dropped when they're returned
+	let (tx, rx) = pool.channel();
+	assert_eq!(pool.idle(), 0);
+	assert!(!rx.is_disconnected());
+	assert_eq!(rx.peek(), None);
+	tx.send(String::from("Goodbye, world!")).unwrap();
+	drop(rx);
+	assert_eq!(pool.idle(), 1);
+
+	let (tx, rx) = pool.channel();
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+	drop(tx);
+	assert_eq!(rx.recv(), None);
+
+	// Beams that outlive their pool are simply freed
+	let (tx, rx) = pool.channel();
+	drop(pool);
+	drop(tx);
+	drop(rx);
+}
+
+#[cfg(all(feature = "pool", not(feature = "no_std")))]
+#[test]
+fn test_recv_adaptive() {
+	use std::time::Duration;
+
+	let pool = ThreadBeamPool::<u32>::new();
+
+	// Values come through in order whether the sending side is fast enough to be spun for or slow enough to block for
+	for delay in [Duration::ZERO, Duration::from_millis(1)] {
+		for _ in 0..3 {
+			let (tx, rx) = pool.channel();
+			let t = std::thread::spawn(move || {
+				for i in 0..20 {
+					std::thread::sleep(delay);
+					tx.send_streaming(i).unwrap();
+				}
+			});
+			for i in 0..20 {
+				assert_eq!(rx.recv_adaptive(), Some(i));
+			}
+			assert_eq!(rx.recv_adaptive(), None);
+			t.join().unwrap();
+		}
+	}
+
+	// A value that is already there, or a sending side that is already gone, never waits
+	let (tx, rx) = pool.channel();
+	tx.send(42).unwrap();
+	assert_eq!(rx.recv_adaptive(), Some(42));
+	assert_eq!(rx.recv_adaptive(), None);
+}
+
+#[cfg(all(any(feature = "alloc", not(feature = "no_std")), feature = "broadcast"))]
+#[test]
+fn test_broadcast() {
+	let (tx, rx) = channel::<String>();
+	let receivers = [rx.clone(), rx.clone(), rx];
+
+	// Receivers dropped before the send just stop being counted
+	let [early, receivers @ ..] = receivers;
+	drop(early);
+
+	let threads = receivers.map(|rx| std::thread::spawn(move || rx.recv()));
+	tx.send(String::from("Hello, world!")).unwrap();
+	for thread in threads {
+		assert_eq!(thread.join().unwrap().as_deref(), Some("Hello, world!"));
+	}
+
+	let (tx, rx) = channel::<String>();
+	let rx2 = rx.clone();
+	drop(tx);
+	assert_eq!(rx.recv(), None);
+	assert_eq!(rx2.recv(), None);
+
+	let (tx, rx) = channel::<String>();
+	let rx2 = rx.clone();
+	drop(rx);
+	drop(rx2);
+	assert!(tx.send(String::from("Hello, world!")).is_err());
+
+	// Borrowing receives get one clone of the value per receiving side, not one per call
+	let (tx, rx) = channel::<String>();
+	let rx2 = rx.clone();
+	let rx3 = rx.clone();
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv_keep().as_deref(), Some("Hello, world!"));
+	assert_eq!(rx.recv_keep(), None);
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+	assert!(rx.recv_ref().is_none());
+	assert_eq!(rx2.try_recv().as_deref(), Ok("Hello, world!"));
+	assert_eq!(rx2.try_recv(), Err(TryRecvError::Disconnected));
+	assert_eq!(rx3.recv_keep().as_deref(), Some("Hello, world!"));
+	drop((rx, rx2, rx3));
+
+	// A receiving side that drops after taking its clone doesn't stop the others from getting theirs
+	let (tx, rx) = channel::<String>();
+	let rx2 = rx.clone();
+	let rx3 = rx.clone();
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv_keep().as_deref(), Some("Hello, world!"));
+	drop(rx);
+	assert_eq!(rx2.recv_keep().as_deref(), Some("Hello, world!"));
+	assert_eq!(rx3.recv().as_deref(), Some("Hello, world!"));
+	assert_eq!(rx2.recv(), None);
+
+	// Each value streamed to cloned receiving sides reaches every one of them once
+	let (tx, rx) = channel::<u32>();
+	let rx2 = rx.clone();
+	tx.send_streaming(1).unwrap();
+	assert_eq!(rx.try_recv(), Ok(1));
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+	assert_eq!(rx2.try_recv(), Ok(1));
+	tx.send_streaming(2).unwrap();
+	assert_eq!(rx2.try_recv(), Ok(2));
+	assert_eq!(rx2.try_recv(), Err(TryRecvError::Empty));
+	assert_eq!(rx.try_recv(), Ok(2));
+	drop(tx);
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+	assert_eq!(rx2.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_once_beam() {
+	static BEAM: OnceBeam<String> = OnceBeam::new();
+
+	assert!(!BEAM.is_set());
+	let t = std::thread::spawn(|| BEAM.wait());
+	BEAM.set(String::from("Hello, world!")).unwrap();
+	assert_eq!(t.join().unwrap().as_deref(), Some("Hello, world!"));
+
+	// Only the first set and the first wait get anywhere
+	assert!(BEAM.is_set());
+	assert_eq!(BEAM.set(String::from("Goodbye, world!")).unwrap_err().0, "Goodbye, world!");
+	assert_eq!(BEAM.wait(), None);
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_has_waiter() {
+	let (tx, rx) = channel::<String>();
+	assert!(!tx.has_waiter());
+
+	let t = std::thread::spawn(move || rx.recv());
+	while !tx.has_waiter() {
+		std::thread::yield_now();
+	}
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(t.join().unwrap().as_deref(), Some("Hello, world!"));
+
+	// Waiting doesn't get in the way of hang-up detection
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || rx.recv());
+	while !tx.has_waiter() {
+		std::thread::yield_now();
+	}
+	assert!(!tx.is_disconnected());
+	drop(tx);
+	assert_eq!(t.join().unwrap(), None);
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_recv_keep() {
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || tx.send(String::from("Hello, world!")).unwrap());
+	assert_eq!(rx.recv_keep().as_deref(), Some("Hello, world!"));
+	assert_eq!(rx.recv_keep(), None);
+	assert!(rx.is_disconnected());
+	t.join().unwrap();
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_panic_safety() {
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || {
+		let _tx = tx;
+		panic!("worker panicked before sending");
+	});
+	assert_eq!(rx.recv(), None);
+	assert!(t.join().is_err());
+
+	// Panicking while a `BeamGuard` holds the lock poisons it, but both sides can still hang up
+	let (tx, rx) = channel::<String>();
+	tx.send_streaming(String::from("Hello, world!")).unwrap();
+	let t = std::thread::spawn(move || {
+		let _guard = rx.recv_ref();
+		panic!("receiver panicked while borrowing the value");
+	});
+	assert!(t.join().is_err());
+	assert!(tx.is_disconnected());
+	assert!(tx.send_streaming(String::from("Goodbye, world!")).is_err());
+	assert_eq!(format!("{tx:?}"), "ThreadBeamTx { has_data: false, tx_alive: true, rx_alive: false }");
+}
+
+#[test]
+fn test_overwrite_drops() {
+	use core::sync::atomic::{AtomicUsize, Ordering};
+
+	static DROPS: AtomicUsize = AtomicUsize::new(0);
+	struct Counted(u32);
+	impl Drop for Counted {
+		fn drop(&mut self) {
+			DROPS.fetch_add(1, Ordering::SeqCst);
+		}
+	}
+
+	let mut state = ThreadBeamState::new();
+	state.set_data(Counted(1));
+	state.set_data(Counted(2));
+	assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+	assert_eq!(state.take_data().0, 2);
+	assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+
+	let _ = state.drop_tx();
+	let _ = state.drop_rx(&Seen::new());
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_peek_with() {
+	use core::cell::Cell;
+
+	fn assert_send_sync<T: Send + Sync>() {}
+	assert_send_sync::<ThreadBeamTx<Cell<u32>>>();
+	assert_send_sync::<ThreadBeamRx<Cell<u32>>>();
+
+	// `Cell` isn't `Sync`, but can still be read in place
+	let (tx, rx) = channel::<Cell<u32>>();
+	assert_eq!(rx.peek_with(Cell::get), None);
+	tx.send(Cell::new(42)).unwrap();
+	assert_eq!(rx.peek_with(Cell::get), Some(42));
+	assert_eq!(rx.recv().map(Cell::into_inner), Some(42));
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_spawn_or_join() {
+	match spawn_or_join(|tx| {
+		tx.send(String::from("Hello, world!")).unwrap();
+		42
+	}) {
+		SpawnOutcome::Beamed(hello, thread) => {
+			assert_eq!(hello, "Hello, world!");
+			assert_eq!(thread.join().ok(), Some(42));
+		}
+		outcome => panic!("expected a beamed value, got {outcome:?}"),
+	}
+
+	assert!(matches!(spawn_or_join(|_: ThreadBeamTx<String>| 42), SpawnOutcome::Returned(42)));
+
+	assert!(matches!(
+		spawn_or_join(|_: ThreadBeamTx<String>| -> u32 { panic!("worker panicked before sending") }),
+		SpawnOutcome::Panicked(_)
+	));
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_spawn_joined() {
+	let hello = spawn_joined(|tx| {
+		tx.send(String::from("Hello, world!")).unwrap();
+		42
+	});
+	assert_eq!(hello.ok().as_deref(), Some("Hello, world!"));
+
+	assert!(matches!(spawn_joined(|_: ThreadBeamTx<String>| 42), Err(SpawnError::NoValue(42))));
+
+	match spawn_joined(|_: ThreadBeamTx<String>| -> u32 { panic!("worker panicked before sending") }) {
+		Err(SpawnError::Panicked(panic)) => assert_eq!(panic.downcast_ref::<&str>(), Some(&"worker panicked before sending")),
+		result => panic!("expected a panic, got {result:?}"),
+	}
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_recv_status() {
+	use std::time::Duration;
+
+	let (tx, rx) = channel::<String>();
+	assert_eq!(rx.recv_status(Duration::from_millis(10)), RecvStatus::TimedOut);
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(
+		rx.recv_status(Duration::from_millis(10)),
+		RecvStatus::Received(String::from("Hello, world!"))
+	);
+	assert_eq!(rx.recv_status(Duration::from_millis(10)), RecvStatus::SenderDropped);
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_disconnected() {
+	let rx = ThreadBeamRx::<String>::disconnected();
+	assert!(rx.is_disconnected());
+	assert_eq!(rx.recv(), None);
+
+	drop(ThreadBeamRx::<String>::disconnected());
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_ready() {
+	let rx = ThreadBeamRx::ready(String::from("Hello, world!"));
+	assert!(rx.is_disconnected());
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+
+	let rx = ThreadBeamRx::ready(42);
+	assert_eq!(rx.try_recv(), Ok(42));
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+
+	drop(ThreadBeamRx::ready(String::from("Hello, world!")));
+}
+
+#[cfg(all(feature = "metrics", not(feature = "no_std")))]
+#[test]
+fn test_recv_timed() {
+	use std::time::Duration;
+
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	let (hello, blocked) = rx.recv_timed();
+	assert_eq!(hello.as_deref(), Some("Hello, world!"));
+	assert_eq!(blocked, Duration::ZERO);
+
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || rx.recv_timed());
+	while !tx.has_waiter() {
+		std::thread::yield_now();
+	}
+	std::thread::sleep(Duration::from_millis(50));
+	drop(tx);
+	let (hello, blocked) = t.join().unwrap();
+	assert_eq!(hello, None);
+	assert!(blocked >= Duration::from_millis(50));
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_local() {
+	use alloc::rc::Rc;
+
+	let (tx, rx) = local::channel::<Rc<u32>>();
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+	tx.send(Rc::new(42)).unwrap();
+	assert_eq!(rx.recv().as_deref(), Some(&42));
+
+	let (tx, rx) = local::channel::<Rc<u32>>();
+	drop(tx);
+	assert!(rx.is_disconnected());
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+
+	let (tx, rx) = local::channel::<Rc<u32>>();
+	drop(rx);
+	assert!(tx.is_disconnected());
+	assert_eq!(*tx.send(Rc::new(42)).unwrap_err().0, 42);
+
+	// The value is dropped along with the thread beam if it's never received
+	let value = Rc::new(42);
+	let (tx, rx) = local::channel::<Rc<u32>>();
+	tx.send(value.clone()).unwrap();
+	drop(rx);
+	assert_eq!(Rc::strong_count(&value), 1);
+}
+
+#[cfg(all(feature = "paranoid", not(feature = "no_std")))]
+#[test]
+fn test_paranoid() {
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || rx.recv());
+	while !tx.has_waiter() {
+		std::thread::yield_now();
+	}
+	std::thread::sleep(std::time::Duration::from_millis(200));
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(t.join().unwrap().as_deref(), Some("Hello, world!"));
+
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || rx.recv());
+	while !tx.has_waiter() {
+		std::thread::yield_now();
+	}
+	std::thread::sleep(std::time::Duration::from_millis(200));
+	drop(tx);
+	assert_eq!(t.join().unwrap(), None);
+
+	// A change that nobody was notified of
+	let mut state = ThreadBeamState::new();
+	state.set_data(42);
+	let lost = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| state.check_notified()));
+	assert!(lost.is_err());
+	state.notified();
+	state.check_notified();
+	assert_eq!(state.take_data(), 42);
+	let _ = state.drop_tx();
+	let _ = state.drop_rx(&Seen::new());
+}
+
+#[cfg(all(feature = "debug-stats", feature = "no_std", feature = "alloc"))]
+#[test]
+fn test_recv_counted() {
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	let (hello, spins) = rx.recv_counted();
+	assert_eq!(hello.as_deref(), Some("Hello, world!"));
+	assert_eq!(spins, 0);
+
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || {
+		while !tx.has_waiter() {
+			std::thread::yield_now();
+		}
+		drop(tx);
+	});
+	let (hello, spins) = rx.recv_counted();
+	assert_eq!(hello, None);
+	assert!(spins > 0);
+	t.join().unwrap();
+}
+
+#[cfg(all(feature = "debug-stats", not(feature = "no_std")))]
+#[test]
+fn test_recv_with_stats() {
+	let (tx, rx) = channel::<String>();
+	tx.send(String::from("Hello, world!")).unwrap();
+	let (hello, stats) = rx.recv_with_stats();
+	assert_eq!(hello.as_deref(), Some("Hello, world!"));
+	assert_eq!(stats.spurious_wakeups, 0);
+
+	// Downgrading and upgrading a sender notifies the receiver without sending anything
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || rx.recv_with_stats());
+	while !tx.has_waiter() {
+		std::thread::yield_now();
+	}
+	let weak = tx.downgrade();
+	drop(weak.upgrade());
+	std::thread::sleep(std::time::Duration::from_millis(50));
+	tx.send(String::from("Hello, world!")).unwrap();
+	let (hello, stats) = t.join().unwrap();
+	assert_eq!(hello.as_deref(), Some("Hello, world!"));
+	assert!(stats.spurious_wakeups >= 1);
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_spawn_catching() {
+	let (hello, thread) = spawn_catching(|tx| {
+		tx.send(String::from("Hello, world!")).unwrap();
+		42
+	});
+	assert_eq!(hello.ok().flatten().as_deref(), Some("Hello, world!"));
+	assert_eq!(thread.join().ok(), Some(Some(42)));
 
-use r#impl::ThreadBeamInner;
-pub use r#impl::*;
+	let (hello, thread) = spawn_catching(|_: ThreadBeamTx<String>| 42);
+	assert!(matches!(hello, Ok(None)));
+	assert_eq!(thread.join().ok(), Some(Some(42)));
 
-use core::{mem::MaybeUninit, ptr::NonNull};
+	let (hello, thread) = spawn_catching(|_: ThreadBeamTx<String>| -> u32 { panic!("worker panicked before sending") });
+	assert_eq!(hello.unwrap_err().downcast_ref::<&str>(), Some(&"worker panicked before sending"));
+	assert_eq!(thread.join().ok(), Some(None));
+}
 
-/// The sending side of a thread beam.
-pub struct ThreadBeamTx<T: Send>(NonNull<ThreadBeamInner<T>>);
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_recv_or_abort() {
+	let (tx, rx) = channel::<String>();
+	let (abort_tx, abort) = channel();
+	let t = std::thread::spawn(move || abort_tx.send(()).unwrap());
+	assert_eq!(rx.recv_or_abort(abort), RecvOrAbort::Aborted);
+	assert!(tx.is_disconnected());
+	t.join().unwrap();
 
-/// The receiving side of a thread beam.
-pub struct ThreadBeamRx<T: Send>(NonNull<ThreadBeamInner<T>>);
+	let (tx, rx) = channel::<String>();
+	let (abort_tx, abort) = channel();
+	let t = std::thread::spawn(move || {
+		drop(abort_tx);
+		std::thread::sleep(std::time::Duration::from_millis(50));
+		tx.send(String::from("Hello, world!")).unwrap();
+	});
+	assert_eq!(rx.recv_or_abort(abort), RecvOrAbort::Value(String::from("Hello, world!")));
+	t.join().unwrap();
 
-unsafe impl<T: Send> Sync for ThreadBeamTx<T> {}
-unsafe impl<T: Send> Send for ThreadBeamTx<T> {}
+	// The value wins if both are already there
+	let (tx, rx) = channel::<String>();
+	let (abort_tx, abort) = channel();
+	tx.send(String::from("Hello, world!")).unwrap();
+	abort_tx.send(()).unwrap();
+	assert_eq!(rx.recv_or_abort(abort), RecvOrAbort::Value(String::from("Hello, world!")));
 
-unsafe impl<T: Send> Sync for ThreadBeamRx<T> {}
-unsafe impl<T: Send> Send for ThreadBeamRx<T> {}
+	let (tx, rx) = channel::<String>();
+	let (_abort_tx, abort) = channel();
+	drop(tx);
+	assert_eq!(rx.recv_or_abort(abort), RecvOrAbort::Disconnected);
+}
 
-bitflags::bitflags! {
-	struct ThreadBeamFlags: u8 {
-		// Option<T> but packed into a bitflag
-		const HAS_DATA = 0b10000000;
+#[test]
+fn test_flag_layout() {
+	// The std backend's lock-free snapshot stores and reloads these bits, so their meaning must not drift
+	assert_eq!(ThreadBeamFlags::HAS_DATA.bits(), 0b1000_0000);
+	assert_eq!(ThreadBeamFlags::TX.bits(), 0b0100_0000);
+	assert_eq!(ThreadBeamFlags::RX.bits(), 0b0010_0000);
+	assert_eq!(ThreadBeamFlags::WAITING.bits(), 0b0001_0000);
+	assert_eq!(ThreadBeamFlags::CANCELLED.bits(), 0b0000_1000);
+	assert_eq!(ThreadBeamFlags::RESERVED.bits(), 0b0000_0100);
+	assert_eq!(ThreadBeamFlags::RX_CLOSED.bits(), 0b0000_0010);
+	assert_eq!(ThreadBeamFlags::all().bits(), 0b1111_1110);
 
-		// Whether the sending side is closed
-		const TX = 0b01000000;
+	let mut state = ThreadBeamState::new();
+	assert_eq!(state.flags.bits(), 0b0110_0000);
+	state.set_data(());
+	assert_eq!(state.flags.bits(), 0b1110_0000);
+	state.take_data();
+	let _ = state.drop_tx();
+	let _ = state.drop_rx(&Seen::new());
+	assert_eq!(state.flags.bits(), 0);
+	assert!(ThreadBeamState::<()>::unsplit().flags.is_empty());
+}
 
-		// Whether the receiving side is closed
-		const RX = 0b00100000;
+#[cfg(all(any(feature = "alloc", not(feature = "no_std")), not(feature = "safe")))]
+#[test]
+fn test_raw() {
+	let (tx, rx) = channel::<String>();
+	let tx = raw::tx_into_raw(tx);
+	let rx = raw::rx_into_raw(rx);
+
+	let tx = unsafe { raw::tx_from_raw(tx) };
+	tx.send(String::from("Hello, world!")).unwrap();
+	let rx = unsafe { raw::rx_from_raw(rx) };
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+
+	// Driven entirely through pointers, waiting by yielding instead of blocking
+	let (tx, rx) = raw::create::<String>();
+	let tx = tx.as_ptr() as usize;
+	let t = std::thread::spawn(move || {
+		let tx = core::ptr::NonNull::new(tx as *mut raw::RawThreadBeam<String>).unwrap();
+		for hello in ["Hello", "world"] {
+			let mut value = String::from(hello);
+			while let Err(SendError(unsent)) = unsafe { raw::send(tx, value) } {
+				value = unsent;
+				std::thread::yield_now();
+			}
+		}
+		unsafe { raw::release_tx(tx) };
+	});
+	let mut received = Vec::new();
+	loop {
+		match unsafe { raw::try_recv(rx) } {
+			Ok(value) => received.push(value),
+			Err(TryRecvError::Empty) => std::thread::yield_now(),
+			Err(TryRecvError::Disconnected) => break,
+		}
 	}
+	t.join().unwrap();
+	assert_eq!(received, ["Hello", "world"]);
+	unsafe { raw::release_rx(rx) };
+
+	// A value left in the thread beam is dropped along with whichever side goes last
+	let (tx, rx) = raw::create::<String>();
+	unsafe { raw::send(tx, String::from("Hello, world!")) }.unwrap();
+	assert!(unsafe { raw::send(tx, String::from("Goodbye, world!")) }.is_err());
+	unsafe { raw::release_rx(rx) };
+	assert!(unsafe { raw::send(tx, String::from("Goodbye, world!")) }.is_err());
+	unsafe { raw::release_tx(tx) };
 }
-struct ThreadBeamState<T> {
-	data: MaybeUninit<T>,
-	flags: ThreadBeamFlags,
+
+#[cfg(all(not(feature = "no_std"), not(feature = "safe")))]
+#[test]
+fn test_tx_into_raw() {
+	let (tx, rx) = channel::<String>();
+
+	// Stashed as an address, as native code would hold on to it
+	let tx = tx.into_raw() as usize;
+	let t = std::thread::spawn(move || {
+		let tx = unsafe { ThreadBeamTx::<String>::from_raw(tx as *mut ()) };
+		tx.send(String::from("Hello, world!")).unwrap();
+	});
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+	t.join().unwrap();
+
+	// Turning it back and dropping it still hangs up and frees the thread beam
+	let (tx, rx) = channel::<String>();
+	drop(unsafe { ThreadBeamTx::<String>::from_raw(tx.into_raw()) });
+	assert_eq!(rx.recv(), None);
 }
-impl<T> ThreadBeamState<T> {
-	#[inline(always)]
-	pub fn set_data(&mut self, value: T) {
-		debug_assert!(!self.has_data());
-		self.flags |= ThreadBeamFlags::HAS_DATA;
-		self.data = MaybeUninit::new(value);
-	}
 
-	#[inline(always)]
-	pub fn has_data(&self) -> bool {
-		self.flags & ThreadBeamFlags::HAS_DATA != ThreadBeamFlags::empty()
-	}
+#[cfg(all(not(feature = "no_std"), not(feature = "safe")))]
+#[test]
+fn test_raw_poll_recv() {
+	use std::{
+		sync::Arc,
+		task::{Poll, Wake, Waker},
+	};
 
-	#[inline(always)]
-	pub fn hung_up(&self) -> bool {
-		self.flags & (ThreadBeamFlags::TX | ThreadBeamFlags::RX) != (ThreadBeamFlags::TX | ThreadBeamFlags::RX)
+	// A receiver that parks the thread instead of waiting on the condvar
+	struct Unpark(std::thread::Thread);
+	impl Wake for Unpark {
+		fn wake(self: Arc<Self>) {
+			self.0.unpark();
+		}
 	}
-
-	#[must_use]
-	#[inline(always)]
-	pub fn drop_tx(&mut self) -> bool {
-		self.flags &= !ThreadBeamFlags::TX;
-		self.flags & ThreadBeamFlags::RX == ThreadBeamFlags::empty()
+	fn recv_parking<T: Send>(rx: ThreadBeamRx<T>) -> Option<T> {
+		let waker = Waker::from(Arc::new(Unpark(std::thread::current())));
+		loop {
+			match raw::poll_recv(&rx, &waker) {
+				Poll::Ready(value) => return value,
+				Poll::Pending => std::thread::park(),
+			}
+		}
 	}
 
-	#[must_use]
-	#[inline(always)]
-	pub fn drop_rx(&mut self) -> bool {
-		self.flags &= !ThreadBeamFlags::RX;
-		self.flags & ThreadBeamFlags::TX == ThreadBeamFlags::empty()
-	}
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || {
+		std::thread::sleep(std::time::Duration::from_millis(50));
+		tx.send(String::from("Hello, world!")).unwrap();
+	});
+	assert_eq!(recv_parking(rx).as_deref(), Some("Hello, world!"));
+	t.join().unwrap();
+
+	// The same through a pointer to the receiving side
+	let (tx, rx) = raw::create::<String>();
+	let waker = Waker::from(Arc::new(Unpark(std::thread::current())));
+	let tx = tx.as_ptr() as usize;
+	let t = std::thread::spawn(move || {
+		std::thread::sleep(std::time::Duration::from_millis(50));
+		let tx = core::ptr::NonNull::new(tx as *mut raw::RawThreadBeam<String>).unwrap();
+		unsafe { raw::send(tx, String::from("Hello, world!")) }.unwrap();
+		unsafe { raw::release_tx(tx) };
+	});
+	let hello = loop {
+		match unsafe { raw::poll(rx, &waker) } {
+			Poll::Ready(value) => break value,
+			Poll::Pending => std::thread::park(),
+		}
+	};
+	assert_eq!(hello.as_deref(), Some("Hello, world!"));
+	unsafe { raw::release_rx(rx) };
+	t.join().unwrap();
 }
-impl<T> Drop for ThreadBeamState<T> {
-	#[inline(always)]
-	fn drop(&mut self) {
-		if self.has_data() {
-			self.flags &= !ThreadBeamFlags::HAS_DATA;
-			unsafe { core::ptr::drop_in_place(self.data.as_mut_ptr()) };
-			self.data = MaybeUninit::uninit();
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_wake_once() {
+	// A lone blocked receiver is woken by the send alone
+	#[cfg(feature = "debug-stats")]
+	{
+		let (tx, rx) = channel::<String>();
+		let t = std::thread::spawn(move || rx.recv_with_stats());
+		while !tx.has_waiter() {
+			std::thread::yield_now();
+		}
+		tx.send(String::from("Hello, world!")).unwrap();
+		let (hello, stats) = t.join().unwrap();
+		assert_eq!(hello.as_deref(), Some("Hello, world!"));
+		assert_eq!(stats.spurious_wakeups, 0);
+	}
+
+	#[cfg(feature = "broadcast")]
+	{
+		// Several blocked receivers are all woken
+		let (tx, rx) = channel::<String>();
+		let rx2 = rx.clone();
+		let t = std::thread::spawn(move || rx.recv());
+		let t2 = std::thread::spawn(move || rx2.recv());
+		while !tx.has_waiter() {
+			std::thread::yield_now();
 		}
+		std::thread::sleep(std::time::Duration::from_millis(50));
+		tx.send(String::from("Hello, world!")).unwrap();
+		assert_eq!(t.join().unwrap().as_deref(), Some("Hello, world!"));
+		assert_eq!(t2.join().unwrap().as_deref(), Some("Hello, world!"));
 	}
 }
 
+#[cfg(all(any(feature = "alloc", not(feature = "no_std")), not(feature = "safe")))]
 #[test]
-fn test_thread_beam() {
+fn test_try_channel() {
+	let (tx, rx) = try_channel::<String>().unwrap();
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+}
+
+#[cfg(all(feature = "no_std", feature = "alloc"))]
+#[test]
+fn test_try_recv_from_interrupt() {
+	let (tx, rx) = channel::<u32>();
+	assert_eq!(rx.try_recv_from_interrupt(), Err(TryRecvError::Empty));
+	tx.send(42).unwrap();
+	assert_eq!(rx.try_recv_from_interrupt(), Ok(42));
+	assert_eq!(rx.try_recv_from_interrupt(), Err(TryRecvError::Disconnected));
+
+	// The code the interrupt preempted is holding the lock, so this gives up rather than spinning
+	let (tx, rx) = channel::<u32>();
+	tx.send(42).unwrap();
+	let held = rx.recv_ref().unwrap();
+	assert_eq!(rx.try_recv_from_interrupt(), Err(TryRecvError::Empty));
+	drop(held);
+	assert_eq!(rx.try_recv_from_interrupt(), Err(TryRecvError::Disconnected));
+}
+
+#[cfg(all(feature = "no_std", feature = "alloc"))]
+#[test]
+fn test_recv_spin_timeout() {
+	let (tx, rx) = channel::<String>();
+	assert_eq!(rx.recv_spin_timeout(0), RecvStatus::TimedOut);
+	assert_eq!(rx.recv_spin_timeout(100), RecvStatus::TimedOut);
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.recv_spin_timeout(0), RecvStatus::Received(String::from("Hello, world!")));
+
+	let (tx, rx) = channel::<String>();
+	drop(tx);
+	assert_eq!(rx.recv_spin_timeout(100), RecvStatus::SenderDropped);
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_error_eq() {
+	let (tx, rx) = channel::<String>();
+	drop(rx);
+	assert_eq!(tx.send(String::from("Hello, world!")), Err(SendError(String::from("Hello, world!"))));
+
 	let (tx, rx) = channel::<String>();
+	drop(rx);
+	assert_eq!(
+		tx.try_send(String::from("Hello, world!")),
+		Err(TrySendError::Disconnected(String::from("Hello, world!")))
+	);
+
+	let (tx, rx) = channel::<String>();
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+	drop(tx);
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+	assert_eq!(rx.recv_result(), Err(RecvError));
 
+	// The value-free errors are `Copy`, so they can be compared against more than once
+	let error = TryRecvError::Empty;
+	let copied = error;
+	assert_eq!(error, copied);
+	assert_ne!(RecvTimeoutError::Timeout, RecvTimeoutError::Disconnected);
+	assert_ne!(RecvStatus::Received(String::new()), RecvStatus::TimedOut);
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_send_timeout() {
+	use std::time::Duration;
+
+	let (tx, rx) = channel::<u32>();
+	assert_eq!(tx.send_timeout(0, Duration::from_millis(10)), Ok(()));
+	assert_eq!(tx.send_timeout(1, Duration::from_millis(10)), Err(SendTimeoutError::Timeout(1)));
+
+	// Room is made while the sender waits
 	let t = std::thread::spawn(move || {
-		tx.send(String::from("Hello, world!"));
+		std::thread::sleep(Duration::from_millis(50));
+		assert_eq!(rx.recv_streaming(), Some(0));
+		rx
 	});
+	assert_eq!(tx.send_timeout(1, Duration::from_secs(5)), Ok(()));
+	let rx = t.join().unwrap();
+	assert_eq!(rx.recv_streaming(), Some(1));
 
-	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
-	t.join().unwrap();
+	drop(rx);
+	assert_eq!(tx.send_timeout(2, Duration::from_secs(5)), Err(SendTimeoutError::Disconnected(2)));
 }
 
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
 #[test]
-fn test_dropped_thread_beam() {
+fn test_close() {
 	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || rx.recv());
+	std::thread::sleep(std::time::Duration::from_millis(10));
+	let other = tx.clone();
+	tx.close();
+	assert_eq!(t.join().unwrap(), None);
+	assert!(other.send(String::from("Too late")).is_err());
+	assert!(tx.send(String::from("Too late")).is_err());
+
+	// A value sent before closing is still delivered
+	let (tx, rx) = channel();
+	tx.send_streaming(42).unwrap();
+	tx.close();
+	tx.close();
+	assert!(rx.is_disconnected());
+	assert_eq!(rx.recv_streaming(), Some(42));
+	assert_eq!(rx.recv_streaming(), None);
+	drop(tx);
+
+	let (tx, rx) = channel();
+	tx.send_streaming(42).unwrap();
+	rx.close();
+	assert!(tx.is_disconnected());
+	assert!(tx.send_streaming(43).is_err());
+	assert!(tx.downgrade().upgrade().is_none());
+	assert_eq!(rx.try_recv(), Ok(42));
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+	drop(rx);
 	drop(tx);
-	assert_eq!(rx.recv(), None);
 }
 
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
 #[test]
-fn test_dropped_thread_beam_2() {
+fn test_drain() {
+	use core::sync::atomic::{AtomicUsize, Ordering};
+
+	static DROPS: AtomicUsize = AtomicUsize::new(0);
+	struct Counted;
+	impl Drop for Counted {
+		fn drop(&mut self) {
+			DROPS.fetch_add(1, Ordering::SeqCst);
+		}
+	}
+
+	// Dropping the receiving side leaves the value until the sending side is gone too, but draining drops it there and then
+	let (tx, rx) = channel::<Counted>();
+	let other = tx.clone();
+	tx.send(Counted).unwrap();
+	assert!(rx.drain());
+	assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+	assert!(other.is_disconnected());
+	assert!(other.send(Counted).is_err());
+	assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+
+	let (tx, rx) = channel::<Counted>();
+	assert!(!rx.drain());
+	assert!(tx.send(Counted).is_err());
+	assert_eq!(DROPS.load(Ordering::SeqCst), 3);
+}
+
+#[cfg(all(any(feature = "alloc", not(feature = "no_std")), feature = "broadcast"))]
+#[test]
+fn test_drain_broadcast() {
+	// Draining one clone only lets go of it: the value and the thread beam are still there for the others
 	let (tx, rx) = channel::<String>();
+	let other = rx.clone();
+	tx.send_streaming(String::from("Hello, world!")).unwrap();
+	assert!(!rx.drain());
+	assert!(!tx.is_disconnected());
+	assert_eq!(other.try_recv().as_deref(), Ok("Hello, world!"));
+	tx.send_streaming(String::from("Goodbye, world!")).unwrap();
+	assert_eq!(other.try_recv().as_deref(), Ok("Goodbye, world!"));
 
-	let t = std::thread::spawn(move || {
-		std::thread::sleep(std::time::Duration::from_secs(1));
-		drop(tx);
-	});
+	// A clone that has already had its copy doesn't take the original from under the others
+	let rx = other.clone();
+	let third = rx.clone();
+	tx.send_streaming(String::from("Hello again")).unwrap();
+	assert_eq!(rx.try_recv().as_deref(), Ok("Hello again"));
+	assert!(!rx.drain());
+	assert_eq!(other.try_recv().as_deref(), Ok("Hello again"));
+
+	// The last receiving side left drains as usual
+	assert!(third.is_ready());
+	assert!(third.drain());
+	assert!(!other.drain());
+	assert!(tx.is_disconnected());
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_clone_tx() {
+	let (tx, rx) = channel::<usize>();
+	let threads = (0..4)
+		.map(|i| {
+			let tx = tx.clone();
+			std::thread::spawn(move || tx.send(i).is_ok())
+		})
+		.collect::<Vec<_>>();
+	drop(tx);
 
+	// Exactly one producer gets its value through, and the beam only hangs up once they have all gone
+	let winner = rx.recv().unwrap();
+	let sent = threads.into_iter().map(|t| t.join().unwrap()).collect::<Vec<_>>();
+	assert_eq!(sent.iter().filter(|&&sent| sent).count(), 1);
+	assert!(sent[winner]);
+
+	let (tx, rx) = channel::<usize>();
+	let tx2 = tx.clone();
+	drop(tx);
+	assert!(!rx.is_disconnected());
+	drop(tx2);
 	assert_eq!(rx.recv(), None);
-	t.join().unwrap();
 }
 
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
 #[test]
-fn test_dropped_thread_beam_3() {
+fn test_racing_senders() {
+	const SENDERS: usize = 16;
+
+	for _ in 0..20 {
+		let (tx, rx) = channel::<usize>();
+		let start = std::sync::Arc::new(std::sync::Barrier::new(SENDERS));
+		let threads = (0..SENDERS)
+			.map(|i| {
+				let tx = tx.clone();
+				let start = start.clone();
+				std::thread::spawn(move || {
+					start.wait();
+					tx.try_send(i)
+				})
+			})
+			.collect::<Vec<_>>();
+		drop(tx);
+
+		// Exactly one value is delivered, and every loser gets its own value back
+		let winner = rx.recv().unwrap();
+		for (i, t) in threads.into_iter().enumerate() {
+			match t.join().unwrap() {
+				Ok(()) => assert_eq!(i, winner),
+				Err(TrySendError::Disconnected(value)) => assert!(value == i && i != winner),
+			}
+		}
+	}
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_try_send_handoff() {
+	for _ in 0..50 {
+		let (tx, rx) = channel::<String>();
+		let threads = (0..4)
+			.map(|i| {
+				let tx = tx.clone();
+				std::thread::spawn(move || tx.try_send(format!("value {i}")).is_ok())
+			})
+			.collect::<Vec<_>>();
+
+		// The receiving side polls while the senders race, freeing the slot for the next winner each time it receives
+		let mut received = Vec::new();
+		loop {
+			let finished = threads.iter().all(|t| t.is_finished());
+			match rx.try_recv() {
+				Ok(value) => received.push(value),
+				Err(TryRecvError::Empty) if finished => break,
+				Err(TryRecvError::Empty) => std::thread::yield_now(),
+				Err(TryRecvError::Disconnected) => panic!("disconnected while a sending side was still alive"),
+			}
+		}
+
+		// Every value that was sent is received once and whole, and every other one went back to its sender
+		let mut sent = (0..4)
+			.zip(threads)
+			.filter_map(|(i, t)| t.join().unwrap().then(|| format!("value {i}")))
+			.collect::<Vec<_>>();
+		sent.sort();
+		received.sort();
+		assert!(!sent.is_empty());
+		assert_eq!(received, sent);
+
+		// Nothing the losers claimed is left behind, so the slot can be sent to again
+		tx.clone().try_send(String::from("again")).unwrap();
+		assert_eq!(rx.try_recv().as_deref(), Ok("again"));
+	}
+}
+
+#[cfg(all(feature = "debug-sender", not(feature = "no_std")))]
+#[test]
+fn test_recv_with_sender() {
+	let (tx, rx) = channel::<String>();
+	let t = std::thread::spawn(move || tx.send(String::from("Hello, world!")).unwrap());
+	let sender = t.thread().id();
+	assert_eq!(rx.recv_with_sender(), Some((String::from("Hello, world!"), sender)));
+	t.join().unwrap();
+
 	let (tx, rx) = channel::<String>();
+	drop(tx);
+	assert_eq!(rx.recv_with_sender(), None);
+}
 
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_wait_for_disconnect() {
+	let (tx, rx) = channel::<u32>();
 	let t = std::thread::spawn(move || {
-		drop(tx);
+		for i in 0..3 {
+			tx.send_streaming(i).unwrap();
+		}
 	});
+	rx.wait_for_disconnect();
+	t.join().unwrap();
+
+	let (tx, rx) = channel::<u32>();
+	drop(tx);
+	rx.wait_for_disconnect();
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_shared_rx() {
+	let (tx, rx) = channel::<String>();
+	let rx = SharedRx::from(rx);
+	let takers = (0..4)
+		.map(|_| {
+			let rx = rx.clone();
+			std::thread::spawn(move || rx.recv())
+		})
+		.collect::<Vec<_>>();
+	tx.send(String::from("Hello, world!")).unwrap();
+
+	// Exactly one of the racing clones gets the value
+	let taken = takers.into_iter().filter_map(|t| t.join().unwrap()).collect::<Vec<_>>();
+	assert_eq!(taken, [String::from("Hello, world!")]);
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+
+	let (tx, rx) = channel::<String>();
+	let rx = SharedRx::from(rx);
+	assert_eq!(rx.clone().try_recv(), Err(TryRecvError::Empty));
+	drop(tx);
+	assert!(rx.is_disconnected());
 
+	// A clone that comes second while the sending side is still alive finds the thread beam empty, not disconnected
+	let (tx, rx) = channel::<String>();
+	let rx = SharedRx::from(rx);
+	let other = rx.clone();
+	tx.send_streaming(String::from("Hello, world!")).unwrap();
+	assert_eq!(other.try_recv().as_deref(), Ok("Hello, world!"));
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+	assert!(!rx.is_disconnected());
+	drop(tx);
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
 	assert_eq!(rx.recv(), None);
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_concurrent_drop() {
+	use std::sync::{mpsc, Arc, Barrier};
+
+	const PAIRS: usize = 1000;
+
+	let value = Arc::new(());
+	let barrier = Arc::new(Barrier::new(2));
+	let (pairs_tx, pairs_rx) = mpsc::channel::<ThreadBeamTx<Arc<()>>>();
+	let t = std::thread::spawn({
+		let barrier = barrier.clone();
+		move || {
+			for tx in pairs_rx {
+				barrier.wait();
+				drop(tx);
+			}
+		}
+	});
+
+	// Both sides let go at the same moment, with and without a value in between them
+	for i in 0..PAIRS {
+		let (tx, rx) = channel();
+		if i % 2 == 0 {
+			tx.send_streaming(value.clone()).unwrap();
+		}
+		pairs_tx.send(tx).unwrap();
+		barrier.wait();
+		drop(rx);
+	}
+	drop(pairs_tx);
 	t.join().unwrap();
+
+	assert_eq!(Arc::strong_count(&value), 1);
 }
 
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
 #[test]
-fn test_weird_usage() {
+fn test_recv_into() {
+	let (tx, rx) = channel::<Vec<u8>>();
+	let mut buf = b"Hello, ".to_vec();
+	tx.send_streaming(b"world!".to_vec()).unwrap();
+	assert!(rx.recv_into(&mut buf));
+	assert_eq!(buf, b"Hello, world!");
+
+	drop(tx);
+	assert!(!rx.recv_into(&mut buf));
+	assert_eq!(buf, b"Hello, world!");
+}
+
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
+#[test]
+fn test_is_ready() {
 	let (tx, rx) = channel::<String>();
-	tx.send(String::from("Hello, world!"));
-	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+	assert!(!rx.is_ready());
+	assert!(tx.receiver_alive());
+	tx.send_streaming(String::from("Hello, world!")).unwrap();
+	assert!(rx.is_ready());
+	assert_eq!(rx.try_recv().as_deref(), Ok("Hello, world!"));
+	assert!(!rx.is_ready());
+
+	drop(rx);
+	assert!(!tx.receiver_alive());
 }
 
+#[cfg(not(feature = "no_std"))]
 #[test]
-fn test_weird_usage_2() {
+fn test_spawn_diagnostic() {
+	let (hello, thread) = spawn_diagnostic(|tx| tx.send(String::from("Hello, world!")).unwrap());
+	assert_eq!(hello.as_deref(), Ok("Hello, world!"));
+	thread.join().unwrap();
+
+	let builder = std::thread::Builder::new().name(String::from("worker"));
+	let (hello, thread) = spawn_diagnostic_builder(builder, |tx: ThreadBeamTx<String>| drop(tx)).unwrap();
+	let disconnected = hello.unwrap_err();
+	assert_eq!(disconnected.name(), Some("worker"));
+	assert_eq!(disconnected.id(), thread.thread().id());
+	assert!(disconnected.to_string().contains("\"worker\""));
+	thread.join().unwrap();
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_completion_token() {
 	let (tx, rx) = channel::<String>();
 	let t = std::thread::spawn(move || {
-		assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
+		let token = tx.send_and_keep_alive(String::from("Hello, world!")).unwrap();
+		std::thread::sleep(std::time::Duration::from_millis(50));
+		token.finish();
 	});
-	tx.send(String::from("Hello, world!"));
+	assert_eq!(rx.recv_keep().as_deref(), Some("Hello, world!"));
+	assert!(!rx.is_disconnected());
+	rx.wait_for_completion();
+	assert!(rx.is_disconnected());
 	t.join().unwrap();
+
+	// The value is still there after waiting for completion
+	let (tx, rx) = channel::<String>();
+	let token = tx.send_and_keep_alive(String::from("Hello, world!")).unwrap();
+	assert!(!token.is_consumed());
+	drop(token);
+	rx.wait_for_completion();
+	assert_eq!(rx.recv().as_deref(), Some("Hello, world!"));
 }
 
+#[cfg(not(feature = "no_std"))]
 #[test]
-fn test_never_recv() {
-	let (tx, rx) = channel::<String>();
-	tx.send(String::from("Hello, world!"));
-	drop(rx);
+fn test_recv_with() {
+	use std::{
+		sync::{
+			atomic::{AtomicUsize, Ordering},
+			Condvar, Mutex,
+		},
+		time::Duration,
+	};
+
+	#[derive(Default)]
+	struct Counted {
+		notified: Mutex<bool>,
+		cvar: Condvar,
+		waits: AtomicUsize,
+	}
+	impl WaitStrategy for std::sync::Arc<Counted> {
+		fn wait(&self) {
+			self.waits.fetch_add(1, Ordering::Relaxed);
+			let mut notified = self.cvar.wait_while(self.notified.lock().unwrap(), |notified| !*notified).unwrap();
+			*notified = false;
+		}
+
+		fn wait_timeout(&self, timeout: Duration) {
+			self.waits.fetch_add(1, Ordering::Relaxed);
+			let (mut notified, _) = self
+				.cvar
+				.wait_timeout_while(self.notified.lock().unwrap(), timeout, |notified| !*notified)
+				.unwrap();
+			*notified = false;
+		}
+
+		fn notify(&self) {
+			*self.notified.lock().unwrap() = true;
+			self.cvar.notify_one();
+		}
+	}
+
+	let strategy = std::sync::Arc::new(Counted::default());
+	let (tx, rx) = channel();
+	let thread = std::thread::spawn(move || {
+		std::thread::sleep(Duration::from_millis(10));
+		tx.send(String::from("Hello, world!")).unwrap();
+	});
+	assert_eq!(rx.recv_with(strategy.clone()).as_deref(), Some("Hello, world!"));
+	thread.join().unwrap();
+
+	// Nothing waiting, so the strategy isn't used
+	let (tx, rx) = channel();
+	tx.send(42).unwrap();
+	let waits = strategy.waits.load(Ordering::Relaxed);
+	assert_eq!(rx.recv_with(strategy.clone()), Some(42));
+	assert_eq!(strategy.waits.load(Ordering::Relaxed), waits);
+
+	let (tx, rx) = channel::<u32>();
+	drop(tx);
+	assert_eq!(rx.recv_with(strategy.clone()), None);
+
+	let (_tx, rx) = channel::<u32>();
+	assert_eq!(
+		rx.recv_timeout_with(strategy.clone(), Duration::from_millis(10)),
+		Err(RecvTimeoutError::Timeout)
+	);
+	assert!(strategy.waits.load(Ordering::Relaxed) > waits);
 }
 
+#[cfg(any(feature = "alloc", not(feature = "no_std")))]
 #[test]
-fn test_drop_rx_then_send() {
-	let (tx, rx) = channel::<String>();
+fn test_oneshot() {
+	let (tx, mut rx) = oneshot::<String>();
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+	assert!(!tx.is_closed());
+	tx.send(String::from("Hello, world!")).unwrap();
+	assert_eq!(rx.try_recv().as_deref(), Ok("Hello, world!"));
+	assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+
+	let (tx, rx) = oneshot::<String>();
 	drop(rx);
-	tx.send(String::from("Hello, world!"));
+	assert_eq!(tx.send(String::from("Hello, world!")), Err(String::from("Hello, world!")));
+
+	let (tx, mut rx) = oneshot::<String>();
+	rx.close();
+	assert!(tx.is_closed());
+	assert!(tx.send(String::from("Hello, world!")).is_err());
+
+	let (tx, rx) = oneshot::<String>();
+	drop(tx);
+	assert_eq!(rx.blocking_recv(), Err(RecvError));
+
+	#[cfg(feature = "async")]
+	{
+		use core::{future::Future, pin::Pin, task::Context};
+
+		struct Noop;
+		impl std::task::Wake for Noop {
+			fn wake(self: std::sync::Arc<Self>) {}
+		}
+		let waker = std::task::Waker::from(std::sync::Arc::new(Noop));
+		let mut cx = Context::from_waker(&waker);
+
+		let (tx, mut rx) = oneshot::channel::<u32>();
+		assert!(Pin::new(&mut rx).poll(&mut cx).is_pending());
+		tx.send(42).unwrap();
+		assert_eq!(Pin::new(&mut rx).poll(&mut cx), core::task::Poll::Ready(Ok(42)));
+	}
 }
@@ -0,0 +1,113 @@
+//! Thread beams whose sides both stay on the thread that created them, for values that aren't [`Send`].
+//!
+//! These keep the one-shot handshake of a thread beam, e.g. for passing a value out of a nested closure, but swap the lock for a
+//! [`RefCell`], so nothing ever blocks: with both sides on the same thread, a value that hasn't been sent by the time the receiving side
+//! looks never will be.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::rc::Rc;
+//!
+//! let (tx, rx) = threadbeam::local::channel();
+//!
+//! let produce = move || tx.send(Rc::new(42)).unwrap();
+//! produce();
+//!
+//! assert_eq!(rx.recv().as_deref(), Some(&42));
+//! ```
+//!
+//! Neither side is [`Send`], so moving one to another thread doesn't compile:
+//!
+//! ```rust,compile_fail
+//! let (tx, rx) = threadbeam::local::channel::<u32>();
+//! std::thread::spawn(move || tx.send(42).unwrap());
+//! ```
+
+use super::{fmt_state, Seen, SendError, ThreadBeamState, TryRecvError};
+use alloc::rc::Rc;
+use core::{cell::RefCell, task::Poll};
+
+/// Creates a new local thread beam pair.
+#[inline]
+#[must_use = "a thread beam does nothing unless both of its sides are used"]
+pub fn channel<T>() -> (LocalBeamTx<T>, LocalBeamRx<T>) {
+	let state = Rc::new(RefCell::new(ThreadBeamState::new()));
+	(LocalBeamTx(state.clone()), LocalBeamRx(state))
+}
+
+/// The sending side of a local thread beam.
+pub struct LocalBeamTx<T>(Rc<RefCell<ThreadBeamState<T>>>);
+impl<T> LocalBeamTx<T> {
+	/// Send a value to the receiving side of the thread beam.
+	///
+	/// Returns the value back in a [`SendError`] if the receiving side of the thread beam has been dropped.
+	pub fn send(self, value: T) -> Result<(), SendError<T>> {
+		let mut state = self.0.borrow_mut();
+		if !state.can_send() {
+			return Err(SendError(value));
+		}
+		state.set_data(value);
+		Ok(())
+	}
+
+	/// Returns whether the receiving side of the thread beam has been dropped.
+	#[inline]
+	pub fn is_disconnected(&self) -> bool {
+		!self.0.borrow().rx_alive()
+	}
+}
+impl<T> Drop for LocalBeamTx<T> {
+	fn drop(&mut self) {
+		// The `Rc` frees the state once both sides are gone
+		let _ = self.0.borrow_mut().drop_tx();
+	}
+}
+impl<T> core::fmt::Debug for LocalBeamTx<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		fmt_state("LocalBeamTx", self.0.try_borrow().ok().as_deref(), f)
+	}
+}
+
+/// The receiving side of a local thread beam.
+#[must_use = "dropping the receiving side of a thread beam makes every send to it fail"]
+pub struct LocalBeamRx<T>(Rc<RefCell<ThreadBeamState<T>>>);
+impl<T> LocalBeamRx<T> {
+	/// Receive the value sent by the sending side of the thread beam.
+	///
+	/// Returns `None` if nothing has been sent. This never blocks, since the sending side is on this thread too, so waiting for it would
+	/// never end.
+	#[inline]
+	#[must_use = "the beamed value is discarded if not used"]
+	pub fn recv(self) -> Option<T> {
+		self.try_recv().ok()
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, if it's been sent.
+	///
+	/// Returns [`TryRecvError::Empty`] if nothing has been sent yet, or [`TryRecvError::Disconnected`] if the sending side of the thread beam has been dropped.
+	pub fn try_recv(&self) -> Result<T, TryRecvError> {
+		// Local receiving sides can't be cloned, so they never need to remember which value they have seen
+		match self.0.borrow_mut().poll_data(&Seen::new()) {
+			Poll::Ready(Some(value)) => Ok(value),
+			Poll::Ready(None) => Err(TryRecvError::Disconnected),
+			Poll::Pending => Err(TryRecvError::Empty),
+		}
+	}
+
+	/// Returns whether the sending side of the thread beam has been dropped.
+	#[inline]
+	pub fn is_disconnected(&self) -> bool {
+		!self.0.borrow().tx_alive()
+	}
+}
+impl<T> Drop for LocalBeamRx<T> {
+	fn drop(&mut self) {
+		let _ = self.0.borrow_mut().drop_rx(&Seen::new());
+	}
+}
+impl<T> core::fmt::Debug for LocalBeamRx<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		fmt_state("LocalBeamRx", self.0.try_borrow().ok().as_deref(), f)
+	}
+}
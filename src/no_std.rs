@@ -1,87 +1,889 @@
-use super::{ThreadBeamFlags, ThreadBeamRx, ThreadBeamState, ThreadBeamTx};
-use alloc::boxed::Box;
-use core::{mem::MaybeUninit, ptr::NonNull};
+use super::{RecvTimeoutError, Seen, SendError, ThreadBeamState};
+#[cfg(not(feature = "safe"))]
+use core::sync::atomic::AtomicPtr;
+use core::sync::atomic::{AtomicU32, Ordering};
+use core::task::Poll;
 use spin::Mutex;
+#[cfg(all(not(feature = "safe"), feature = "alloc"))]
+use {super::AllocError, alloc::boxed::Box};
+#[cfg(feature = "alloc")]
+use {
+	super::{share, split, Allocation, RecvStatus, ThreadBeamRx, ThreadBeamTx, TryRecvError, WeakThreadBeamTx},
+	alloc::vec::Vec,
+	core::marker::PhantomData,
+	spin::MutexGuard,
+};
 
+#[cfg(not(feature = "safe"))]
+static YIELD_HOOK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+// Function pointers can't be stored atomically without casting them to and from data pointers
+#[cfg(feature = "safe")]
+static YIELD_HOOK: Mutex<Option<fn()>> = Mutex::new(None);
+
+/// Sets a hook that is called by waiting receivers once they have backed off as far as they will by spinning.
+///
+/// Use this to yield to other tasks on a cooperative scheduler instead of burning the CPU while waiting for a slow sender.
+///
+/// Without a hook, a waiting receiver on a single core may keep the sender it's waiting for from ever running. To catch that, debug
+/// builds panic with "possible single-core spin starvation" once a receiver has spun without a hook for longer than
+/// [`set_spin_patience`] allows.
+pub fn set_yield_hook(hook: fn()) {
+	#[cfg(not(feature = "safe"))]
+	YIELD_HOOK.store(hook as *mut (), Ordering::Release);
+	#[cfg(feature = "safe")]
+	{
+		*YIELD_HOOK.lock() = Some(hook);
+	}
+}
+
+static SPIN_PATIENCE: AtomicU32 = AtomicU32::new(Backoff::DEFAULT_PATIENCE);
+
+/// Sets how many times a waiting receiver may back off as far as it will without a yield hook before debug builds take it for
+/// single-core spin starvation and panic. `0` turns the check off, and release builds never check.
+///
+/// Each of these spins 64 times, so the default of `u32::MAX` is about 2.7 × 10¹¹ spin hints. How long that takes depends on the CPU:
+/// about a minute and a half where a hint takes a single cycle at 3 GHz, as `yield` can on AArch64, and hours where it takes over a
+/// hundred, like `pause` on recent x86. Raise it, or turn the check off, if a receiver may legitimately wait on another core for longer.
+/// Receivers read it when they start waiting.
+pub fn set_spin_patience(patience: u32) {
+	SPIN_PATIENCE.store(patience, Ordering::Relaxed);
+}
+
+#[cfg(not(feature = "safe"))]
+#[inline]
+fn yield_hook() -> Option<fn()> {
+	let hook = YIELD_HOOK.load(Ordering::Acquire);
+	(!hook.is_null()).then(|| unsafe { core::mem::transmute::<*mut (), fn()>(hook) })
+}
+#[cfg(feature = "safe")]
+#[inline]
+fn yield_hook() -> Option<fn()> {
+	*YIELD_HOOK.lock()
+}
+
+// Frees the shared state once the side in `$shared` is the last one to drop; with the `safe` feature, dropping the side does that
+#[cfg(all(not(feature = "safe"), feature = "alloc"))]
+macro_rules! deallocate {
+	($shared:expr) => {
+		drop(unsafe { Box::from_raw($shared.as_ptr()) })
+	};
+}
+#[cfg(all(feature = "safe", feature = "alloc"))]
+macro_rules! deallocate {
+	($shared:expr) => {{}};
+}
+
+pub(super) struct Backoff {
+	step: u32,
+
+	// How many more times a debug build may spin as far as it will without a yield hook before suspecting that whoever it's waiting for
+	// shares its core and will never get to run, or 0 if it may spin forever
+	#[cfg(debug_assertions)]
+	patience: u32,
+}
+impl Backoff {
+	// Spins at most 2^SPIN_LIMIT times between checks
+	pub(super) const SPIN_LIMIT: u32 = 6;
+
+	// This counts spins rather than time, since there may be no clock, so it's set high enough for the fastest spin hints to take over a
+	// minute; see `set_spin_patience`
+	const DEFAULT_PATIENCE: u32 = u32::MAX;
+
+	#[inline]
+	fn new() -> Self {
+		Self {
+			step: 0,
+			#[cfg(debug_assertions)]
+			patience: SPIN_PATIENCE.load(Ordering::Relaxed),
+		}
+	}
+
+	#[cfg(all(test, debug_assertions))]
+	pub(super) fn with_patience(patience: u32) -> Self {
+		Self { step: 0, patience }
+	}
+
+	#[inline]
+	fn snooze(&mut self) {
+		self.snooze_with(yield_hook())
+	}
+
+	pub(super) fn snooze_with(&mut self, hook: Option<fn()>) {
+		if self.step < Self::SPIN_LIMIT {
+			self.step += 1;
+		} else if let Some(hook) = hook {
+			hook();
+			return;
+		} else {
+			// On a single core, a receiver that never yields can keep a cooperatively scheduled sender from ever running
+			#[cfg(debug_assertions)]
+			if self.patience != 0 {
+				self.patience -= 1;
+				assert!(
+					self.patience != 0,
+					"possible single-core spin starvation: did you forget to set a yield hook?"
+				);
+			}
+		}
+
+		for _ in 0..1 << self.step {
+			core::hint::spin_loop();
+		}
+	}
+}
+
+// What to wake once the value is sent or the sending side is dropped; taken under the lock, but only fired once it has been released
+struct Notify(Option<core::task::Waker>, Option<fn()>);
+impl Notify {
+	#[inline]
+	fn take<T>(state: &mut ThreadBeamState<T>) -> Self {
+		let on_ready = if state.has_data() || !state.tx_alive() {
+			state.on_ready.take()
+		} else {
+			None
+		};
+		Self(state.take_waker(), on_ready)
+	}
+
+	#[inline]
+	fn fire(self) {
+		if let Some(waker) = self.0 {
+			waker.wake();
+		}
+		if let Some(on_ready) = self.1 {
+			on_ready();
+		}
+	}
+}
+
+#[cfg(feature = "alloc")]
 pub(super) struct ThreadBeamInner<T> {
 	lock: Mutex<ThreadBeamState<T>>,
 }
+#[cfg(feature = "alloc")]
+impl<T> ThreadBeamInner<T> {
+	#[inline]
+	pub(crate) fn new() -> Self {
+		Self {
+			lock: Mutex::new(ThreadBeamState::new()),
+		}
+	}
+}
 
-impl<T: Send> ThreadBeamTx<T> {
+#[cfg(feature = "alloc")]
+impl<T: Send, Tag> ThreadBeamTx<T, Tag> {
 	/// Send a value to the receiving side of the thread beam.
-	pub fn send(self, value: T) {
-		let inner = unsafe { self.0.as_ref() };
+	///
+	/// Returns the value back in a [`SendError`] if the receiving side of the thread beam has been dropped, or if another sending side (a clone, or one upgraded from a [`WeakThreadBeamTx`]) has already sent a value that is still waiting to be received.
+	pub fn send(self, value: T) -> Result<(), SendError<T>> {
+		let inner = self.inner();
+		spin_send(&inner.lock, value)
+	}
 
-		let mut lock = inner.lock.lock();
-		lock.set_data(value);
+	// Sends without dropping the sending side, for the raw interface
+	#[cfg(not(feature = "safe"))]
+	#[inline]
+	pub(crate) fn send_open(&self, value: T) -> Result<(), SendError<T>> {
+		let inner = self.inner();
+		spin_send(&inner.lock, value)
+	}
+
+	/// Returns whether the receiving side of the thread beam is still alive, the opposite of [`is_disconnected`](ThreadBeamTx::is_disconnected).
+	///
+	/// This never blocks for long, but takes the thread beam's lock for a moment, as there is no lock-free snapshot of its flags in `no_std`. The answer is only advisory: the receiving side may be dropped immediately after this returns `true`.
+	#[inline]
+	pub fn receiver_alive(&self) -> bool {
+		let inner = self.inner();
+		inner.lock.lock().rx_alive()
+	}
+
+	/// Returns whether the receiving side of the thread beam has been dropped.
+	///
+	/// This never blocks for long, but the answer is only advisory: the receiving side may be dropped immediately after this returns `false`.
+	pub fn is_disconnected(&self) -> bool {
+		let inner = self.inner();
+		!inner.lock.lock().rx_alive()
+	}
+
+	/// Returns whether the receiving side of the thread beam is currently spinning while waiting for a value, so that sending now would be picked up straight away.
+	///
+	/// Use this for heuristics such as finishing the current batch of work before sending while nobody is waiting yet. This never blocks for long, but the answer is only advisory: the receiving side may start waiting immediately after this returns `false`.
+	pub fn has_waiter(&self) -> bool {
+		let inner = self.inner();
+		inner.lock.lock().has_waiter()
+	}
+
+	/// Send a value to the receiving side of the thread beam while keeping the sending side open, so that more values can be sent after it.
+	///
+	/// The thread beam only holds one value at a time, so this spins until the previously sent value, if any, has been received. The receiving side should receive each value with [`recv_streaming`](ThreadBeamRx::recv_streaming), and sees the thread beam as disconnected once the sending side is dropped.
+	///
+	/// Returns the value back in a [`SendError`] if the receiving side of the thread beam has been dropped, including while waiting for the previous value to be received.
+	pub fn send_streaming(&self, value: T) -> Result<(), SendError<T>> {
+		let inner = self.inner();
+
+		let mut backoff = Backoff::new();
+		loop {
+			let mut lock = inner.lock.lock();
+			if lock.hung_up() {
+				return Err(SendError(value));
+			} else if lock.can_send() {
+				lock.set_data(value);
+
+				let notify = Notify::take(&mut lock);
+				drop(lock);
+				notify.fire();
+
+				return Ok(());
+			}
+			drop(lock);
+			backoff.snooze();
+		}
+	}
+
+	/// Send every value in `values` to the receiving side of the thread beam in order, like [`send_streaming`](ThreadBeamTx::send_streaming).
+	///
+	/// The thread beam still only holds one value at a time, so this spins until each value has been received before sending the next.
+	///
+	/// Returns the values that weren't sent back in a [`SendError`], starting with the one that was waiting to be sent, if the receiving side of the thread beam has been dropped, including while waiting.
+	pub fn send_all(&self, values: impl IntoIterator<Item = T>) -> Result<(), SendError<Vec<T>>> {
+		let mut values = values.into_iter();
+		while let Some(value) = values.next() {
+			if let Err(SendError(value)) = self.send_streaming(value) {
+				return Err(SendError(core::iter::once(value).chain(values).collect()));
+			}
+		}
+		Ok(())
+	}
+
+	/// Returns the id of this thread beam, as given to the hook set by [`set_unconsumed_hook`](crate::set_unconsumed_hook).
+	#[cfg(feature = "debug-unconsumed")]
+	pub fn id(&self) -> usize {
+		let inner = self.inner();
+		inner.lock.lock().id
+	}
+}
+#[cfg(feature = "alloc")]
+impl<T: Send, Tag> core::fmt::Debug for ThreadBeamTx<T, Tag> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		let inner = self.inner();
+		super::fmt_state("ThreadBeamTx", inner.lock.try_lock().as_deref(), f)
+	}
+}
+#[cfg(feature = "alloc")]
+impl<T: Send, Tag> Drop for ThreadBeamTx<T, Tag> {
+	fn drop(&mut self) {
+		let (deallocate, notify) = {
+			let inner = self.inner();
+			let mut lock = inner.lock.lock();
+			(lock.drop_tx(), Notify::take(&mut lock))
+		};
+		notify.fire();
+		if deallocate {
+			deallocate!(self.0);
+		}
+	}
+}
+
+fn spin_send<T>(lock: &Mutex<ThreadBeamState<T>>, value: T) -> Result<(), SendError<T>> {
+	let mut lock = lock.lock();
+	if !lock.can_send() {
+		return Err(SendError(value));
+	}
+	lock.set_data(value);
+
+	let notify = Notify::take(&mut lock);
+	drop(lock);
+	notify.fire();
+
+	Ok(())
+}
+
+fn spin_recv<T>(lock: &Mutex<ThreadBeamState<T>>, seen: &Seen) -> Option<T> {
+	spin_recv_deadline(lock, seen, || false).ok()
+}
+
+fn spin_recv_deadline<T>(lock: &Mutex<ThreadBeamState<T>>, seen: &Seen, mut is_expired: impl FnMut() -> bool) -> Result<T, RecvTimeoutError> {
+	let mut backoff = Backoff::new();
+	loop {
+		{
+			let mut lock = lock.lock();
+			if let Poll::Ready(value) = lock.poll_data(seen) {
+				lock.set_waiting(false);
+				return value.ok_or(RecvTimeoutError::Disconnected);
+			}
+			lock.set_waiting(true);
+		}
+
+		// Checked after the beam, so a value that is already there is received even if the deadline has passed
+		if is_expired() {
+			lock.lock().set_waiting(false);
+			return Err(RecvTimeoutError::Timeout);
+		}
+
+		backoff.snooze();
+	}
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Send, Tag> ThreadBeamTx<T, Tag> {
+	/// Disconnects the sending side of the thread beam without dropping it, as if every sending side had been dropped.
+	///
+	/// A receiving side waiting for a value stops waiting and returns `None`, unless a value has already been sent, which can still be received. Every later send, from this or any clone of the sending side, fails. Dropping the sending side afterwards only frees the thread beam, if it's the last side left.
+	pub fn close(&self) {
+		let notify = {
+			let inner = self.inner();
+			let mut lock = inner.lock.lock();
+			lock.close_tx();
+			Notify::take(&mut lock)
+		};
+		notify.fire();
+	}
+
+	/// Creates a [`WeakThreadBeamTx`] for this sending side of the thread beam.
+	///
+	/// The weak sending side can't send by itself and doesn't keep the receiving side waiting, but it can be upgraded back into a sending side for as long as this one (or another upgraded one) and the receiving side are alive.
+	pub fn downgrade(&self) -> WeakThreadBeamTx<T, Tag> {
+		let inner = self.inner();
+		inner.lock.lock().downgrade_tx();
+		WeakThreadBeamTx(share(&self.0), PhantomData)
+	}
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Send, Tag> WeakThreadBeamTx<T, Tag> {
+	/// Upgrades into a sending side of the thread beam.
+	///
+	/// Returns `None` if the receiving side of the thread beam has been dropped, or if every sending side has already been dropped.
+	pub fn upgrade(&self) -> Option<ThreadBeamTx<T, Tag>> {
+		let inner = self.inner();
+		let upgraded = inner.lock.lock().upgrade_tx();
+		upgraded.then(|| ThreadBeamTx(share(&self.0), PhantomData))
+	}
+}
+#[cfg(feature = "alloc")]
+impl<T: Send, Tag> core::fmt::Debug for WeakThreadBeamTx<T, Tag> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		let inner = self.inner();
+		super::fmt_state("WeakThreadBeamTx", inner.lock.try_lock().as_deref(), f)
 	}
 }
-impl<T: Send> Drop for ThreadBeamTx<T> {
+#[cfg(feature = "alloc")]
+impl<T: Send, Tag> Drop for WeakThreadBeamTx<T, Tag> {
 	fn drop(&mut self) {
 		let deallocate = {
-			let inner = unsafe { self.0.as_ref() };
-			inner.lock.lock().drop_tx()
+			let inner = self.inner();
+			inner.lock.lock().drop_weak_tx()
 		};
 		if deallocate {
-			unsafe { Box::from_raw(self.0.as_ptr()) };
+			deallocate!(self.0);
 		}
 	}
 }
 
-impl<T: Send> ThreadBeamRx<T> {
+#[cfg(feature = "alloc")]
+impl<T: Send, Tag> ThreadBeamRx<T, Tag> {
 	/// Receive the value sent by the sending side of the thread beam.
 	///
 	/// Returns `None` if the sending side of the thread beam has been dropped.
+	///
+	/// This blocks until a value is sent or the sending side is dropped, so calling it on the thread that holds the sending side, before that thread has sent or dropped it, will deadlock.
+	#[must_use = "the beamed value is discarded if not used"]
 	pub fn recv(self) -> Option<T> {
-		let inner = unsafe { self.0.as_ref() };
+		let inner = self.inner();
+		spin_recv(&inner.lock, &self.1)
+	}
+
+	/// Receive the value sent by the sending side of the thread beam like [`recv`](ThreadBeamRx::recv), and report how many times it had to back off before the value arrived or the sending side was dropped.
+	///
+	/// Only available with the `debug-stats` feature, for tuning the backoff or a yield hook against how long senders actually take.
+	#[cfg(feature = "debug-stats")]
+	#[must_use = "the beamed value is discarded if not used"]
+	pub fn recv_counted(self) -> (Option<T>, u64) {
+		let inner = self.inner();
+
+		// The deadline is checked once before every backoff, so counting the checks counts the spins without touching `recv`'s loop
+		let mut spins = 0;
+		let value = spin_recv_deadline(&inner.lock, &self.1, || {
+			spins += 1;
+			false
+		})
+		.ok();
+		(value, spins)
+	}
+
+	/// Receive the next value sent with [`send_streaming`](ThreadBeamTx::send_streaming) by the sending side of the thread beam.
+	///
+	/// Returns `None` once the sending side of the thread beam has been dropped and every value sent before that has been received.
+	///
+	/// Like [`recv`](ThreadBeamRx::recv), this spins until a value is sent or the sending side is dropped.
+	#[inline]
+	pub fn recv_streaming(&self) -> Option<T> {
+		let inner = self.inner();
+		spin_recv(&inner.lock, &self.1)
+	}
+
+	/// Like [`recv_streaming`](ThreadBeamRx::recv_streaming), but fails fast on shutdown: returns `None` as soon as the thread beam has been disconnected, dropping any value that was sent before that but hasn't been received yet.
+	///
+	/// Every other receive drains the thread beam first, only reporting a disconnect once there is nothing left to receive.
+	pub fn recv_fail_fast(&self) -> Option<T> {
+		let inner = self.inner();
+
+		let mut backoff = Backoff::new();
+		loop {
+			{
+				let mut lock = inner.lock.lock();
+				if lock.hung_up() {
+					lock.set_waiting(false);
+					// Dropped once the lock has been released, in case dropping it uses the thread beam
+					let leftover = lock.poll_data(&self.1);
+					drop(lock);
+					drop(leftover);
+					return None;
+				}
+				if let Poll::Ready(value) = lock.poll_data(&self.1) {
+					lock.set_waiting(false);
+					return value;
+				}
+				lock.set_waiting(true);
+			}
+			backoff.snooze();
+		}
+	}
+
+	/// Spins until the sending side of the thread beam has been dropped, dropping any value it sent.
+	///
+	/// This uses the thread beam as a barrier, e.g. to wait for a thread to finish with its sending side. Unlike [`recv`](ThreadBeamRx::recv), a sent value doesn't end the wait, and a sending side streaming values with [`send_streaming`](ThreadBeamTx::send_streaming) isn't left waiting for them to be received.
+	pub fn wait_for_disconnect(self) {
+		let inner = self.inner();
+
+		let mut backoff = Backoff::new();
 		loop {
 			let mut lock = inner.lock.lock();
-			if lock.has_data() {
-				lock.flags &= !ThreadBeamFlags::HAS_DATA;
-				return Some(unsafe { lock.data.assume_init_read() });
-			} else if lock.hung_up() {
-				return None;
-			} else {
-				drop(lock);
-				core::hint::spin_loop();
-				continue;
+			if let Poll::Ready(Some(value)) = lock.poll_data(&self.1) {
+				drop(value);
+			}
+			if !lock.tx_alive() {
+				lock.set_waiting(false);
+				return;
+			}
+			lock.set_waiting(true);
+			drop(lock);
+			backoff.snooze();
+		}
+	}
+
+	/// Waits for the value sent by the sending side of the thread beam, then borrows it in place instead of moving it out.
+	///
+	/// The value is dropped when the returned guard is dropped. Returns `None` if the sending side of the thread beam has been dropped.
+	///
+	/// The guard keeps the thread beam locked, so the sending side spins if it is dropped (or sends again with [`send_streaming`](ThreadBeamTx::send_streaming)) while the guard is alive.
+	pub fn recv_ref(&self) -> Option<BeamGuard<'_, T>> {
+		let inner = self.inner();
+
+		let mut backoff = Backoff::new();
+		loop {
+			let mut lock = inner.lock.lock();
+			if let Poll::Ready(has_data) = lock.poll_ready(&self.1) {
+				lock.set_waiting(false);
+				return has_data.then(|| BeamGuard(lock, &self.1));
 			}
+			lock.set_waiting(true);
+			drop(lock);
+			backoff.snooze();
 		}
 	}
+
+	/// Attempts to receive the value sent by the sending side of the thread beam without spinning.
+	///
+	/// Returns [`TryRecvError::Empty`] if nothing has been sent yet, or [`TryRecvError::Disconnected`] if the sending side of the thread beam has been dropped.
+	///
+	/// This borrows the receiving side of the thread beam rather than consuming it, so it can be polled repeatedly, e.g. from a main loop. A received value is moved out of the thread beam, so it's never received twice: once the value sent with [`send`](ThreadBeamTx::send) has been received, later calls, and a later [`recv`](ThreadBeamRx::recv), see the thread beam as disconnected and return `None`. After a value sent with [`send_streaming`](ThreadBeamTx::send_streaming), they get the next one sent instead.
+	pub fn try_recv(&self) -> Result<T, TryRecvError> {
+		let inner = self.inner();
+
+		let mut lock = inner.lock.lock();
+		match lock.poll_data(&self.1) {
+			Poll::Ready(Some(value)) => Ok(value),
+			Poll::Ready(None) => Err(TryRecvError::Disconnected),
+			Poll::Pending => Err(TryRecvError::Empty),
+		}
+	}
+
+	/// Attempts to receive the value sent by the sending side of the thread beam without ever waiting on the thread beam's lock, for polling from an interrupt handler.
+	///
+	/// This is [`try_recv`](ThreadBeamRx::try_recv), except that it only tries the lock once: if another context holds it, such as the code this interrupt preempted in the middle of a send, it returns [`TryRecvError::Empty`] straight away instead of spinning on a lock that can't be released until the handler returns. It never spins, never calls the yield hook and never frees the thread beam, so the only code it runs besides its own is a receiving side's [`Clone`] of the value, for broadcasts.
+	///
+	/// `Empty` therefore only means "not received this time": poll again later, e.g. on the next interrupt.
+	pub fn try_recv_from_interrupt(&self) -> Result<T, TryRecvError> {
+		let inner = self.inner();
+
+		let Some(mut lock) = inner.lock.try_lock() else {
+			return Err(TryRecvError::Empty);
+		};
+		match lock.poll_data(&self.1) {
+			Poll::Ready(Some(value)) => Ok(value),
+			Poll::Ready(None) => Err(TryRecvError::Disconnected),
+			Poll::Pending => Err(TryRecvError::Empty),
+		}
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, giving up once `is_expired` returns `true`.
+	///
+	/// There is no clock in `no_std`, so `is_expired` is checked between spins and should compare against your own tick counter or timer.
+	///
+	/// Returns [`RecvTimeoutError::Disconnected`] if the sending side of the thread beam has been dropped, or [`RecvTimeoutError::Timeout`] if `is_expired` returned `true` first.
+	///
+	/// This borrows the receiving side of the thread beam rather than consuming it, so it can be retried after a timeout. Once the value has been received, later calls return [`RecvTimeoutError::Disconnected`].
+	pub fn recv_deadline(&self, is_expired: impl FnMut() -> bool) -> Result<T, RecvTimeoutError> {
+		let inner = self.inner();
+		spin_recv_deadline(&inner.lock, &self.1, is_expired)
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, giving up after checking it `max_spins` times.
+	///
+	/// The spin count is not a unit of time: each spin waits a little longer than the previous one while backing off, and calls the hook set by [`set_yield_hook`] once it has backed off as far as it will, so how long `max_spins` lasts depends on the CPU, the scheduler and the hook. Use it as a portable bound against hanging forever, and use [`recv_deadline`](ThreadBeamRx::recv_deadline) with your own timer when the wait needs to be measured.
+	///
+	/// The thread beam is always checked at least once, so a value that has already been sent is received even with a `max_spins` of zero. Returns the same [`RecvStatus`] as `recv_status` does with the standard library, so code matching on it compiles under both.
+	///
+	/// Like `recv_status`, this borrows the receiving side of the thread beam rather than consuming it, so it can be retried after [`RecvStatus::TimedOut`]. Once the value has been received, later calls return [`RecvStatus::SenderDropped`].
+	pub fn recv_spin_timeout(&self, max_spins: u64) -> RecvStatus<T> {
+		let inner = self.inner();
+
+		let mut spins = 0;
+		let result = spin_recv_deadline(&inner.lock, &self.1, || {
+			spins += 1;
+			spins > max_spins
+		});
+		match result {
+			Ok(value) => RecvStatus::Received(value),
+			Err(RecvTimeoutError::Timeout) => RecvStatus::TimedOut,
+			Err(RecvTimeoutError::Disconnected) => RecvStatus::SenderDropped,
+		}
+	}
+
+	/// Sets a callback that is called once the value is sent, or once the sending side of the thread beam is dropped, whichever comes first.
+	///
+	/// Use this to wake a task on your own scheduler or event system instead of spinning in [`recv`](ThreadBeamRx::recv). The callback is called on the thread that sends or drops the sending side, after the thread beam has been unlocked, so it may receive from the thread beam right away.
+	///
+	/// If the value has already been sent, or the sending side has already been dropped, the callback is called immediately. Setting another callback before the first one has been called replaces it.
+	pub fn on_ready(&self, callback: fn()) {
+		let inner = self.inner();
+
+		let mut lock = inner.lock.lock();
+		if lock.poll_ready(&self.1).is_ready() {
+			drop(lock);
+			callback();
+		} else {
+			lock.on_ready = Some(callback);
+		}
+	}
+
+	/// Returns a clone of the value sent by the sending side of the thread beam, without receiving it.
+	///
+	/// Returns `None` if nothing has been sent yet, or if the sending side of the thread beam was dropped without sending anything. This never blocks waiting for a value, and the value is left in place for a later call to [`recv`](ThreadBeamRx::recv).
+	#[inline]
+	pub fn peek(&self) -> Option<T>
+	where
+		T: Clone,
+	{
+		self.peek_with(T::clone)
+	}
+
+	/// Calls `f` with a reference to the value sent by the sending side of the thread beam, without receiving it or cloning it.
+	///
+	/// Returns `None` without calling `f` if nothing has been sent yet, or if the sending side of the thread beam was dropped without sending anything. This never blocks waiting for a value, but the thread beam stays locked while `f` runs.
+	///
+	/// Like [`Mutex`], this only needs `T: Send`: the reference can't escape `f`, and nothing else can touch the value until `f` returns.
+	pub fn peek_with<R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
+		let inner = self.inner();
+		inner.lock.lock().peek_data().map(f)
+	}
+
+	/// Checks the thread beam once without blocking.
+	///
+	/// Returns `Poll::Ready(Some(value))` if a value has been sent, or `Poll::Ready(None)` if the sending side of the thread beam has been dropped.
+	///
+	/// Otherwise, `register` is called and `Poll::Pending` is returned. `register` is called after the lock has been released, so it may safely touch the thread beam again. The thread beam does not wake anything by itself, so `register` should arrange for this method to be called again later, e.g. on the next tick of an event loop.
+	pub fn poll_once(&self, register: impl FnOnce()) -> Poll<Option<T>> {
+		let inner = self.inner();
+
+		if let Poll::Ready(value) = inner.lock.lock().poll_data(&self.1) {
+			return Poll::Ready(value);
+		}
+
+		register();
+		Poll::Pending
+	}
+
+	#[cfg(feature = "async")]
+	pub(crate) fn poll_recv(&self, waker: &core::task::Waker) -> Poll<Option<T>> {
+		let inner = self.inner();
+
+		let mut lock = inner.lock.lock();
+		let poll = lock.poll_data(&self.1);
+		if poll.is_pending() {
+			// Registering under the same lock as the check above means a send or hang-up can't slip in between them unnoticed
+			lock.register_waker(waker);
+		}
+		poll
+	}
+
+	/// Returns whether a value has been sent and is waiting to be received.
+	///
+	/// This never blocks for long, but takes the thread beam's lock for a moment, as there is no lock-free snapshot of its flags in `no_std`. Once this returns `true`, the value stays put until it is received, so a following [`try_recv`](ThreadBeamRx::try_recv) is guaranteed to get it, unless a clone of this receiving side gets there first.
+	#[inline]
+	pub fn is_ready(&self) -> bool {
+		let inner = self.inner();
+		inner.lock.lock().has_data()
+	}
+
+	/// Returns whether the sending side of the thread beam has been dropped.
+	///
+	/// A value sent before the sending side was dropped can still be received. This never blocks for long, but the answer is only advisory: the sending side may be dropped immediately after this returns `false`.
+	pub fn is_disconnected(&self) -> bool {
+		let inner = self.inner();
+		!inner.lock.lock().tx_alive()
+	}
+
+	/// Returns whether there's nothing left to receive and never will be: the sending side of the thread beam has been dropped, and any value it sent has been received.
+	///
+	/// This is when [`try_recv`](ThreadBeamRx::try_recv) returns [`TryRecvError::Disconnected`] rather than [`TryRecvError::Empty`], for ending a non-blocking streaming drain. This never blocks for long, but takes the thread beam's lock for a moment, as there is no lock-free snapshot of its flags in `no_std`.
+	pub fn is_complete(&self) -> bool {
+		let inner = self.inner();
+		inner.lock.lock().poll_ready(&self.1) == Poll::Ready(false)
+	}
+
+	/// Returns the id of this thread beam, as given to the hook set by [`set_unconsumed_hook`](crate::set_unconsumed_hook).
+	#[cfg(feature = "debug-unconsumed")]
+	pub fn id(&self) -> usize {
+		let inner = self.inner();
+		inner.lock.lock().id
+	}
+}
+#[cfg(feature = "alloc")]
+impl<T: Send, Tag> core::fmt::Debug for ThreadBeamRx<T, Tag> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		let inner = self.inner();
+		super::fmt_state("ThreadBeamRx", inner.lock.try_lock().as_deref(), f)
+	}
 }
-impl<T: Send> Drop for ThreadBeamRx<T> {
+#[cfg(feature = "alloc")]
+impl<T: Send, Tag> ThreadBeamRx<T, Tag> {
+	/// Disconnects the receiving side of the thread beam without dropping it, as if every receiving side had been dropped.
+	///
+	/// Every later send fails, including from a sending side waiting for the previous value to be received. A value that has already been sent can still be received. Dropping the receiving side afterwards only frees the thread beam, if it's the last side left.
+	pub fn close(&self) {
+		let inner = self.inner();
+		inner.lock.lock().close_rx();
+	}
+
+	/// Drops a value that has been sent but not received straight away, on this thread, then disconnects the receiving side of the thread beam.
+	///
+	/// Just dropping the receiving side leaves a sent value in the thread beam until the sending side is dropped too, so its destructor runs on whichever thread drops the last side, whenever that is. Draining runs it now, and like [`close`](ThreadBeamRx::close), makes every later send fail, so no other value can take its place. Returns whether there was a value to drop.
+	///
+	/// With the `broadcast` feature, for a cloned receiving side, the value is only dropped if every other clone has already received it, and the thread beam is only disconnected if this is the last clone left. Otherwise, draining is the same as dropping it.
+	pub fn drain(self) -> bool {
+		let value = {
+			let inner = self.inner();
+
+			let mut lock = inner.lock.lock();
+			// A clone of a broadcast receiving side only drops the value if the others have all had their copies, and only closes the thread
+			// beam if it's the last one left; otherwise it just lets go of its share of both when it's dropped below
+			let value = lock.holds_original(&self.1).then(|| lock.take_data());
+			if lock.last_receiver() {
+				lock.close_rx();
+			}
+			value
+		};
+
+		// Outside the lock, in case the destructor takes a while
+		value.is_some()
+	}
+}
+#[cfg(feature = "alloc")]
+impl<T: Send, Tag> Drop for ThreadBeamRx<T, Tag> {
 	fn drop(&mut self) {
 		let deallocate = {
-			let inner = unsafe { self.0.as_ref() };
-			inner.lock.lock().drop_rx()
+			let inner = self.inner();
+			inner.lock.lock().drop_rx(&self.1)
 		};
 		if deallocate {
-			unsafe { Box::from_raw(self.0.as_ptr()) };
+			deallocate!(self.0);
 		}
 	}
 }
 
+/// Cloning the sending side of a thread beam lets several producers race to send its value.
+///
+/// The first value sent wins: later sends from any clone return their value back in a [`SendError`] while the first one is still waiting to be received, and after [`recv`](ThreadBeamRx::recv) has consumed the receiving side. The receiving side only sees the thread beam as disconnected once every clone has been dropped, and the allocation is freed once every clone and the receiving side are gone.
+#[cfg(feature = "alloc")]
+impl<T: Send, Tag> Clone for ThreadBeamTx<T, Tag> {
+	fn clone(&self) -> Self {
+		let inner = self.inner();
+		inner.lock.lock().clone_tx();
+		ThreadBeamTx(share(&self.0), PhantomData)
+	}
+}
+
+/// Cloning the receiving side of a thread beam broadcasts the value sent by the sending side to every clone. Only available with the `broadcast` feature.
+///
+/// Each receiving side that is still alive when it receives gets a clone of the value, except the last one, which gets the original. Receiving sides that are dropped without receiving simply aren't counted anymore.
+///
+/// Each clone receives each value once, however it receives: the value stays in the thread beam until every receiving side has had it, and receiving it again through a borrowing method such as [`try_recv`](ThreadBeamRx::try_recv) or [`recv_keep`](ThreadBeamRx::recv_keep) finds nothing, just as if the original had been taken.
+#[cfg(all(feature = "alloc", feature = "broadcast"))]
+impl<T: Send + Clone, Tag> Clone for ThreadBeamRx<T, Tag> {
+	fn clone(&self) -> Self {
+		let inner = self.inner();
+		inner.lock.lock().clone_rx();
+		ThreadBeamRx(share(&self.0), Seen::new(), PhantomData)
+	}
+}
+
+/// A value received with [`ThreadBeamRx::recv_ref`], borrowed in place.
+///
+/// The value is dropped along with the guard. Like a [`MutexGuard`], the guard gives out `&T`, so it can only be shared between threads if `T: Sync`.
+#[cfg(feature = "alloc")]
+pub struct BeamGuard<'a, T: Send>(MutexGuard<'a, ThreadBeamState<T>>, &'a Seen);
+#[cfg(feature = "alloc")]
+impl<T: Send> core::ops::Deref for BeamGuard<'_, T> {
+	type Target = T;
+
+	#[inline]
+	fn deref(&self) -> &T {
+		// Guards are only handed out while there is data, and only dropping the guard takes it
+		self.0.data_ref()
+	}
+}
+#[cfg(feature = "alloc")]
+impl<T: Send> Drop for BeamGuard<'_, T> {
+	fn drop(&mut self) {
+		drop(self.0.recv_data(self.1));
+	}
+}
+
 /// Creates a new thread beam channel pair.
 ///
+/// Zero-sized values, such as `()` used as a readiness signal, take up no space in the thread beam, but the shared lock is still allocated on the heap. Use [`ThreadBeamInline`] to avoid the allocation entirely.
+///
 /// # Example
 ///
 /// ```rust
 /// let (tx, rx) = threadbeam::channel();
 ///
 /// std::thread::spawn(move || {
-///     tx.send(String::from("Hello, world!"));
+///     tx.send(String::from("Hello, world!")).unwrap();
 /// });
 ///
 /// let hello = rx.recv();
 /// assert_eq!(hello.as_deref(), Some("Hello, world!"));
 /// ```
+#[cfg(feature = "alloc")]
+#[must_use = "a thread beam does nothing unless both of its sides are used"]
 pub fn channel<T: Send>() -> (ThreadBeamTx<T>, ThreadBeamRx<T>) {
-	let inner = Box::into_raw(Box::new(ThreadBeamInner {
-		lock: Mutex::new(ThreadBeamState {
-			data: MaybeUninit::uninit(),
-			flags: ThreadBeamFlags::TX | ThreadBeamFlags::RX,
-		}),
-	}));
-	let inner = unsafe { NonNull::new_unchecked(inner) };
-	(ThreadBeamTx(inner), ThreadBeamRx(inner))
+	split(Allocation::new(ThreadBeamInner::new()))
+}
+
+/// Creates a new thread beam channel pair, returning an [`AllocError`] instead of aborting if the allocation fails.
+///
+/// # Example
+///
+/// ```rust
+/// let Ok((tx, rx)) = threadbeam::try_channel() else {
+///     return; // Out of memory, so skip the work instead of aborting
+/// };
+///
+/// # let j =
+/// std::thread::spawn(move || {
+///     tx.send(String::from("Hello, world!")).unwrap();
+/// });
+///
+/// let hello = rx.recv();
+/// assert_eq!(hello.as_deref(), Some("Hello, world!"));
+/// # j.join().unwrap();
+/// ```
+#[cfg(all(not(feature = "safe"), feature = "alloc"))]
+#[must_use = "a thread beam does nothing unless both of its sides are used"]
+pub fn try_channel<T: Send>() -> Result<(ThreadBeamTx<T>, ThreadBeamRx<T>), AllocError> {
+	super::try_box(ThreadBeamInner::new()).map(split)
+}
+
+/// Storage for a thread beam that lives inline (on the stack or in a `static`) rather than on the heap.
+///
+/// The endpoints returned by [`split`](ThreadBeamInline::split) borrow the storage, so no allocation takes place.
+///
+/// # Example
+///
+/// ```rust
+/// let mut beam = threadbeam::ThreadBeamInline::new();
+/// let (tx, rx) = beam.split();
+///
+/// std::thread::scope(|s| {
+///     s.spawn(move || {
+///         tx.send(String::from("Hello, world!")).unwrap();
+///     });
+///
+///     let hello = rx.recv();
+///     assert_eq!(hello.as_deref(), Some("Hello, world!"));
+/// });
+/// ```
+pub struct ThreadBeamInline<T: Send> {
+	lock: Mutex<ThreadBeamState<T>>,
+}
+impl<T: Send> ThreadBeamInline<T> {
+	/// Creates new, empty inline storage for a thread beam.
+	pub const fn new() -> Self {
+		Self {
+			lock: Mutex::new(ThreadBeamState::unsplit()),
+		}
+	}
+
+	/// Creates a new thread beam channel pair borrowing this storage.
+	///
+	/// Any value left over from a previous pair is dropped.
+	pub fn split(&mut self) -> (ThreadBeamInlineTx<'_, T>, ThreadBeamInlineRx<'_, T>) {
+		*self.lock.get_mut() = ThreadBeamState::new();
+		(ThreadBeamInlineTx(&self.lock), ThreadBeamInlineRx(&self.lock))
+	}
+}
+impl<T: Send> Default for ThreadBeamInline<T> {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// The sending side of an inline thread beam.
+pub struct ThreadBeamInlineTx<'a, T: Send>(&'a Mutex<ThreadBeamState<T>>);
+
+/// The receiving side of an inline thread beam.
+pub struct ThreadBeamInlineRx<'a, T: Send>(&'a Mutex<ThreadBeamState<T>>);
+
+impl<T: Send> ThreadBeamInlineTx<'_, T> {
+	/// Send a value to the receiving side of the thread beam.
+	///
+	/// Returns the value back in a [`SendError`] if the receiving side of the thread beam has been dropped.
+	pub fn send(self, value: T) -> Result<(), SendError<T>> {
+		spin_send(self.0, value)
+	}
+}
+impl<T: Send> Drop for ThreadBeamInlineTx<'_, T> {
+	fn drop(&mut self) {
+		let _ = self.0.lock().drop_tx();
+	}
+}
+
+impl<T: Send> ThreadBeamInlineRx<'_, T> {
+	/// Receive the value sent by the sending side of the thread beam.
+	///
+	/// Returns `None` if the sending side of the thread beam has been dropped.
+	#[must_use = "the beamed value is discarded if not used"]
+	pub fn recv(self) -> Option<T> {
+		// Inline receiving sides can't be cloned, so they never need to remember which value they have seen
+		spin_recv(self.0, &Seen::new())
+	}
+}
+impl<T: Send> Drop for ThreadBeamInlineRx<'_, T> {
+	fn drop(&mut self) {
+		let _ = self.0.lock().drop_rx(&Seen::new());
+	}
 }
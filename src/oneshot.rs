@@ -0,0 +1,112 @@
+//! A layer over thread beams shaped like the `oneshot` channels of `tokio` and `futures`, for migrating from them.
+//!
+//! Swapping `oneshot::channel()` for [`threadbeam::oneshot()`](crate::oneshot()) (or this module's [`channel`]) should mostly be mechanical:
+//! [`Sender::send`] hands the value back as a plain `T` on failure, and with the `async` feature, a [`Receiver`] can be `.await`ed for a
+//! `Result<T, RecvError>`.
+//!
+//! The differences come from thread beams being made for beaming values out of threads rather than between tasks:
+//!
+//! * A [`Receiver`] can also block the current thread until the value arrives, with [`blocking_recv`](Receiver::blocking_recv), and
+//!   `.await`ing it needs the `async` feature, without which it is blocking-only.
+//! * There is no async `Sender::closed`; poll [`is_closed`](Sender::is_closed) instead.
+//! * [`try_recv`](Receiver::try_recv) returns threadbeam's own [`TryRecvError`], with the same `Empty` and `Disconnected` cases as `tokio`'s.
+//!
+//! # Example
+//!
+//! ```rust
+//! let (tx, rx) = threadbeam::oneshot();
+//!
+//! # let j =
+//! std::thread::spawn(move || {
+//!     if tx.send(String::from("Hello, world!")).is_err() {
+//!         println!("the receiver dropped");
+//!     }
+//! });
+//!
+//! assert_eq!(rx.blocking_recv().as_deref(), Ok("Hello, world!"));
+//! # j.join().unwrap();
+//! ```
+
+use super::{channel as beam, RecvError, SendError, ThreadBeamRx, ThreadBeamTx, TryRecvError};
+
+/// Creates a new one-shot channel pair.
+#[inline]
+#[must_use = "a thread beam does nothing unless both of its sides are used"]
+pub fn channel<T: Send>() -> (Sender<T>, Receiver<T>) {
+	let (tx, rx) = beam();
+	(Sender(tx), Receiver(rx))
+}
+
+/// The sending half of a one-shot channel, wrapping a [`ThreadBeamTx`].
+pub struct Sender<T: Send>(ThreadBeamTx<T>);
+impl<T: Send> Sender<T> {
+	/// Sends a value to the receiving half, returning it back if the receiving half has been dropped or closed.
+	#[inline]
+	pub fn send(self, value: T) -> Result<(), T> {
+		self.0.send(value).map_err(|SendError(value)| value)
+	}
+
+	/// Returns whether the receiving half has been dropped or closed.
+	#[inline]
+	pub fn is_closed(&self) -> bool {
+		self.0.is_disconnected()
+	}
+}
+impl<T: Send> From<ThreadBeamTx<T>> for Sender<T> {
+	#[inline]
+	fn from(tx: ThreadBeamTx<T>) -> Self {
+		Sender(tx)
+	}
+}
+impl<T: Send> core::fmt::Debug for Sender<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.debug_tuple("Sender").field(&self.0).finish()
+	}
+}
+
+/// The receiving half of a one-shot channel, wrapping a [`ThreadBeamRx`].
+#[must_use = "dropping the receiving side of a thread beam makes every send to it fail"]
+pub struct Receiver<T: Send>(ThreadBeamRx<T>);
+impl<T: Send> Receiver<T> {
+	/// Attempts to receive the value without blocking.
+	///
+	/// Returns [`TryRecvError::Empty`] if nothing has been sent yet, or [`TryRecvError::Disconnected`] if the sending half has been dropped without sending anything, or the value has already been received.
+	#[inline]
+	pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+		self.0.try_recv()
+	}
+
+	/// Blocks the current thread until the value is sent.
+	///
+	/// Returns [`RecvError`] if the sending half was dropped without sending anything.
+	#[inline]
+	pub fn blocking_recv(self) -> Result<T, RecvError> {
+		self.0.recv_result()
+	}
+
+	/// Closes the receiving half without dropping it, so that sending fails, while still letting a value that has already been sent be received.
+	#[inline]
+	pub fn close(&mut self) {
+		self.0.close();
+	}
+}
+impl<T: Send> From<ThreadBeamRx<T>> for Receiver<T> {
+	#[inline]
+	fn from(rx: ThreadBeamRx<T>) -> Self {
+		Receiver(rx)
+	}
+}
+impl<T: Send> core::fmt::Debug for Receiver<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.debug_tuple("Receiver").field(&self.0).finish()
+	}
+}
+#[cfg(feature = "async")]
+impl<T: Send> core::future::Future for Receiver<T> {
+	type Output = Result<T, RecvError>;
+
+	#[inline]
+	fn poll(self: core::pin::Pin<&mut Self>, cx: &mut core::task::Context<'_>) -> core::task::Poll<Self::Output> {
+		self.0.poll_recv(cx.waker()).map(|value| value.ok_or(RecvError))
+	}
+}
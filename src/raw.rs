@@ -0,0 +1,184 @@
+//! Lower-level building blocks for wrappers over thread beams with their own storage or blocking strategy.
+//!
+//! Everything that can break the thread beam's invariants is `unsafe`, with the contract spelled out. Everything else is safe, and is enough to
+//! build a receiver that waits however you like on top of the same lock and flags as [`ThreadBeamRx::recv`].
+//!
+//! Without a [`ThreadBeamTx`] or [`ThreadBeamRx`] at all, a thread beam can be driven entirely through pointers: [`create`] one, [`send`] and
+//! [`try_recv`] or [`poll`] through it without ever blocking, and [`release_tx`] and [`release_rx`] its sides when done, which frees it once
+//! both are gone. How to wait in between is up to you. Each pointer stands for one side, so every pointer must be released exactly once, and
+//! never used after that.
+//!
+//! With the `broadcast` feature, only use a pointer for a receiving side that hasn't been cloned: a pointer doesn't remember which values its
+//! side has already had a clone of, so receiving through it again may get the same value twice.
+
+use super::{Seen, SendError, ThreadBeamInner, ThreadBeamRx, ThreadBeamTx, TryRecvError};
+use core::{marker::PhantomData, mem::ManuallyDrop, ptr::NonNull};
+
+/// The shared core of a thread beam, only ever handled through a pointer.
+///
+/// A pointer to this stands in for one side of a thread beam, as returned by [`tx_into_raw`] or [`rx_into_raw`].
+#[repr(transparent)]
+pub struct RawThreadBeam<T>(ThreadBeamInner<T>);
+
+/// Turns the sending side of a thread beam into a raw pointer, without dropping it.
+///
+/// The thread beam stays alive, with this side counted as alive too, until the pointer is turned back with [`tx_from_raw`] and dropped.
+#[inline]
+pub fn tx_into_raw<T: Send>(tx: ThreadBeamTx<T>) -> NonNull<RawThreadBeam<T>> {
+	let ptr = tx.0.cast();
+	core::mem::forget(tx);
+	ptr
+}
+
+/// Turns a raw pointer back into the sending side of a thread beam.
+///
+/// # Safety
+///
+/// `ptr` must have been returned by [`tx_into_raw`] for the same `T`, and must not have been turned back already. Each call to `tx_into_raw` may
+/// be matched by at most one call to this; turning the same pointer back twice makes both sides drop it, which is a double free.
+#[inline]
+pub unsafe fn tx_from_raw<T: Send>(ptr: NonNull<RawThreadBeam<T>>) -> ThreadBeamTx<T> {
+	ThreadBeamTx(ptr.cast(), PhantomData)
+}
+
+/// Turns the receiving side of a thread beam into a raw pointer, without dropping it.
+///
+/// The thread beam stays alive, with this side counted as alive too, until the pointer is turned back with [`rx_from_raw`] and dropped.
+///
+/// The pointer only refers to the shared thread beam, so a receiving side that has been cloned to broadcast the value forgets whether it already
+/// received a clone of the current value. Only turn such a receiving side into a pointer before it receives, or after the value has been taken.
+#[inline]
+pub fn rx_into_raw<T: Send>(rx: ThreadBeamRx<T>) -> NonNull<RawThreadBeam<T>> {
+	let ptr = rx.0.cast();
+	core::mem::forget(rx);
+	ptr
+}
+
+/// Turns a raw pointer back into the receiving side of a thread beam.
+///
+/// # Safety
+///
+/// `ptr` must have been returned by [`rx_into_raw`] for the same `T`, and must not have been turned back already. Each call to `rx_into_raw` may
+/// be matched by at most one call to this; turning the same pointer back twice makes both sides drop it, which is a double free. A pointer
+/// from [`tx_into_raw`] must not be turned into a receiving side, or vice versa.
+#[inline]
+pub unsafe fn rx_from_raw<T: Send>(ptr: NonNull<RawThreadBeam<T>>) -> ThreadBeamRx<T> {
+	ThreadBeamRx(ptr.cast(), Seen::new(), PhantomData)
+}
+
+/// Creates a thread beam, returning a pointer that stands for its sending side and one that stands for its receiving side, in that order.
+///
+/// This is [`channel`](crate::channel), followed by [`tx_into_raw`] and [`rx_into_raw`]. Both pointers have the same address, but they aren't
+/// interchangeable: each must only be passed to the functions for its own side, and released exactly once, with [`release_tx`] or
+/// [`release_rx`], or by turning it back into its side and dropping that. Creating them is safe, since never releasing them only leaks the
+/// thread beam.
+#[inline]
+pub fn create<T: Send>() -> (NonNull<RawThreadBeam<T>>, NonNull<RawThreadBeam<T>>) {
+	let (tx, rx) = super::channel();
+	(tx_into_raw(tx), rx_into_raw(rx))
+}
+
+/// Sends `value` through the sending side that `tx` stands for, without blocking and without releasing it.
+///
+/// Returns the value back in a [`SendError`] if the receiving side has hung up, or if a value that was sent before is still waiting to be
+/// received. Once that one has been received, the slot is free again, so values can be streamed through this with a waiting strategy of your own.
+///
+/// # Safety
+///
+/// `tx` must stand for the sending side of a thread beam of the same `T`, from [`create`] or [`tx_into_raw`], that hasn't been released or
+/// turned back with [`tx_from_raw`] yet. Calls may race with each other and with calls for the receiving side, but not with releasing `tx`.
+#[inline]
+pub unsafe fn send<T: Send>(tx: NonNull<RawThreadBeam<T>>, value: T) -> Result<(), SendError<T>> {
+	ManuallyDrop::new(tx_from_raw(tx)).send_open(value)
+}
+
+/// Receives a value through the receiving side that `rx` stands for, without blocking and without releasing it.
+///
+/// This is [`ThreadBeamRx::try_recv`]: it returns [`TryRecvError::Empty`] if nothing has been sent yet, or [`TryRecvError::Disconnected`] once
+/// the sending side has hung up and there's nothing left to receive.
+///
+/// # Safety
+///
+/// `rx` must stand for the receiving side of a thread beam of the same `T`, from [`create`] or [`rx_into_raw`], that hasn't been released or
+/// turned back with [`rx_from_raw`] yet. Calls may race with each other and with calls for the sending side, but not with releasing `rx`.
+#[inline]
+pub unsafe fn try_recv<T: Send>(rx: NonNull<RawThreadBeam<T>>) -> Result<T, TryRecvError> {
+	ManuallyDrop::new(rx_from_raw(rx)).try_recv()
+}
+
+/// Like [`poll_recv`], for the receiving side that `rx` stands for: checks the thread beam once without blocking, registering `waker` to be
+/// woken once a value is sent or the sending side hangs up.
+///
+/// # Safety
+///
+/// The same as for [`try_recv`].
+#[cfg(any(feature = "async", not(feature = "no_std")))]
+#[inline]
+pub unsafe fn poll<T: Send>(rx: NonNull<RawThreadBeam<T>>, waker: &core::task::Waker) -> core::task::Poll<Option<T>> {
+	ManuallyDrop::new(rx_from_raw(rx)).poll_recv(waker)
+}
+
+/// Releases the sending side that `tx` stands for, as if it had been dropped.
+///
+/// The receiving side sees the thread beam as disconnected once every other sending side is gone too, and whichever side is released or
+/// dropped last frees the thread beam, along with any value still in it.
+///
+/// # Safety
+///
+/// The same as for [`send`]. `tx` must not be used at all afterwards, so releasing it twice, or also turning it back with [`tx_from_raw`], is
+/// a double free.
+#[inline]
+pub unsafe fn release_tx<T: Send>(tx: NonNull<RawThreadBeam<T>>) {
+	drop(tx_from_raw(tx));
+}
+
+/// Releases the receiving side that `rx` stands for, as if it had been dropped.
+///
+/// Every later send fails, and whichever side is released or dropped last frees the thread beam, along with any value still in it.
+///
+/// # Safety
+///
+/// The same as for [`try_recv`]. `rx` must not be used at all afterwards, so releasing it twice, or also turning it back with [`rx_from_raw`],
+/// is a double free.
+#[inline]
+pub unsafe fn release_rx<T: Send>(rx: NonNull<RawThreadBeam<T>>) {
+	drop(rx_from_raw(rx));
+}
+
+impl<T: Send> ThreadBeamTx<T> {
+	/// Turns the sending side of the thread beam into an opaque pointer without dropping it, e.g. to stash it in native code until a callback
+	/// fires.
+	///
+	/// This is [`tx_into_raw`], for code that only needs a `void*`. The pointer is never null.
+	#[inline]
+	pub fn into_raw(self) -> *mut () {
+		tx_into_raw(self).as_ptr().cast()
+	}
+
+	/// Turns an opaque pointer returned by [`into_raw`](ThreadBeamTx::into_raw) back into the sending side of the thread beam.
+	///
+	/// # Safety
+	///
+	/// `ptr` must have been returned by [`into_raw`](ThreadBeamTx::into_raw), or cast from a pointer returned by [`tx_into_raw`], for a thread
+	/// beam of the same `T`. Each pointer may be turned back exactly once: turning it back twice makes both sending sides drop it, which is a
+	/// double free, and never turning it back leaks the thread beam and leaves its receiving side waiting forever.
+	#[inline]
+	pub unsafe fn from_raw(ptr: *mut ()) -> Self {
+		debug_assert!(!ptr.is_null(), "null pointer turned into the sending side of a thread beam");
+		tx_from_raw(NonNull::new_unchecked(ptr.cast()))
+	}
+}
+
+/// Checks the thread beam once without blocking, registering `waker` to be woken once a value is sent or the sending side is dropped.
+///
+/// This is the primitive for waiting on a thread beam with a blocking strategy of your own: call it, and if it returns `Poll::Pending`, wait
+/// however you like until `waker` is woken, then call it again. The thread beam holds a single waker, which is replaced by each pending call
+/// unless [`Waker::will_wake`](core::task::Waker::will_wake) says it would wake the same task, and woken once, after the thread beam has been
+/// unlocked.
+///
+/// Returns `Poll::Ready(None)` if the sending side of the thread beam has been dropped.
+#[cfg(any(feature = "async", not(feature = "no_std")))]
+#[inline]
+pub fn poll_recv<T: Send>(rx: &ThreadBeamRx<T>, waker: &core::task::Waker) -> core::task::Poll<Option<T>> {
+	rx.poll_recv(waker)
+}
@@ -0,0 +1,70 @@
+use super::{ThreadBeamRx, TryRecvError};
+use alloc::sync::Arc;
+
+/// The receiving side of a thread beam, shared between threads that race to take the value.
+///
+/// Clones share the same receiving side, and the value is delivered exactly once: whichever clone takes it first gets it, and every other
+/// clone, then and later, sees the thread beam as empty or disconnected, never the value. The thread beam stays connected until the last
+/// clone has been dropped.
+///
+/// # Example
+///
+/// ```rust
+/// let (tx, rx) = threadbeam::channel();
+/// let rx = threadbeam::SharedRx::from(rx);
+///
+/// tx.send(42).unwrap();
+///
+/// let takers = (0..4)
+///     .map(|_| {
+///         let rx = rx.clone();
+///         std::thread::spawn(move || rx.try_recv().ok())
+///     })
+///     .collect::<Vec<_>>();
+///
+/// let taken = takers.into_iter().filter_map(|taker| taker.join().unwrap()).collect::<Vec<_>>();
+/// assert_eq!(taken, [42]);
+/// ```
+pub struct SharedRx<T: Send>(Arc<ThreadBeamRx<T>>);
+impl<T: Send> SharedRx<T> {
+	/// Attempts to take the value sent by the sending side of the thread beam without blocking.
+	///
+	/// Returns [`TryRecvError::Empty`] if nothing has been sent yet, or [`TryRecvError::Disconnected`] if the sending side of the thread beam has been dropped.
+	///
+	/// Once another clone has taken the value, this returns `Empty` for as long as the sending side is still alive, e.g. a streaming sending side that may send again, and `Disconnected` once it has been dropped.
+	#[inline]
+	pub fn try_recv(&self) -> Result<T, TryRecvError> {
+		self.0.try_recv()
+	}
+
+	/// Waits for the value sent by the sending side of the thread beam, racing the other clones for it.
+	///
+	/// Returns `None` if the sending side of the thread beam has been dropped. If another clone took the value first, this keeps waiting for as long as the sending side is still alive, either for it to send another value or to be dropped.
+	#[inline]
+	pub fn recv(&self) -> Option<T> {
+		self.0.recv_streaming()
+	}
+
+	/// Returns whether the sending side of the thread beam has been dropped.
+	#[inline]
+	pub fn is_disconnected(&self) -> bool {
+		self.0.is_disconnected()
+	}
+}
+impl<T: Send> Clone for SharedRx<T> {
+	#[inline]
+	fn clone(&self) -> Self {
+		Self(self.0.clone())
+	}
+}
+impl<T: Send> From<ThreadBeamRx<T>> for SharedRx<T> {
+	#[inline]
+	fn from(rx: ThreadBeamRx<T>) -> Self {
+		Self(Arc::new(rx))
+	}
+}
+impl<T: Send> core::fmt::Debug for SharedRx<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.debug_tuple("SharedRx").field(&*self.0).finish()
+	}
+}
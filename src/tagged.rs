@@ -0,0 +1,43 @@
+use super::{split, ThreadBeamInner, ThreadBeamRx, ThreadBeamTx};
+
+/// Creates a new thread beam channel pair whose sides are tagged with the marker type `Tag`.
+///
+/// Sides with different tags are different types, so the compiler catches a sender from one channel being wired up to code expecting another channel of the same `T`. The tag is never constructed and doesn't change the layout of either side.
+///
+/// # Example
+///
+/// ```rust
+/// struct Config;
+/// struct Handshake;
+///
+/// fn wait_for_config(rx: threadbeam::ThreadBeamRx<String, Config>) -> Option<String> {
+///     rx.recv()
+/// }
+///
+/// let (tx, rx) = threadbeam::channel_tagged::<Config, String>();
+/// tx.send(String::from("Hello, world!")).unwrap();
+/// assert_eq!(wait_for_config(rx).as_deref(), Some("Hello, world!"));
+/// ```
+///
+/// Mixing up channels with different tags doesn't compile:
+///
+/// ```rust,compile_fail
+/// struct Config;
+/// struct Handshake;
+///
+/// fn wait_for_config(rx: threadbeam::ThreadBeamRx<String, Config>) -> Option<String> {
+///     rx.recv()
+/// }
+///
+/// let (_tx, rx) = threadbeam::channel_tagged::<Handshake, String>();
+/// wait_for_config(rx);
+/// ```
+#[inline]
+#[must_use = "a thread beam does nothing unless both of its sides are used"]
+pub fn channel_tagged<Tag, T: Send>() -> (ThreadBeamTx<T, Tag>, ThreadBeamRx<T, Tag>) {
+	#[cfg(not(feature = "no_std"))]
+	let inner = ThreadBeamInner::new();
+	#[cfg(feature = "no_std")]
+	let inner = super::Allocation::new(ThreadBeamInner::new());
+	split(inner)
+}
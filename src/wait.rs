@@ -0,0 +1,120 @@
+use super::{RecvTimeoutError, ThreadBeamRx};
+use core::task::Poll;
+use std::{
+	sync::Arc,
+	task::{Wake, Waker},
+	time::{Duration, Instant},
+};
+
+/// A way of blocking the receiving thread until the sending side of a thread beam makes progress, for [`ThreadBeamRx::recv_with`].
+///
+/// The thread beam calls [`notify`](WaitStrategy::notify) once a value is sent or the sending side is dropped, possibly before the receiving
+/// thread has started waiting, so like [`thread::park`](std::thread::park) and [`Thread::unpark`](std::thread::Thread::unpark), a
+/// notification must make the next wait return straight away. Waits may also return spuriously; the thread beam is always checked again
+/// afterwards.
+///
+/// # Example
+///
+/// An adaptive strategy that spins for a moment before parking the thread:
+///
+/// ```rust
+/// use std::{sync::atomic::{AtomicBool, Ordering}, thread::Thread, time::Duration};
+///
+/// struct SpinThenPark {
+///     thread: Thread,
+///     notified: AtomicBool,
+/// }
+/// impl threadbeam::WaitStrategy for SpinThenPark {
+///     fn wait(&self) {
+///         for _ in 0..100 {
+///             if self.notified.swap(false, Ordering::Acquire) {
+///                 return;
+///             }
+///             std::hint::spin_loop();
+///         }
+///         std::thread::park();
+///     }
+///
+///     fn wait_timeout(&self, timeout: Duration) {
+///         if !self.notified.swap(false, Ordering::Acquire) {
+///             std::thread::park_timeout(timeout);
+///         }
+///     }
+///
+///     fn notify(&self) {
+///         self.notified.store(true, Ordering::Release);
+///         self.thread.unpark();
+///     }
+/// }
+///
+/// let (tx, rx) = threadbeam::channel();
+///
+/// # let j =
+/// std::thread::spawn(move || {
+///     tx.send(String::from("Hello, world!")).unwrap();
+/// });
+///
+/// let strategy = SpinThenPark { thread: std::thread::current(), notified: AtomicBool::new(false) };
+/// let hello = rx.recv_with(strategy);
+/// assert_eq!(hello.as_deref(), Some("Hello, world!"));
+/// # j.join().unwrap();
+/// ```
+pub trait WaitStrategy: Send + Sync + 'static {
+	/// Blocks the current thread until [`notify`](WaitStrategy::notify) is called, or returns straight away if it already has been since the
+	/// last wait.
+	fn wait(&self);
+
+	/// Like [`wait`](WaitStrategy::wait), but returns once `timeout` has elapsed at the latest.
+	fn wait_timeout(&self, timeout: Duration);
+
+	/// Wakes the thread that is waiting, or that is about to.
+	fn notify(&self);
+}
+
+struct Notify<W>(W);
+impl<W: WaitStrategy> Wake for Notify<W> {
+	#[inline]
+	fn wake(self: Arc<Self>) {
+		self.0.notify();
+	}
+
+	#[inline]
+	fn wake_by_ref(self: &Arc<Self>) {
+		self.0.notify();
+	}
+}
+
+impl<T: Send, Tag> ThreadBeamRx<T, Tag> {
+	/// Receive the value sent by the sending side of the thread beam, blocking with `strategy` instead of the backend's own condvar.
+	///
+	/// Returns `None` if the sending side of the thread beam has been dropped.
+	#[must_use = "the beamed value is discarded if not used"]
+	pub fn recv_with(self, strategy: impl WaitStrategy) -> Option<T> {
+		let notify = Arc::new(Notify(strategy));
+		let waker = Waker::from(notify.clone());
+		loop {
+			match self.poll_recv(&waker) {
+				Poll::Ready(value) => return value,
+				Poll::Pending => notify.0.wait(),
+			}
+		}
+	}
+
+	/// Receive the value sent by the sending side of the thread beam, blocking with `strategy` for at most `timeout`.
+	///
+	/// Returns [`RecvTimeoutError::Timeout`] if `timeout` elapses before a value is sent, or [`RecvTimeoutError::Disconnected`] if the sending side of the thread beam has been dropped.
+	pub fn recv_timeout_with(self, strategy: impl WaitStrategy, timeout: Duration) -> Result<T, RecvTimeoutError> {
+		let deadline = Instant::now() + timeout;
+		let notify = Arc::new(Notify(strategy));
+		let waker = Waker::from(notify.clone());
+		loop {
+			match self.poll_recv(&waker) {
+				Poll::Ready(value) => return value.ok_or(RecvTimeoutError::Disconnected),
+				Poll::Pending => match deadline.checked_duration_since(Instant::now()) {
+					Some(remaining) if !remaining.is_zero() => notify.0.wait_timeout(remaining),
+					_ => return Err(RecvTimeoutError::Timeout),
+				},
+			}
+		}
+	}
+}
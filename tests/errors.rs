@@ -0,0 +1,91 @@
+//! Checks that the error and status types are the same whichever backend is enabled, so that code generic over them compiles everywhere.
+//!
+//! This is only meaningful when run under each backend:
+//!
+//! ```text
+//! cargo test --test errors
+//! cargo test --test errors --features parking_lot
+//! cargo test --test errors --features spin
+//! cargo test --test errors --features no_std
+//! cargo test --test errors --features safe,no_std
+//! ```
+//!
+//! Every check needs a thread beam to get its errors from, so there's nothing to check under `no_std` without `alloc`.
+
+#![cfg(any(feature = "alloc", not(feature = "no_std")))]
+
+use threadbeam::{RecvCancelError, RecvError, RecvStatus, RecvTimeoutError, SendError, SendTimeoutError, SpawnError, TryRecvError};
+
+fn check<E: core::fmt::Debug + core::fmt::Display + Clone + PartialEq>(error: E) {
+	assert_eq!(error.clone(), error);
+	assert!(!format!("{error}").is_empty());
+	assert!(!format!("{error:?}").is_empty());
+}
+
+#[cfg(not(feature = "no_std"))]
+fn check_error<E: std::error::Error + 'static>(error: E) {
+	let boxed: Box<dyn std::error::Error> = Box::new(error);
+	assert!(!boxed.to_string().is_empty());
+}
+#[cfg(feature = "no_std")]
+fn check_error<E>(_: E) {}
+
+#[test]
+fn test_shared_errors() {
+	let (tx, rx) = threadbeam::channel::<u32>();
+	let try_recv = rx.try_recv().unwrap_err();
+	assert_eq!(try_recv, TryRecvError::Empty);
+	check(try_recv);
+	check_error(try_recv);
+
+	drop(rx);
+	let send = tx.send(42).unwrap_err();
+	assert_eq!(send, SendError(42));
+	assert_eq!(send.0, 42);
+	check(send);
+	check_error(send);
+
+	let (tx, rx) = threadbeam::channel::<u32>();
+	drop(tx);
+	let recv = rx.recv_result().unwrap_err();
+	assert_eq!(recv, RecvError);
+	check(recv);
+	check_error(recv);
+
+	for error in [RecvTimeoutError::Timeout, RecvTimeoutError::Disconnected] {
+		check(error);
+		check_error(error);
+	}
+	for error in [RecvCancelError::Cancelled, RecvCancelError::Disconnected] {
+		check(error);
+		check_error(error);
+	}
+	for error in [SendTimeoutError::Timeout(42), SendTimeoutError::Disconnected(42)] {
+		assert_eq!(error.into_inner(), 42);
+		check(error);
+		check_error(error);
+	}
+
+	let spawn = SpawnError::NoValue(42);
+	assert!(!format!("{spawn} {spawn:?}").is_empty());
+	check_error(spawn);
+
+	let status: RecvStatus<u32> = RecvStatus::TimedOut;
+	assert_eq!(status, RecvStatus::TimedOut);
+	assert_ne!(RecvStatus::Received(42), RecvStatus::SenderDropped);
+}
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_recv_error_propagates() {
+	fn handshake(rx: threadbeam::ThreadBeamRx<u32>) -> Result<u32, Box<dyn std::error::Error>> {
+		Ok(rx.recv_result()?)
+	}
+
+	let (tx, rx) = threadbeam::channel();
+	tx.send(42).unwrap();
+	assert_eq!(handshake(rx).unwrap(), 42);
+
+	let error = handshake(threadbeam::ThreadBeamRx::disconnected()).unwrap_err();
+	assert_eq!(error.downcast_ref::<RecvError>(), Some(&RecvError));
+}
@@ -0,0 +1,61 @@
+//! Checks that an inline thread beam never allocates, so that it keeps working without the `alloc` feature.
+//!
+//! This counts allocations process-wide, so it runs without the test harness. It's only meaningful under `no_std`, and most of all
+//! with `alloc` turned off:
+//!
+//! ```text
+//! cargo test --test inline --features no_std
+//! cargo test --test inline --no-default-features --features no_std
+//! ```
+
+#[cfg(feature = "no_std")]
+mod counting {
+	use std::{
+		alloc::{GlobalAlloc, Layout, System},
+		sync::atomic::{AtomicUsize, Ordering},
+	};
+
+	struct CountingAllocator;
+
+	pub static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+	unsafe impl GlobalAlloc for CountingAllocator {
+		unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+			ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+			System.alloc(layout)
+		}
+
+		unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+			System.dealloc(ptr, layout)
+		}
+	}
+
+	#[global_allocator]
+	static ALLOCATOR: CountingAllocator = CountingAllocator;
+}
+
+#[cfg(feature = "no_std")]
+fn main() {
+	use counting::ALLOCATIONS;
+	use std::sync::atomic::Ordering;
+
+	let mut beam = threadbeam::ThreadBeamInline::new();
+	let before = ALLOCATIONS.load(Ordering::SeqCst);
+
+	let (tx, rx) = beam.split();
+	tx.send([42u64; 4]).unwrap();
+	assert_eq!(rx.recv(), Some([42; 4]));
+
+	// Reusing the storage for a second pair must not allocate either
+	let (tx, rx) = beam.split();
+	drop(tx);
+	assert_eq!(rx.recv(), None);
+
+	let allocations = ALLOCATIONS.load(Ordering::SeqCst) - before;
+	assert_eq!(allocations, 0, "inline thread beam allocated {allocations} times");
+
+	println!("inline thread beam ... ok");
+}
+
+#[cfg(not(feature = "no_std"))]
+fn main() {}
@@ -0,0 +1,263 @@
+//! Checks that every way of using a thread beam frees its allocation exactly once and drops the beamed value exactly once.
+//!
+//! This counts allocations process-wide, so it runs its scenarios one after the other on the main thread without the test harness.
+
+use std::{
+	alloc::{GlobalAlloc, Layout, System},
+	sync::atomic::{AtomicIsize, AtomicUsize, Ordering},
+};
+
+struct CountingAllocator;
+
+static LIVE_ALLOCATIONS: AtomicIsize = AtomicIsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		let ptr = System.alloc(layout);
+		if !ptr.is_null() {
+			LIVE_ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+		}
+		ptr
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		LIVE_ALLOCATIONS.fetch_sub(1, Ordering::SeqCst);
+		System.dealloc(ptr, layout)
+	}
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone)]
+struct Counted(#[allow(dead_code)] Box<u64>);
+impl Drop for Counted {
+	fn drop(&mut self) {
+		DROPS.fetch_add(1, Ordering::SeqCst);
+	}
+}
+
+fn counted() -> Counted {
+	Counted(Box::new(42))
+}
+
+fn check(name: &str, expected_drops: usize, scenario: impl FnOnce()) {
+	DROPS.store(0, Ordering::SeqCst);
+	let live = LIVE_ALLOCATIONS.load(Ordering::SeqCst);
+
+	scenario();
+
+	let drops = DROPS.load(Ordering::SeqCst);
+	assert_eq!(drops, expected_drops, "{name}: payload dropped {drops} times, expected {expected_drops}");
+
+	let leaked = LIVE_ALLOCATIONS.load(Ordering::SeqCst) - live;
+	assert_eq!(leaked, 0, "{name}: {leaked} allocations leaked");
+
+	println!("{name} ... ok");
+}
+
+fn main() {
+	// Let std (and parking_lot's global table of parked threads) set up anything it allocates lazily before counting
+	let (tx, rx) = threadbeam::channel();
+	let thread = std::thread::spawn(move || {
+		std::thread::sleep(std::time::Duration::from_millis(10));
+		tx.send(()).unwrap();
+	});
+	assert!(rx.recv().is_some());
+	thread.join().unwrap();
+
+	check("send then recv", 1, || {
+		let (tx, rx) = threadbeam::channel();
+		tx.send(counted()).unwrap();
+		drop(rx.recv());
+	});
+
+	check("send, never recv", 1, || {
+		let (tx, rx) = threadbeam::channel();
+		tx.send(counted()).unwrap();
+		drop(rx);
+	});
+
+	check("recv side dropped first, then send", 1, || {
+		let (tx, rx) = threadbeam::channel();
+		drop(rx);
+		drop(tx.send(counted()).unwrap_err());
+	});
+
+	check("send side dropped without sending", 0, || {
+		let (tx, rx) = threadbeam::channel::<Counted>();
+		drop(tx);
+		assert!(rx.recv().is_none());
+	});
+
+	check("recv side dropped without receiving, send side never used", 0, || {
+		let (tx, rx) = threadbeam::channel::<Counted>();
+		drop(rx);
+		drop(tx);
+	});
+
+	check("peek then recv", 2, || {
+		let (tx, rx) = threadbeam::channel();
+		tx.send(counted()).unwrap();
+		drop(rx.peek());
+		drop(rx.recv());
+	});
+
+	check("streaming, partially received", 3, || {
+		let (tx, rx) = threadbeam::channel();
+		tx.send_streaming(counted()).unwrap();
+		drop(rx.recv_streaming());
+		tx.send_streaming(counted()).unwrap();
+		drop(rx.recv_streaming());
+		tx.send_streaming(counted()).unwrap();
+		drop(tx);
+		drop(rx);
+	});
+
+	check("weak sender outliving both sides", 1, || {
+		let (tx, rx) = threadbeam::channel();
+		let weak = tx.downgrade();
+		tx.send(counted()).unwrap();
+		drop(rx);
+		assert!(weak.upgrade().is_none());
+		drop(weak);
+	});
+
+	check("upgraded sender losing the race", 2, || {
+		let (tx, rx) = threadbeam::channel();
+		let upgraded = tx.downgrade().upgrade().unwrap();
+		tx.send(counted()).unwrap();
+		drop(upgraded.send(counted()).unwrap_err());
+		drop(rx.recv());
+	});
+
+	check("cloned senders racing", 2, || {
+		let (tx, rx) = threadbeam::channel();
+		let tx2 = tx.clone();
+		tx.send(counted()).unwrap();
+		drop(tx2.send(counted()).unwrap_err());
+		drop(rx.recv());
+	});
+
+	#[cfg(feature = "broadcast")]
+	check("broadcast, one receiver dropped early", 2, || {
+		let (tx, rx) = threadbeam::channel();
+		let early = rx.clone();
+		let late = rx.clone();
+		drop(early);
+		tx.send(counted()).unwrap();
+		drop(rx.recv());
+		drop(late.recv());
+	});
+
+	#[cfg(feature = "broadcast")]
+	check("broadcast, never recv", 1, || {
+		let (tx, rx) = threadbeam::channel();
+		let rx2 = rx.clone();
+		tx.send(counted()).unwrap();
+		drop(rx);
+		drop(rx2);
+	});
+
+	check("both sides closed, then dropped", 1, || {
+		let (tx, rx) = threadbeam::channel();
+		tx.send_streaming(counted()).unwrap();
+		tx.close();
+		rx.close();
+		drop(tx);
+		drop(rx);
+	});
+
+	check("drained while the sending side is alive", 1, || {
+		let (tx, rx) = threadbeam::channel();
+		tx.send_streaming(counted()).unwrap();
+		assert!(rx.drain());
+		drop(tx);
+	});
+
+	check("pre-disconnected receiver", 0, || {
+		let rx = threadbeam::ThreadBeamRx::<Counted>::disconnected();
+		assert!(rx.recv().is_none());
+	});
+
+	check("pre-filled receiver, never recv", 1, || {
+		drop(threadbeam::ThreadBeamRx::ready(counted()));
+	});
+
+	#[cfg(not(feature = "safe"))]
+	check("try_channel, never recv", 1, || {
+		let (tx, rx) = threadbeam::try_channel().unwrap();
+		tx.send(counted()).unwrap();
+		drop(rx);
+	});
+
+	#[cfg(all(not(feature = "no_std"), not(feature = "safe")))]
+	check("reserved, recv side dropped before commit", 1, || {
+		let (tx, rx) = threadbeam::channel();
+		let writer = tx.reserve().unwrap();
+		drop(rx);
+		writer.write(counted());
+	});
+
+	check("send across threads", 1, || {
+		let (tx, rx) = threadbeam::channel();
+		let thread = std::thread::spawn(move || tx.send(counted()).unwrap());
+		drop(rx.recv());
+		thread.join().unwrap();
+	});
+
+	check("send across threads, never recv", 1, || {
+		let (tx, rx) = threadbeam::channel();
+		let thread = std::thread::spawn(move || tx.send(counted()).unwrap());
+		thread.join().unwrap();
+		drop(rx);
+	});
+
+	check("try_recv", 1, || {
+		let (tx, rx) = threadbeam::channel();
+		assert!(rx.try_recv().is_err());
+		tx.send(counted()).unwrap();
+		drop(rx.try_recv().unwrap());
+	});
+
+	#[cfg(not(feature = "no_std"))]
+	check("recv_timeout timed out, then dropped with a value", 1, || {
+		let (tx, rx) = threadbeam::channel();
+		assert!(rx.recv_timeout(std::time::Duration::from_millis(1)).is_err());
+		tx.send(counted()).unwrap();
+		drop(rx);
+	});
+
+	#[cfg(not(feature = "no_std"))]
+	check("send receipt, never recv", 1, || {
+		let (tx, rx) = threadbeam::channel();
+		let receipt = tx.send_with_receipt(counted()).unwrap();
+		drop(rx);
+		assert!(!receipt.wait_consumed());
+		drop(receipt);
+	});
+
+	#[cfg(all(feature = "pool", not(feature = "no_std")))]
+	check("pooled, reused and outliving the pool", 3, || {
+		let pool = threadbeam::ThreadBeamPool::new();
+		let (tx, rx) = pool.channel();
+		tx.send(counted()).unwrap();
+		drop(rx.recv());
+		let (tx, rx) = pool.channel();
+		tx.send(counted()).unwrap();
+		drop(rx);
+		let (tx, rx) = pool.channel();
+		drop(pool);
+		tx.send(counted()).unwrap();
+		drop(rx);
+	});
+
+	#[cfg(not(feature = "no_std"))]
+	check("spawn", 1, || {
+		let (value, thread) = threadbeam::spawn(|tx| tx.send(counted()).unwrap());
+		drop(value);
+		thread.join().unwrap();
+	});
+}
@@ -0,0 +1,114 @@
+//! Checks that timeouts are measured against the wall clock, and that values still get through, when the sending side is starved of CPU.
+//!
+//! Every thread is pinned to the same core, the sending side gets the lowest priority, and another thread keeps that core busy, so the
+//! sending side barely runs until the busy thread is done. This takes a few seconds and depends on the OS scheduler, so it is only
+//! built with the `slow-tests` feature:
+//!
+//! ```sh
+//! cargo test --features slow-tests --test scheduling
+//! ```
+//!
+//! Only Linux is supported, through `sched_setaffinity` and `setpriority`. Where those calls fail, e.g. in a container that doesn't
+//! allow them, the tests print why and pass without checking anything. Lowering a thread's priority needs no privileges, so they never
+//! need to run as root. The `no_std` backend has no wall-clock timeouts, so there is nothing to check there.
+
+#![cfg(all(target_os = "linux", not(feature = "no_std")))]
+
+use std::time::{Duration, Instant};
+use threadbeam::RecvTimeoutError;
+
+// `cpu_set_t` is a 1024-bit mask
+type CpuSet = [u64; 16];
+
+const PRIO_PROCESS: i32 = 0;
+
+// Both are in the C library that std already links against
+extern "C" {
+	fn sched_setaffinity(pid: i32, cpusetsize: usize, mask: *const CpuSet) -> i32;
+	fn setpriority(which: i32, who: u32, prio: i32) -> i32;
+}
+
+// Pins the calling thread, and every thread it spawns from now on, to the first core
+fn pin_to_one_core() -> Result<(), std::io::Error> {
+	let mut mask: CpuSet = [0; 16];
+	mask[0] = 1;
+	match unsafe { sched_setaffinity(0, core::mem::size_of::<CpuSet>(), &mask) } {
+		0 => Ok(()),
+		_ => Err(std::io::Error::last_os_error()),
+	}
+}
+
+// On Linux, a `who` of 0 sets the niceness of the calling thread alone
+fn lowest_priority() -> Result<(), std::io::Error> {
+	match unsafe { setpriority(PRIO_PROCESS, 0, 19) } {
+		0 => Ok(()),
+		_ => Err(std::io::Error::last_os_error()),
+	}
+}
+
+fn busy_for(duration: Duration) {
+	let start = Instant::now();
+	while start.elapsed() < duration {
+		core::hint::spin_loop();
+	}
+}
+
+// Runs `test` with a sending side that is starved by a thread keeping its only core busy for `starved_for`
+fn with_starved_sender(starved_for: Duration, test: impl FnOnce(threadbeam::ThreadBeamRx<u32>)) {
+	if let Err(err) = pin_to_one_core() {
+		eprintln!("skipping: can't pin threads to one core ({err})");
+		return;
+	}
+
+	let (tx, rx) = threadbeam::channel();
+	let (ready_tx, ready_rx) = threadbeam::channel();
+	let sender = std::thread::spawn(move || {
+		let lowered = lowest_priority();
+		ready_tx.send(lowered.is_ok()).unwrap();
+		// The sending side has work of its own to do first, so it only gets to send once it has had enough of a core it is starved of
+		for _ in 0..20_000_000 {
+			core::hint::spin_loop();
+		}
+		tx.send(42).unwrap();
+	});
+	let hog = std::thread::spawn(move || busy_for(starved_for));
+
+	if ready_rx.recv() != Some(true) {
+		eprintln!("skipping: can't lower the sending side's priority");
+		hog.join().unwrap();
+		sender.join().unwrap();
+		return;
+	}
+
+	test(rx);
+
+	hog.join().unwrap();
+	sender.join().unwrap();
+}
+
+#[test]
+fn test_recv_timeout_under_starvation() {
+	with_starved_sender(Duration::from_secs(2), |rx| {
+		// The timeout runs on the wall clock, not on how much CPU anyone got, so it elapses on time even though the core is saturated
+		let start = Instant::now();
+		assert_eq!(rx.recv_timeout(Duration::from_millis(100)), Err(RecvTimeoutError::Timeout));
+		let elapsed = start.elapsed();
+		assert!(elapsed >= Duration::from_millis(100), "{elapsed:?}");
+		assert!(elapsed < Duration::from_secs(1), "{elapsed:?}");
+
+		// And the value still gets through once the sending side gets to run
+		assert_eq!(rx.recv(), Some(42));
+	});
+}
+
+#[test]
+fn test_recv_deadline_under_starvation() {
+	with_starved_sender(Duration::from_secs(1), |rx| {
+		let deadline = Instant::now() + Duration::from_millis(100);
+		assert_eq!(rx.recv_deadline(deadline), Err(RecvTimeoutError::Timeout));
+		let late = Instant::now().saturating_duration_since(deadline);
+		assert!(late < Duration::from_secs(1), "woke {late:?} after the deadline");
+
+		assert_eq!(rx.recv(), Some(42));
+	});
+}
@@ -0,0 +1,121 @@
+//! Soak test that creates and tears down millions of thread beams in random patterns and checks that no allocation is leaked.
+//!
+//! This takes a few seconds in release mode and much longer in debug mode, so it is only built with the `slow-tests` feature:
+//!
+//! ```sh
+//! cargo test --release --features slow-tests --test soak
+//! ```
+//!
+//! The number of channels created per thread can be changed with the `THREADBEAM_SOAK_ITERATIONS` environment variable.
+
+use std::{
+	alloc::{GlobalAlloc, Layout, System},
+	sync::atomic::{AtomicIsize, Ordering},
+};
+
+struct CountingAllocator;
+
+static LIVE_ALLOCATIONS: AtomicIsize = AtomicIsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		let ptr = System.alloc(layout);
+		if !ptr.is_null() {
+			LIVE_ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+		}
+		ptr
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		LIVE_ALLOCATIONS.fetch_sub(1, Ordering::SeqCst);
+		System.dealloc(ptr, layout)
+	}
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const THREADS: usize = 8;
+
+struct XorShift(u64);
+impl XorShift {
+	fn next(&mut self) -> u64 {
+		self.0 ^= self.0 << 13;
+		self.0 ^= self.0 >> 7;
+		self.0 ^= self.0 << 17;
+		self.0
+	}
+}
+
+fn soak(seed: u64, iterations: usize) {
+	let mut rng = XorShift(seed);
+
+	// A helper thread to hand senders and receivers to, so that some beams are torn down across threads
+	let (helper_tx, helper_rx) = std::sync::mpsc::channel::<Box<dyn FnOnce() + Send>>();
+	let helper = std::thread::spawn(move || {
+		for f in helper_rx {
+			f();
+		}
+	});
+
+	for _ in 0..iterations {
+		let (tx, rx) = threadbeam::channel::<Box<u64>>();
+		let value = Box::new(rng.next());
+		match rng.next() % 6 {
+			0 => {
+				tx.send(value).unwrap();
+				assert!(rx.recv().is_some());
+			}
+			1 => {
+				drop(tx);
+				assert!(rx.recv().is_none());
+			}
+			2 => {
+				tx.send(value).unwrap();
+				drop(rx);
+			}
+			3 => {
+				drop(rx);
+				assert!(tx.send(value).is_err());
+			}
+			4 => {
+				helper_tx.send(Box::new(move || tx.send(value).unwrap())).unwrap();
+				assert!(rx.recv().is_some());
+			}
+			_ => {
+				helper_tx.send(Box::new(move || drop(rx))).unwrap();
+				let _ = tx.send(value);
+			}
+		}
+	}
+
+	drop(helper_tx);
+	helper.join().unwrap();
+}
+
+fn run(iterations: usize) {
+	let threads = (0..THREADS)
+		.map(|i| std::thread::spawn(move || soak(0x9E37_79B9_7F4A_7C15 ^ (i as u64 + 1), iterations)))
+		.collect::<Vec<_>>();
+
+	for thread in threads {
+		thread.join().unwrap();
+	}
+}
+
+fn main() {
+	let iterations = std::env::var("THREADBEAM_SOAK_ITERATIONS")
+		.ok()
+		.and_then(|iterations| iterations.parse().ok())
+		.unwrap_or(250_000);
+
+	// Warm up any lazily allocated runtime state (thread handles, etc.) so it doesn't count as a leak
+	run(1);
+
+	let baseline = LIVE_ALLOCATIONS.load(Ordering::SeqCst);
+	run(iterations);
+	let live = LIVE_ALLOCATIONS.load(Ordering::SeqCst);
+
+	assert_eq!(live, baseline, "{} allocations leaked", live - baseline);
+	println!("soak: {} channels created and destroyed, no leaks", iterations * THREADS);
+}
@@ -0,0 +1,22 @@
+//! Checks that the panics threadbeam raises on misuse point at the caller rather than inside threadbeam.
+//!
+//! This installs a panic hook, so it's kept to its own test binary.
+
+#[cfg(not(feature = "no_std"))]
+#[test]
+fn test_panic_location() {
+	use std::sync::{Arc, Mutex};
+
+	let location = Arc::new(Mutex::new(None));
+	let hook_location = location.clone();
+	std::panic::set_hook(Box::new(move |info| {
+		*hook_location.lock().unwrap() = info.location().map(|location| (location.file().to_owned(), location.line()));
+	}));
+
+	let expected = line!() + 1;
+	let result = std::panic::catch_unwind(|| threadbeam::recv_any(Vec::<threadbeam::ThreadBeamRx<u32>>::new()));
+	let _ = std::panic::take_hook();
+
+	assert!(result.is_err());
+	assert_eq!(*location.lock().unwrap(), Some((file!().to_owned(), expected)));
+}